@@ -0,0 +1,154 @@
+// Browser functions used by the game. See src/storage.rs, src/haptics.rs
+// and src/page.rs.
+"use strict";
+
+const pong_storage_prefix = "pong.";
+
+function pong_string(ptr, len) {
+    return new TextDecoder().decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+}
+
+function pong_storage_len(key_ptr, key_len) {
+    const value = localStorage.getItem(pong_storage_prefix + pong_string(key_ptr, key_len));
+    return value === null ? -1 : new TextEncoder().encode(value).length;
+}
+
+function pong_storage_read(key_ptr, key_len, buf_ptr, buf_len) {
+    const value = localStorage.getItem(pong_storage_prefix + pong_string(key_ptr, key_len));
+    const bytes = new TextEncoder().encode(value === null ? "" : value);
+    new Uint8Array(wasm_memory.buffer, buf_ptr, buf_len).set(bytes.subarray(0, buf_len));
+}
+
+function pong_storage_write(key_ptr, key_len, data_ptr, data_len) {
+    try {
+        localStorage.setItem(
+            pong_storage_prefix + pong_string(key_ptr, key_len),
+            pong_string(data_ptr, data_len),
+        );
+    } catch (e) {
+        console.error("Failed to save data", e);
+    }
+}
+
+function pong_storage_remove(key_ptr, key_len) {
+    localStorage.removeItem(pong_storage_prefix + pong_string(key_ptr, key_len));
+}
+
+// Keys of the entries of the game, one per line.
+function pong_storage_keys() {
+    const keys = [];
+    for (let i = 0; i < localStorage.length; i++) {
+        const key = localStorage.key(i);
+        if (key.startsWith(pong_storage_prefix)) {
+            keys.push(key.slice(pong_storage_prefix.length));
+        }
+    }
+    return new TextEncoder().encode(keys.join("\n"));
+}
+
+function pong_storage_keys_len() {
+    return pong_storage_keys().length;
+}
+
+function pong_storage_keys_read(buf_ptr, buf_len) {
+    const bytes = pong_storage_keys();
+    new Uint8Array(wasm_memory.buffer, buf_ptr, buf_len).set(bytes.subarray(0, buf_len));
+}
+
+function pong_download(name_ptr, name_len, data_ptr, data_len) {
+    const data = new Uint8Array(wasm_memory.buffer, data_ptr, data_len).slice();
+    const url = URL.createObjectURL(new Blob([data]));
+    const link = document.createElement("a");
+    link.href = url;
+    link.download = pong_string(name_ptr, name_len);
+    link.click();
+    URL.revokeObjectURL(url);
+}
+
+// Contents of the file picked to import, until the game takes them.
+let pong_uploaded = null;
+
+function pong_upload() {
+    const input = document.createElement("input");
+    input.type = "file";
+    input.addEventListener("change", async function () {
+        if (input.files.length > 0) {
+            pong_uploaded = new TextEncoder().encode(await input.files[0].text());
+        }
+    });
+    input.click();
+}
+
+function pong_upload_len() {
+    return pong_uploaded === null ? -1 : pong_uploaded.length;
+}
+
+function pong_upload_read(buf_ptr, buf_len) {
+    new Uint8Array(wasm_memory.buffer, buf_ptr, buf_len).set(pong_uploaded.subarray(0, buf_len));
+    pong_uploaded = null;
+}
+
+function pong_vibrate(millis) {
+    // Browsers without the Vibration API, like the desktop ones, ignore it.
+    if (navigator.vibrate) {
+        navigator.vibrate(millis);
+    }
+}
+
+// Whether the page has been hidden since the game last asked.
+let pong_hidden = false;
+
+document.addEventListener("visibilitychange", function () {
+    if (document.hidden) {
+        pong_hidden = true;
+    }
+});
+
+function pong_take_hidden() {
+    const hidden = pong_hidden;
+    pong_hidden = false;
+    return hidden ? 1 : 0;
+}
+
+// Whether a new version of the game has been cached, to be played after
+// reloading the page. The service worker is written by
+// `pong --write-site`.
+let pong_update = false;
+
+if ("serviceWorker" in navigator) {
+    navigator.serviceWorker.register("sw.js").then(function (registration) {
+        registration.addEventListener("updatefound", function () {
+            const worker = registration.installing;
+            worker.addEventListener("statechange", function () {
+                // The first worker installed is not an update.
+                if (worker.state === "installed" && navigator.serviceWorker.controller) {
+                    pong_update = true;
+                }
+            });
+        });
+    });
+}
+
+function pong_update_available() {
+    return pong_update ? 1 : 0;
+}
+
+miniquad_add_plugin({
+    register_plugin: function (importObject) {
+        importObject.env.pong_storage_len = pong_storage_len;
+        importObject.env.pong_storage_read = pong_storage_read;
+        importObject.env.pong_storage_write = pong_storage_write;
+        importObject.env.pong_storage_remove = pong_storage_remove;
+        importObject.env.pong_storage_keys_len = pong_storage_keys_len;
+        importObject.env.pong_storage_keys_read = pong_storage_keys_read;
+        importObject.env.pong_download = pong_download;
+        importObject.env.pong_upload = pong_upload;
+        importObject.env.pong_upload_len = pong_upload_len;
+        importObject.env.pong_upload_read = pong_upload_read;
+        importObject.env.pong_vibrate = pong_vibrate;
+        importObject.env.pong_take_hidden = pong_take_hidden;
+        importObject.env.pong_update_available = pong_update_available;
+    },
+    name: "pong",
+    version: "0.1.0",
+});
@@ -0,0 +1,92 @@
+// This shader is based on https://www.shadertoy.com/view/XtlSD7
+
+#version 100
+
+precision lowp float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+uniform vec4 _Time;
+// 1 lets the scanlines roll and 0 keeps them still.
+uniform float Motion;
+// Strength of every part of the effect, from 0 (none) to 1 (full).
+uniform float Curvature;
+uniform float Scanlines;
+uniform float Vignette;
+// Retro palettes, a row of four colors each.
+uniform sampler2D Lut;
+// Row of the palette in Lut the colors are reduced to, counting from 1, or
+// 0 to keep the colors.
+uniform float Palette;
+
+// How much the image is collapsed into a bright horizontal line, from 0 (not
+// at all) to 1 (fully).
+uniform float Collapse;
+// Pulses fading after the events of a match, from 0 (none) to 1 (full):
+// splitting of the colors and darkening of the corners.
+uniform float Aberration;
+uniform float VignettePulse;
+
+const float PALETTES = 3.0;
+
+vec2 crt_curve_uv(vec2 uv) {
+    uv = uv * 2.0 - 1.0;
+    vec2 offset = abs(uv.yx) / vec2(6.0, 4.0);
+    uv = uv + uv * offset * offset * Curvature;
+    uv = uv * 0.5 + 0.5;
+    return uv;
+}
+
+void reduce_to_palette(inout vec3 color) {
+    if (Palette < 0.5) {
+        return;
+    }
+    float row = (Palette - 0.5) / PALETTES;
+    vec3 nearest = color;
+    float nearest_distance = 2.0;
+    for (int i = 0; i < 4; i++) {
+        vec3 candidate = texture2D(Lut, vec2((float(i) + 0.5) / 4.0, row)).rgb;
+        float candidate_distance = distance(color, candidate);
+        if (candidate_distance < nearest_distance) {
+            nearest = candidate;
+            nearest_distance = candidate_distance;
+        }
+    }
+    color = nearest;
+}
+
+void draw_vignette(inout vec3 color, vec2 uv) {
+    float vignette = uv.x * uv.y * (1.0 - uv.x) * (1.0 - uv.y);
+    vignette = clamp(pow(16.0 * vignette, 0.3), 0.0, 1.0);
+    color *= mix(1.0, vignette, Vignette) * mix(1.0, vignette * vignette, VignettePulse);
+}
+
+void draw_scanline(inout vec3 color, vec2 uv) {
+    float scanline = clamp(0.95 + 0.05 * cos(3.14 * (uv.y + 0.008 * _Time.x * Motion) * 240.0 * 1.0), 0.0, 1.0);
+    float grille = 0.85 + 0.15 * clamp(1.5 * cos(3.14 * uv.x * 640.0 * 1.0), 0.0, 1.0);
+    color *= mix(1.0, scanline * grille * 1.2, Scanlines);
+}
+
+void main() {
+    vec2 image_uv = uv;
+    image_uv.y = (uv.y - 0.5) / max(1.0 - Collapse, 0.01) + 0.5;
+    vec3 frag_color = texture2D(Texture, image_uv).rgb * color.rgb;
+    vec2 split = vec2(0.006 * Aberration, 0.0);
+    frag_color.r = texture2D(Texture, image_uv + split).r * color.r;
+    frag_color.b = texture2D(Texture, image_uv - split).b * color.b;
+    if (image_uv.y < 0.0 || image_uv.y > 1.0) {
+        frag_color = vec3(0.0, 0.0, 0.0);
+    } else {
+        frag_color = mix(frag_color, vec3(1.0, 1.0, 1.0), Collapse * Collapse);
+    }
+    reduce_to_palette(frag_color);
+    vec2 crt_uv = crt_curve_uv(uv);
+    if (crt_uv.x < 0.0 || crt_uv.x > 1.0 || crt_uv.y < 0.0 || crt_uv.y > 1.0) {
+        frag_color = vec3(0.0, 0.0, 0.0);
+    }
+    draw_vignette(frag_color, crt_uv);
+    draw_scanline(frag_color, uv);
+    gl_FragColor = vec4(frag_color, 1.0);
+}
@@ -0,0 +1,67 @@
+/* C API of the pong simulation. See src/ffi.rs. */
+#ifndef PONG_H
+#define PONG_H
+
+#include <stdint.h>
+
+#define PONG_LEFT 0
+#define PONG_RIGHT 1
+
+#define PONG_STOP 0
+#define PONG_UP 1
+#define PONG_DOWN 2
+
+#define PONG_WALL_BOUNCE (1u << 0)
+#define PONG_RACKET_BOUNCE (1u << 1)
+#define PONG_POINT_LEFT (1u << 2)
+#define PONG_POINT_RIGHT (1u << 3)
+#define PONG_MATCH_POINT (1u << 4)
+
+/* Size of the court used by pong_new. */
+#define PONG_COURT_WIDTH 800.0f
+#define PONG_COURT_HEIGHT 600.0f
+
+typedef struct Pong Pong;
+
+typedef struct {
+	uint64_t tick;
+	int32_t left_score;
+	int32_t right_score;
+	/* 0 while playing, 1 if the left player won, 2 if the right one did. */
+	int32_t winner;
+	float left_y;
+	float right_y;
+	float ball_x;
+	float ball_y;
+	float ball_dir_x;
+	float ball_dir_y;
+	float ball_speed;
+} PongState;
+
+/* What a bot sees of the match. Mirrored so it always defends the left goal. */
+typedef struct {
+	float court_width;
+	float court_height;
+	float racket_y;
+	float opponent_y;
+	float ball_x;
+	float ball_y;
+	float ball_dir_x;
+	float ball_dir_y;
+	float ball_speed;
+	int32_t score;
+	int32_t opponent_score;
+	/* Second racket of the opponent in co-op matches, else opponent_y. */
+	float second_opponent_y;
+} PongObservation;
+
+Pong *pong_new(uint64_t seed);
+/* Returns NULL if the rackets and the ball don't fit in the court. */
+Pong *pong_new_court(uint64_t seed, float width, float height);
+void pong_free(Pong *pong);
+void pong_reset(Pong *pong, uint64_t seed);
+uint32_t pong_step(Pong *pong, int32_t left, int32_t right);
+void pong_state(const Pong *pong, PongState *state);
+void pong_observe(const Pong *pong, int32_t side, PongObservation *obs);
+
+#endif
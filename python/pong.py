@@ -0,0 +1,132 @@
+"""Python bindings for the pong simulation.
+
+The bindings load the shared library built with:
+
+    cargo rustc --lib --release --features ffi --crate-type cdylib
+
+Set PONG_LIB to its path if it is not in target/release.
+
+Example:
+
+    env = PongEnv(seed=1)
+    while not env.state.winner:
+        obs = env.observe(LEFT)
+        action = UP if obs.ball_y < obs.racket_y else DOWN
+        env.step(action, STOP)
+    print(env.state.left_score, env.state.right_score)
+"""
+import ctypes
+import os
+import sys
+
+LEFT, RIGHT = 0, 1
+STOP, UP, DOWN = 0, 1, 2
+WALL_BOUNCE, RACKET_BOUNCE, POINT_LEFT, POINT_RIGHT, MATCH_POINT = 1, 2, 4, 8, 16
+
+
+class State(ctypes.Structure):
+    _fields_ = [
+        ("tick", ctypes.c_uint64),
+        ("left_score", ctypes.c_int32),
+        ("right_score", ctypes.c_int32),
+        ("winner", ctypes.c_int32),
+        ("left_y", ctypes.c_float),
+        ("right_y", ctypes.c_float),
+        ("ball_x", ctypes.c_float),
+        ("ball_y", ctypes.c_float),
+        ("ball_dir_x", ctypes.c_float),
+        ("ball_dir_y", ctypes.c_float),
+        ("ball_speed", ctypes.c_float),
+    ]
+
+
+class Observation(ctypes.Structure):
+    _fields_ = [
+        ("court_width", ctypes.c_float),
+        ("court_height", ctypes.c_float),
+        ("racket_y", ctypes.c_float),
+        ("opponent_y", ctypes.c_float),
+        ("ball_x", ctypes.c_float),
+        ("ball_y", ctypes.c_float),
+        ("ball_dir_x", ctypes.c_float),
+        ("ball_dir_y", ctypes.c_float),
+        ("ball_speed", ctypes.c_float),
+        ("score", ctypes.c_int32),
+        ("opponent_score", ctypes.c_int32),
+        ("second_opponent_y", ctypes.c_float),
+    ]
+
+
+def _load():
+    path = os.environ.get("PONG_LIB")
+    if path is None:
+        name = {"win32": "pong.dll", "darwin": "libpong.dylib"}.get(
+            sys.platform, "libpong.so"
+        )
+        root = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+        path = os.path.join(root, "target", "release", name)
+    lib = ctypes.CDLL(path)
+    lib.pong_new.argtypes = [ctypes.c_uint64]
+    lib.pong_new.restype = ctypes.c_void_p
+    lib.pong_new_court.argtypes = [ctypes.c_uint64, ctypes.c_float, ctypes.c_float]
+    lib.pong_new_court.restype = ctypes.c_void_p
+    lib.pong_free.argtypes = [ctypes.c_void_p]
+    lib.pong_free.restype = None
+    lib.pong_reset.argtypes = [ctypes.c_void_p, ctypes.c_uint64]
+    lib.pong_reset.restype = None
+    lib.pong_step.argtypes = [ctypes.c_void_p, ctypes.c_int32, ctypes.c_int32]
+    lib.pong_step.restype = ctypes.c_uint32
+    lib.pong_state.argtypes = [ctypes.c_void_p, ctypes.POINTER(State)]
+    lib.pong_state.restype = None
+    lib.pong_observe.argtypes = [
+        ctypes.c_void_p,
+        ctypes.c_int32,
+        ctypes.POINTER(Observation),
+    ]
+    lib.pong_observe.restype = None
+    return lib
+
+
+_lib = _load()
+
+
+class PongEnv:
+    """A match driven step by step, optionally on a court of a custom size."""
+
+    def __init__(self, seed=0, court=None):
+        self._pong = None
+        if court is None:
+            self._pong = _lib.pong_new(seed)
+        else:
+            self._pong = _lib.pong_new_court(seed, *court)
+            if self._pong is None:
+                raise ValueError(f"court too small: {court}")
+
+    def __del__(self):
+        self.close()
+
+    def close(self):
+        if self._pong is not None:
+            _lib.pong_free(self._pong)
+            self._pong = None
+
+    def reset(self, seed):
+        """Starts a new match."""
+        _lib.pong_reset(self._pong, seed)
+
+    def step(self, left, right):
+        """Advances the match by one step and returns the event flags."""
+        return _lib.pong_step(self._pong, left, right)
+
+    @property
+    def state(self):
+        """Returns a snapshot of the match."""
+        state = State()
+        _lib.pong_state(self._pong, ctypes.byref(state))
+        return state
+
+    def observe(self, side):
+        """Returns what a bot playing on `side` sees of the match."""
+        obs = Observation()
+        _lib.pong_observe(self._pong, side, ctypes.byref(obs))
+        return obs
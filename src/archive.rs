@@ -0,0 +1,95 @@
+//! Every entry saved by the game in a single file, to move it to another
+//! machine or between the native and web versions.
+//!
+//! The archive starts with a `pong-data VERSION` line, followed by every
+//! entry as an `entry DIR LEN NAME` line and its `LEN` bytes of contents.
+//! The name takes the rest of the line, so it can have spaces:
+//!
+//! ```text
+//! pong-data 2
+//! entry config 24 settings.txt
+//! version 1
+//! crt on
+//! ...
+//! ```
+//!
+//! `DIR` is the kind of data of the entry, which tells where it is saved.
+use std::fmt::Write;
+
+/// Name of the file the archive is exported to and imported from.
+pub const NAME: &str = "pong-data.txt";
+
+const VERSION: u32 = 2;
+
+/// Entry saved by the game.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entry<'a> {
+    /// Kind of data, like `config` or `replays`.
+    pub dir: &'a str,
+    pub name: &'a str,
+    pub contents: &'a str,
+}
+
+/// Returns the archive of `entries`, or an error if a name or a kind of
+/// data cannot be written.
+pub fn pack(entries: &[Entry]) -> Result<String, String> {
+    let mut archive = format!("pong-data {VERSION}\n");
+    for entry in entries {
+        if entry.dir.is_empty() || entry.dir.contains([' ', '\n']) {
+            return Err(format!("invalid folder: {}", entry.dir));
+        }
+        if !is_valid_name(entry.name) {
+            return Err(format!("invalid name: {}", entry.name));
+        }
+        writeln!(
+            archive,
+            "entry {} {} {}",
+            entry.dir,
+            entry.contents.len(),
+            entry.name
+        )
+        .expect("format entry");
+        archive.push_str(entry.contents);
+    }
+    Ok(archive)
+}
+
+/// Returns the entries of `archive`, or an error if any of them is broken,
+/// so broken archives change nothing.
+pub fn unpack(archive: &str) -> Result<Vec<Entry<'_>>, String> {
+    let mut rest = archive
+        .strip_prefix(&format!("pong-data {VERSION}\n"))
+        .ok_or("not a data archive of this version of the game")?;
+    let mut entries = Vec::new();
+    while !rest.is_empty() {
+        let (header, after) = rest.split_once('\n').ok_or("truncated entry")?;
+        let mut fields = header.splitn(4, ' ');
+        let (Some("entry"), Some(dir), Some(len), Some(name)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(format!("invalid entry: {header}"));
+        };
+        // Names come from another machine, so they must not reach outside
+        // the folders of the game.
+        if !is_valid_name(name) {
+            return Err(format!("invalid name: {name}"));
+        }
+        let len: usize = len.parse().map_err(|_| format!("invalid length: {len}"))?;
+        let contents = after
+            .get(..len)
+            .ok_or_else(|| format!("truncated entry: {name}"))?;
+        entries.push(Entry {
+            dir,
+            name,
+            contents,
+        });
+        rest = &after[len..];
+    }
+    Ok(entries)
+}
+
+/// Returns whether `name` can be the name of an entry: a file of the
+/// folder of its kind of data, which fits in the line of its header.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\', '\n'])
+}
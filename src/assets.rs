@@ -0,0 +1,274 @@
+//! Game assets.
+//!
+//! Every asset is embedded in the binary. Any of them can be overridden by a
+//! file with the same relative path inside the directory given with
+//! `--assets`.
+use std::{io::ErrorKind, path::PathBuf};
+
+use macroquad::{
+    audio::{load_sound_from_bytes, Sound},
+    miniquad::conf::Icon,
+    prelude::*,
+};
+
+use crate::{
+    audio::{Bus, Mixer, Pool, Sounds, CHIME_PITCH, EDGE_PITCH, PITCHES},
+    draw::{self, FOREGROUND_COLOR},
+};
+
+struct Asset {
+    path: &'static str,
+    bytes: &'static [u8],
+}
+
+macro_rules! asset {
+    ($path:literal) => {
+        Asset {
+            path: $path,
+            bytes: include_bytes!(concat!("../assets/", $path)),
+        }
+    };
+}
+
+const WALL_WAV: Asset = asset!("sounds/wall.wav");
+const RACKET_WAV: Asset = asset!("sounds/racket.wav");
+const POINT_WAV: Asset = asset!("sounds/point.wav");
+const CRT_VERT: Asset = asset!("shaders/crt.vert");
+const CRT_FRAG: Asset = asset!("shaders/crt.frag");
+const ICON_16: Asset = asset!("images/icon-16.png");
+const ICON_32: Asset = asset!("images/icon-32.png");
+const ICON_64: Asset = asset!("images/icon-64.png");
+
+const ASSET_COUNT: usize = 5;
+
+/// Most samples in the pool of a sound. Pools have the embedded sample and
+/// the ones overriding it, named like `sounds/wall-2.wav`.
+const MAX_POOL_SAMPLES: usize = 4;
+
+pub struct Assets {
+    pub sounds: Sounds,
+    pub crt_material: Material,
+    /// Directory the assets can be overridden from.
+    pub overrides: Option<PathBuf>,
+}
+
+impl Assets {
+    /// Loads all the assets while showing a loading screen.
+    pub async fn load(overrides: Option<PathBuf>) -> Self {
+        let mut loader = Loader {
+            overrides,
+            loaded: 0,
+        };
+
+        let [wall] = loader.pools(&WALL_WAV, [1.]).await;
+        let [racket, racket_edge] = loader.pools(&RACKET_WAV, [1., EDGE_PITCH]).await;
+        let [point, chime] = loader.sounds(&POINT_WAV, [1., CHIME_PITCH]).await;
+        let sounds = Sounds {
+            wall,
+            racket,
+            racket_edge,
+            point,
+            chime,
+            mixer: Mixer::new(1., [1.; Bus::ALL.len()]),
+        };
+
+        let vertex = loader.text(&CRT_VERT).await;
+        let fragment = loader.text(&CRT_FRAG).await;
+        let crt_material = load_material(
+            ShaderSource::Glsl {
+                vertex: &vertex,
+                fragment: &fragment,
+            },
+            MaterialParams {
+                uniforms: [
+                    "Motion",
+                    "Curvature",
+                    "Scanlines",
+                    "Vignette",
+                    "Palette",
+                    "Collapse",
+                    "Aberration",
+                    "VignettePulse",
+                ]
+                .iter()
+                .map(|&name| (name.to_owned(), UniformType::Float1))
+                .collect(),
+                textures: vec!["Lut".to_owned()],
+                ..Default::default()
+            },
+        )
+        .expect("load CRT shader");
+        crt_material.set_texture("Lut", draw::palette_lut());
+
+        Self {
+            sounds,
+            crt_material,
+            overrides: loader.overrides,
+        }
+    }
+}
+
+/// Returns the icon of the window. It is needed before the window is
+/// created, so it cannot be overridden.
+pub fn icon() -> Icon {
+    Icon {
+        small: icon_pixels(&ICON_16),
+        medium: icon_pixels(&ICON_32),
+        big: icon_pixels(&ICON_64),
+    }
+}
+
+/// Returns the largest icon of the window.
+#[cfg(not(target_family = "wasm"))]
+pub fn big_icon() -> Image {
+    Image::from_file_with_format(ICON_64.bytes, Some(ImageFormat::Png))
+        .unwrap_or_else(|err| panic!("load image {}: {err}", ICON_64.path))
+}
+
+fn icon_pixels<const N: usize>(asset: &Asset) -> [u8; N] {
+    let image = Image::from_file_with_format(asset.bytes, Some(ImageFormat::Png))
+        .unwrap_or_else(|err| panic!("load image {}: {err}", asset.path));
+    image
+        .bytes
+        .try_into()
+        .unwrap_or_else(|_| panic!("load image {}: wrong size", asset.path))
+}
+
+struct Loader {
+    overrides: Option<PathBuf>,
+    loaded: usize,
+}
+
+impl Loader {
+    /// Loads `asset` at every pitch of `pitches`, reading it once. Other
+    /// formats than WAV are only loaded at their own pitch.
+    async fn sounds<const N: usize>(&mut self, asset: &Asset, pitches: [f32; N]) -> [Sound; N] {
+        let bytes = self.bytes(asset).await;
+        let mut sounds = Vec::with_capacity(N);
+        for pitch in pitches {
+            let pitched = (pitch != 1.).then(|| with_pitch(&bytes, pitch)).flatten();
+            let sound = load_sound_from_bytes(pitched.as_deref().unwrap_or(&bytes))
+                .await
+                .unwrap_or_else(|err| panic!("load sound {}: {err}", asset.path));
+            sounds.push(sound);
+        }
+        sounds
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("one sound per pitch"))
+    }
+
+    /// Loads a pool of the samples of `asset` for every pitch of
+    /// `base_pitches`, with each sample at every pitch of [`PITCHES`] raised
+    /// by it. The samples are only read once for all the pools.
+    async fn pools<const N: usize>(&mut self, asset: &Asset, base_pitches: [f32; N]) -> [Pool; N] {
+        let mut samples = vec![(asset.path.to_owned(), self.bytes(asset).await)];
+        for n in 2..=MAX_POOL_SAMPLES {
+            let path = asset.path.replace(".wav", &format!("-{n}.wav"));
+            let Some(bytes) = self.read_override(&path) else {
+                break;
+            };
+            samples.push((path, bytes));
+        }
+        let mut pools = Vec::with_capacity(N);
+        for base_pitch in base_pitches {
+            let mut sounds = Vec::with_capacity(samples.len());
+            for (path, bytes) in &samples {
+                sounds.push(pitched(path, bytes, base_pitch).await);
+            }
+            pools.push(Pool::new(sounds));
+        }
+        pools
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("one pool per pitch"))
+    }
+
+    async fn text(&mut self, asset: &Asset) -> String {
+        let bytes = self.bytes(asset).await;
+        String::from_utf8(bytes).unwrap_or_else(|err| panic!("load text {}: {err}", asset.path))
+    }
+
+    /// Returns the contents of `asset` and updates the loading screen.
+    async fn bytes(&mut self, asset: &Asset) -> Vec<u8> {
+        let bytes = self
+            .read_override(asset.path)
+            .unwrap_or_else(|| asset.bytes.to_vec());
+
+        self.loaded += 1;
+        draw_progress(self.loaded as f32 / ASSET_COUNT as f32);
+        next_frame().await;
+
+        bytes
+    }
+
+    fn read_override(&self, path: &str) -> Option<Vec<u8>> {
+        let path = self.overrides.as_ref()?.join(path);
+        match std::fs::read(&path) {
+            Ok(bytes) => Some(bytes),
+            Err(err) if matches!(err.kind(), ErrorKind::NotFound | ErrorKind::Unsupported) => None,
+            Err(err) => panic!("read asset override {}: {err}", path.display()),
+        }
+    }
+}
+
+/// Loads the WAV file at `path` with `bytes` at every pitch of [`PITCHES`]
+/// raised by `base_pitch`. Other formats are only loaded at their own pitch.
+async fn pitched(path: &str, bytes: &[u8], base_pitch: f32) -> Vec<Sound> {
+    let mut sounds = Vec::with_capacity(PITCHES.len());
+    for pitch in PITCHES {
+        let pitch = pitch * base_pitch;
+        let bytes = if pitch == 1. {
+            bytes.to_vec()
+        } else if let Some(bytes) = with_pitch(bytes, pitch) {
+            bytes
+        } else if sounds.is_empty() {
+            bytes.to_vec()
+        } else {
+            break;
+        };
+        let sound = load_sound_from_bytes(&bytes)
+            .await
+            .unwrap_or_else(|err| panic!("load sound {path}: {err}"));
+        sounds.push(sound);
+    }
+    sounds
+}
+
+/// Returns the WAV file `wav` played `pitch` times faster, or `None` if it is
+/// not a WAV file. The audio backends resample the sounds to the rate of the
+/// output, so changing the sample rate of the file is enough.
+fn with_pitch(wav: &[u8], pitch: f32) -> Option<Vec<u8>> {
+    if wav.get(0..4)? != b"RIFF" || wav.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let read_u32 = |pos: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(wav.get(pos..pos + 4)?.try_into().ok()?))
+    };
+    let mut pos = 12;
+    loop {
+        let id = wav.get(pos..pos + 4)?;
+        let len = read_u32(pos + 4)? as usize;
+        if id == b"fmt " {
+            break;
+        }
+        // Chunks are padded to an even length.
+        pos += 8 + len + len % 2;
+    }
+    let rate_pos = pos + 12;
+    let rate = (read_u32(rate_pos)? as f32 * pitch).round() as u32;
+    let block_align = u16::from_le_bytes(wav.get(pos + 20..pos + 22)?.try_into().ok()?);
+
+    let mut wav = wav.to_vec();
+    wav[rate_pos..rate_pos + 4].copy_from_slice(&rate.to_le_bytes());
+    wav[rate_pos + 4..rate_pos + 8].copy_from_slice(&(rate * block_align as u32).to_le_bytes());
+    Some(wav)
+}
+
+fn draw_progress(progress: f32) {
+    const BAR_SIZE: (f32, f32) = (400., 20.);
+
+    clear_background(BLACK);
+    let x = (screen_width() - BAR_SIZE.0) * 0.5;
+    let y = (screen_height() - BAR_SIZE.1) * 0.5;
+    draw_rectangle_lines(x, y, BAR_SIZE.0, BAR_SIZE.1, 2., FOREGROUND_COLOR);
+    draw_rectangle(x, y, BAR_SIZE.0 * progress, BAR_SIZE.1, FOREGROUND_COLOR);
+}
@@ -0,0 +1,220 @@
+//! Sound effects.
+//!
+//! Every sound is played through a [`Bus`] with a volume of its own, under
+//! the master volume. A limiter keeps many sounds started at once from
+//! clipping.
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+};
+
+use macroquad::{
+    audio::{play_sound, PlaySoundParams, Sound},
+    math::Vec2,
+    rand::gen_range,
+    time::get_time,
+};
+
+use pong::{
+    entity::{Entity, Object, Racket, BALL_SIZE},
+    sim::{Event, Simulation},
+};
+
+/// Name of the system API the sounds are played through.
+pub const BACKEND: &str = if cfg!(target_family = "wasm") {
+    "Web Audio"
+} else if cfg!(target_os = "android") {
+    "OpenSL ES"
+} else if cfg!(any(target_os = "macos", target_os = "ios")) {
+    "Core Audio"
+} else if cfg!(target_os = "windows") {
+    "WASAPI"
+} else {
+    "ALSA"
+};
+
+/// Highest summed volume of the sounds started within
+/// [`LIMITER_WINDOW_SECS`]. Sounds started beyond it are played quieter, so
+/// bursts of hits don't clip.
+const LIMITER_CEILING: f32 = 1.5;
+const LIMITER_WINDOW_SECS: f64 = 0.05;
+
+/// Groups of sounds with a volume of their own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bus {
+    /// Sound effects of the matches.
+    Sfx,
+    Music,
+    /// Sounds of the menus and the screens around the matches.
+    Ui,
+    /// Voices calling the score.
+    Announcer,
+}
+
+impl Bus {
+    pub const ALL: [Bus; 4] = [Bus::Sfx, Bus::Music, Bus::Ui, Bus::Announcer];
+
+    /// Returns the name of the bus, as saved in the settings.
+    pub fn name(self) -> &'static str {
+        match self {
+            Bus::Sfx => "sfx",
+            Bus::Music => "music",
+            Bus::Ui => "ui",
+            Bus::Announcer => "announcer",
+        }
+    }
+}
+
+/// Volumes of the sounds, between 0 and 1, and the limiter they all go
+/// through.
+pub struct Mixer {
+    pub master: f32,
+    /// Volume of every bus, in the order of [`Bus::ALL`].
+    pub buses: [f32; Bus::ALL.len()],
+    /// Time and volume of the sounds started within the window of the
+    /// limiter.
+    recent: RefCell<VecDeque<(f64, f32)>>,
+}
+
+impl Mixer {
+    pub fn new(master: f32, buses: [f32; Bus::ALL.len()]) -> Self {
+        Self {
+            master,
+            buses,
+            recent: RefCell::default(),
+        }
+    }
+
+    /// Returns the volume a sound started now on `bus` is played at.
+    fn gain(&self, bus: Bus) -> f32 {
+        let gain = self.master * self.buses[bus as usize];
+        let now = get_time();
+        let mut recent = self.recent.borrow_mut();
+        while recent
+            .front()
+            .is_some_and(|&(time, _)| now - time > LIMITER_WINDOW_SECS)
+        {
+            recent.pop_front();
+        }
+        let total: f32 = recent.iter().map(|&(_, gain)| gain).sum();
+        let gain = if total + gain > LIMITER_CEILING {
+            (LIMITER_CEILING - total).max(0.)
+        } else {
+            gain
+        };
+        recent.push_back((now, gain));
+        gain
+    }
+}
+
+/// Pitches the samples of the pools are played at, the first one being their
+/// own.
+pub const PITCHES: [f32; 5] = [1., 0.94, 0.97, 1.03, 1.06];
+
+/// Pitch the racket sound is raised by when the ball hits a racket near its
+/// edge, so the players can hear how well they returned it.
+pub const EDGE_PITCH: f32 = 1.3;
+
+/// Pitch the point sound is raised by to make the chime of the service
+/// changing sides.
+pub const CHIME_PITCH: f32 = 2.;
+
+/// Distance from the center of a racket the ball must hit it at to sound
+/// like an edge hit, as a fraction of the reach of the racket.
+const EDGE_CONTACT: f32 = 0.6;
+
+/// Samples of a sound heard many times per match. They are played in turn,
+/// each at a random pitch, so the sound doesn't become tiring.
+pub struct Pool {
+    /// Every sample at every pitch of [`PITCHES`] it could be loaded at.
+    samples: Vec<Vec<Sound>>,
+    /// Sample played next.
+    next: Cell<usize>,
+}
+
+impl Pool {
+    pub fn new(samples: Vec<Vec<Sound>>) -> Self {
+        Self {
+            samples,
+            next: Cell::new(0),
+        }
+    }
+
+    /// Returns the first sample at its own pitch, for when the sound must
+    /// always be the same.
+    pub fn first(&self) -> &Sound {
+        &self.samples[0][0]
+    }
+
+    /// Returns the next sample at a random pitch.
+    fn pick(&self) -> &Sound {
+        let pos = self.next.get();
+        self.next.set((pos + 1) % self.samples.len());
+        let pitches = &self.samples[pos];
+        &pitches[gen_range(0, pitches.len())]
+    }
+}
+
+pub struct Sounds {
+    pub point: Sound,
+    /// Called when the service changes sides in table tennis.
+    pub chime: Sound,
+    pub racket: Pool,
+    /// Racket sound for the hits near the edges of the rackets.
+    pub racket_edge: Pool,
+    pub wall: Pool,
+    pub mixer: Mixer,
+}
+
+impl Sounds {
+    /// Plays the sound effect associated with `event`, which has just
+    /// happened in `sim`, if any. Returns whether there was one.
+    pub fn play(&self, event: Event, sim: &Simulation) -> bool {
+        let sound = match event {
+            Event::WallBounce | Event::TargetHit => self.wall.pick(),
+            Event::RacketBounce => match contact(sim) {
+                Some(contact) if contact.abs() > EDGE_CONTACT => self.racket_edge.pick(),
+                _ => self.racket.pick(),
+            },
+            Event::Point(_) => &self.point,
+            Event::MatchPoint(_) | Event::SetWon(_) | Event::Winner(_) => return false,
+        };
+        self.play_on(Bus::Sfx, sound);
+        true
+    }
+
+    /// Plays `sound` through `bus`.
+    pub fn play_on(&self, bus: Bus, sound: &Sound) {
+        let volume = self.mixer.gain(bus);
+        if volume <= 0. {
+            return;
+        }
+        play_sound(
+            sound,
+            PlaySoundParams {
+                looped: false,
+                volume,
+            },
+        );
+    }
+}
+
+/// Returns where the ball closest to a racket in `sim` is along it, from -1
+/// at its top edge to 1 at its bottom edge, or `None` if there are no balls
+/// or rackets.
+pub fn contact(sim: &Simulation) -> Option<f32> {
+    let balls = sim.entities.iter().filter_map(|entity| match entity {
+        Entity::Ball(ball) => Some(ball.center()),
+        _ => None,
+    });
+    let rackets = sim.entities.iter().filter_map(|entity| match entity {
+        Entity::Racket(racket) => Some(racket),
+        _ => None,
+    });
+    let distance = |&(ball, racket): &(Vec2, &Racket)| (ball.x - racket.rect().center().x).abs();
+    let (ball, racket) = balls
+        .flat_map(|ball| rackets.clone().map(move |racket| (ball, racket)))
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))?;
+    let reach = (racket.height + BALL_SIZE) * 0.5;
+    Some(((ball.y - racket.center_y()) / reach).clamp(-1., 1.))
+}
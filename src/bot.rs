@@ -0,0 +1,560 @@
+//! Computer controlled players.
+//!
+//! A bot observes the match from the point of view of its own racket and
+//! decides how to move it on every step. [`Observation`] only contains plain
+//! numbers and is always mirrored so the bot defends the left goal, which
+//! keeps the interface stable and easy to expose to external bots.
+use crate::{
+    entity::{Entity, BALL_SIZE, RACKET_MARGIN, RACKET_SIZE},
+    sim::{Motion, Rng, Side, Simulation, TICK_SECS},
+};
+
+/// Names accepted by [`by_name`].
+pub const BOT_NAMES: &[&str] = &[
+    "tracker",
+    "defender",
+    "adaptive",
+    "hugger",
+    "angler",
+    "spinner",
+    "lazy",
+    "predictor",
+];
+
+/// Names of the built-in bots from the easiest to beat to the hardest, as
+/// suggested to the players when they keep winning or losing.
+pub const DIFFICULTY: &[&str] = &[
+    "lazy",
+    "tracker",
+    "hugger",
+    "adaptive",
+    "defender",
+    "spinner",
+    "angler",
+    "predictor",
+];
+
+/// What a bot can see of the match.
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct Observation {
+    pub court_width: f32,
+    pub court_height: f32,
+    /// Vertical center of the bot's racket.
+    pub racket_y: f32,
+    /// Vertical center of the opponent's racket.
+    pub opponent_y: f32,
+    /// Center of the ball. `ball_x` is the distance to the bot's goal line.
+    pub ball_x: f32,
+    pub ball_y: f32,
+    /// Direction of the ball. Negative `ball_dir_x` means it is approaching.
+    pub ball_dir_x: f32,
+    pub ball_dir_y: f32,
+    pub ball_speed: f32,
+    pub score: i32,
+    pub opponent_score: i32,
+    /// Vertical center of the second racket of the opponent in co-op
+    /// matches. Same as `opponent_y` otherwise.
+    pub second_opponent_y: f32,
+}
+
+impl Observation {
+    pub fn new(sim: &Simulation, side: Side) -> Self {
+        let mirror_x = |x: f32| match side {
+            Side::Left => x,
+            Side::Right => sim.court.width - x,
+        };
+        let mirror_dir = |dx: f32| match side {
+            Side::Left => dx,
+            Side::Right => -dx,
+        };
+
+        let (score, opponent_score) = match side {
+            Side::Left => sim.scores,
+            Side::Right => (sim.scores.1, sim.scores.0),
+        };
+        let mut has_partner = false;
+        let mut obs = Observation {
+            court_width: sim.court.width,
+            court_height: sim.court.height,
+            score,
+            opponent_score,
+            ..Default::default()
+        };
+        for entity in &sim.entities {
+            match entity {
+                // Bots don't control partner rackets.
+                Entity::Racket(racket) if racket.side == side => {
+                    if !racket.partner {
+                        obs.racket_y = racket.center_y();
+                    }
+                }
+                Entity::Racket(racket) if racket.partner => {
+                    obs.second_opponent_y = racket.center_y();
+                    has_partner = true;
+                }
+                Entity::Racket(racket) => {
+                    obs.opponent_y = racket.center_y();
+                }
+                // Bots only watch the balls they can hit.
+                Entity::Ball(ball) if ball.owner.is_none_or(|owner| owner == side) => {
+                    obs.ball_x = mirror_x(ball.pos.0 + BALL_SIZE * 0.5);
+                    obs.ball_y = ball.pos.1 + BALL_SIZE * 0.5;
+                    obs.ball_dir_x = mirror_dir(ball.dir.0);
+                    obs.ball_dir_y = ball.dir.1;
+                    obs.ball_speed = ball.speed;
+                }
+                Entity::Ball(_) | Entity::Obstacle(_) | Entity::Target(_) => {}
+            }
+        }
+        if !has_partner {
+            obs.second_opponent_y = obs.opponent_y;
+        }
+        obs
+    }
+}
+
+/// What a bot was thinking during its last step, to help tuning it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Telemetry {
+    /// Height at which the bot expects the ball to reach its racket.
+    pub intercept: Option<f32>,
+    /// Height the bot is moving its racket to.
+    pub target_y: f32,
+    /// Short description of what the bot is doing.
+    pub decision: &'static str,
+    /// Level of the skill of the bot, if it has one.
+    pub skill: Option<f32>,
+}
+
+pub trait PongBot: Send {
+    /// Returns how the racket must move during the next step.
+    fn act(&mut self, obs: &Observation) -> Motion;
+
+    /// Returns what the bot was thinking during the last call to
+    /// [`PongBot::act`]. Bots are not required to explain themselves.
+    fn telemetry(&self) -> Option<Telemetry> {
+        None
+    }
+
+    /// Called when a match starts, with the seed of the bot given by
+    /// [`seed`], so bots making random decisions make different ones in
+    /// every match.
+    fn reset(&mut self, _seed: u64) {}
+}
+
+/// Returns the seed of the bot playing on `side` of a match started from
+/// `match_seed`. The bots of both sides get different ones.
+pub fn seed(match_seed: u64, side: Side) -> u64 {
+    match side {
+        Side::Left => match_seed,
+        Side::Right => !match_seed,
+    }
+}
+
+/// Returns the built-in bot called `name`.
+pub fn by_name(name: &str) -> Option<Box<dyn PongBot>> {
+    match name {
+        "tracker" => Some(Box::new(Tracker)),
+        "defender" => Some(Box::new(Defender)),
+        "adaptive" => Some(Box::new(Adaptive::new())),
+        "hugger" => Some(Box::new(WallHugger)),
+        "angler" => Some(Box::new(Angler)),
+        "spinner" => Some(Box::new(Spinner)),
+        "lazy" => Some(Box::new(Lazy)),
+        "predictor" => Some(Box::new(Predictive::new())),
+        _ => None,
+    }
+}
+
+/// Returns the motion that brings a racket centered at `racket_y` closer to
+/// `target_y`.
+fn move_towards(racket_y: f32, target_y: f32) -> Motion {
+    const DEAD_ZONE: f32 = RACKET_SIZE.1 * 0.25;
+
+    let delta = target_y - racket_y;
+    if delta < -DEAD_ZONE {
+        Motion::Up
+    } else if delta > DEAD_ZONE {
+        Motion::Down
+    } else {
+        Motion::Stop
+    }
+}
+
+/// Follows the ball vertically.
+pub struct Tracker;
+
+impl PongBot for Tracker {
+    fn act(&mut self, obs: &Observation) -> Motion {
+        move_towards(obs.racket_y, obs.ball_y)
+    }
+}
+
+/// Follows the ball while it approaches and waits at the center otherwise.
+pub struct Defender;
+
+impl PongBot for Defender {
+    fn act(&mut self, obs: &Observation) -> Motion {
+        let target_y = if obs.ball_dir_x < 0. {
+            obs.ball_y
+        } else {
+            obs.court_height * 0.5
+        };
+        move_towards(obs.racket_y, target_y)
+    }
+}
+
+/// Waits against the wall closest to the ball instead of at the center and
+/// only comes out when the ball approaches.
+pub struct WallHugger;
+
+impl PongBot for WallHugger {
+    fn act(&mut self, obs: &Observation) -> Motion {
+        if obs.ball_dir_x < 0. {
+            return move_towards(obs.racket_y, obs.ball_y);
+        }
+        let half = RACKET_SIZE.1 * 0.5;
+        let target_y = if obs.ball_y < obs.court_height * 0.5 {
+            half
+        } else {
+            obs.court_height - half
+        };
+        move_towards(obs.racket_y, target_y)
+    }
+}
+
+/// Meets the ball with an edge of the racket to send it at a steep angle,
+/// towards the widest gap left by the opponents.
+pub struct Angler;
+
+impl PongBot for Angler {
+    fn act(&mut self, obs: &Observation) -> Motion {
+        if obs.ball_dir_x >= 0. {
+            return move_towards(obs.racket_y, obs.court_height * 0.5);
+        }
+        // Hitting the ball with the upper edge sends it down, and the other
+        // way around.
+        let offset = RACKET_SIZE.1 * 0.2;
+        let target_y = if widest_gap(obs) > obs.court_height * 0.5 {
+            obs.ball_y - offset
+        } else {
+            obs.ball_y + offset
+        };
+        move_towards(obs.racket_y, target_y)
+    }
+}
+
+/// Returns the center of the widest gap between the rackets of the opponents
+/// and the walls.
+fn widest_gap(obs: &Observation) -> f32 {
+    let (a, b) = (
+        obs.opponent_y.min(obs.second_opponent_y),
+        obs.opponent_y.max(obs.second_opponent_y),
+    );
+    [(0., a), (a, b), (b, obs.court_height)]
+        .into_iter()
+        .max_by(|x, y| (x.1 - x.0).total_cmp(&(y.1 - y.0)))
+        .map_or(obs.court_height * 0.5, |(top, bottom)| (top + bottom) * 0.5)
+}
+
+/// Keeps the racket moving along with the ball when it is about to hit it,
+/// which puts spin on the ball with physics that allow it.
+pub struct Spinner;
+
+impl PongBot for Spinner {
+    fn act(&mut self, obs: &Observation) -> Motion {
+        if obs.ball_dir_x >= 0. {
+            return move_towards(obs.racket_y, obs.court_height * 0.5);
+        }
+        let close = obs.ball_x < obs.court_width * 0.15;
+        let in_reach = (obs.ball_y - obs.racket_y).abs() < RACKET_SIZE.1 * 0.5;
+        if close && in_reach {
+            return if obs.ball_dir_y < 0. {
+                Motion::Up
+            } else {
+                Motion::Down
+            };
+        }
+        move_towards(obs.racket_y, obs.ball_y)
+    }
+}
+
+/// Stays where it is until the ball is about to reach its side of the court.
+pub struct Lazy;
+
+impl PongBot for Lazy {
+    fn act(&mut self, obs: &Observation) -> Motion {
+        if obs.ball_dir_x < 0. && obs.ball_x < obs.court_width * 0.4 {
+            return move_towards(obs.racket_y, obs.ball_y);
+        }
+        Motion::Stop
+    }
+}
+
+/// Change of the level of a [`Skill`] per second while it moves towards its
+/// target.
+const SKILL_RATE: f32 = 0.25;
+
+/// How well a bot plays, from 0 (sluggish) to 1 (as good as [`Defender`]).
+///
+/// The level can be retargeted at any time and moves towards the new target
+/// gradually, so the change in strength is hard to notice.
+#[derive(Clone, Copy, Debug)]
+pub struct Skill {
+    level: f32,
+    target: f32,
+    /// Accumulates the fraction of the steps in which the racket moves.
+    stride: f32,
+}
+
+impl Skill {
+    pub fn new(level: f32) -> Self {
+        let level = level.clamp(0., 1.);
+        Self {
+            level,
+            target: level,
+            stride: 0.,
+        }
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Sets the level the skill moves towards.
+    pub fn retarget(&mut self, level: f32) {
+        self.target = level.clamp(0., 1.);
+    }
+
+    /// Returns how close the ball must be, as a fraction of the width of
+    /// the court, before the bot reacts to it.
+    pub fn reach(&self) -> f32 {
+        0.35 + 0.65 * self.level
+    }
+
+    /// Advances the skill by one step and limits `motion` to the speed the
+    /// current level allows. Slower levels skip some of the steps.
+    pub fn apply(&mut self, motion: Motion) -> Motion {
+        let delta = SKILL_RATE * TICK_SECS;
+        self.level += (self.target - self.level).clamp(-delta, delta);
+
+        if motion == Motion::Stop {
+            return motion;
+        }
+        self.stride += 0.55 + 0.45 * self.level;
+        if self.stride < 1. {
+            return Motion::Stop;
+        }
+        self.stride -= 1.;
+        motion
+    }
+}
+
+/// Skill of [`Adaptive`] when the score is tied.
+const ADAPTIVE_LEVEL: f32 = 0.6;
+/// Change of the skill of [`Adaptive`] for every point of lead.
+const ADAPTIVE_LEVEL_PER_POINT: f32 = 0.15;
+
+/// Defends like [`Defender`], but plays worse while winning and better while
+/// losing to keep the match close. The skill is only retargeted between
+/// points.
+pub struct Adaptive {
+    skill: Skill,
+    /// Points played when the skill was last retargeted.
+    points: i32,
+    telemetry: Telemetry,
+}
+
+impl Adaptive {
+    pub fn new() -> Self {
+        Self {
+            skill: Skill::new(ADAPTIVE_LEVEL),
+            points: 0,
+            telemetry: Telemetry::default(),
+        }
+    }
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PongBot for Adaptive {
+    fn act(&mut self, obs: &Observation) -> Motion {
+        let points = obs.score + obs.opponent_score;
+        if points != self.points {
+            self.points = points;
+            let lead = (obs.score - obs.opponent_score) as f32;
+            self.skill
+                .retarget(ADAPTIVE_LEVEL - lead * ADAPTIVE_LEVEL_PER_POINT);
+        }
+
+        let approaching = obs.ball_dir_x < 0. && obs.ball_x < obs.court_width * self.skill.reach();
+        let (target_y, decision) = if approaching {
+            (obs.ball_y, "tracking")
+        } else {
+            (obs.court_height * 0.5, "waiting")
+        };
+        self.telemetry = Telemetry {
+            intercept: None,
+            target_y,
+            decision,
+            skill: Some(self.skill.level()),
+        };
+        self.skill.apply(move_towards(obs.racket_y, target_y))
+    }
+
+    fn telemetry(&self) -> Option<Telemetry> {
+        Some(self.telemetry)
+    }
+
+    fn reset(&mut self, _seed: u64) {
+        *self = Self::new();
+    }
+}
+
+/// Returns the height at which the center of the ball will reach the face of
+/// the racket, assuming it keeps its direction. Bounces off the top and
+/// bottom walls are accounted for unless `misread` is set, in which case the
+/// ball is expected to go straight and stop at the wall.
+pub fn intercept(obs: &Observation, misread: bool) -> f32 {
+    let face_x = RACKET_MARGIN + RACKET_SIZE.0 + BALL_SIZE * 0.5;
+    if obs.ball_dir_x >= 0. {
+        return obs.ball_y;
+    }
+    let dx = (obs.ball_x - face_x).max(0.);
+    let y = obs.ball_y + obs.ball_dir_y / -obs.ball_dir_x * dx;
+
+    // Fold the straight path into the court between the walls.
+    let (min, max) = (BALL_SIZE * 0.5, obs.court_height - BALL_SIZE * 0.5);
+    if misread {
+        return y.clamp(min, max);
+    }
+    let span = max - min;
+    let t = (y - min).rem_euclid(span * 2.);
+    min + if t > span { span * 2. - t } else { t }
+}
+
+/// Predicts where the ball will reach the racket the way a person would: it
+/// takes a while to react when the ball changes direction, the guesses are
+/// off by up to `noise` and wall bounces are sometimes overlooked.
+pub struct Predictor {
+    /// Maximum error of a prediction, in court units.
+    pub noise: f32,
+    /// Steps it takes to react after the ball changes direction.
+    pub latency: u32,
+    /// Probability of overlooking the bounces off the walls.
+    pub misread: f32,
+    rng: Rng,
+    /// Whether the ball was moving left and up when last seen.
+    dir: Option<(bool, bool)>,
+    /// Steps left before reacting to the last change of direction.
+    wait: u32,
+    /// Whether the last change of direction is still to be reacted to.
+    pending: bool,
+    target: Option<f32>,
+    /// Whether the bounces were overlooked in the current target.
+    misread_last: bool,
+}
+
+impl Predictor {
+    pub fn new(noise: f32, latency: u32, misread: f32, seed: u64) -> Self {
+        Self {
+            noise,
+            latency,
+            misread,
+            rng: Rng::new(seed),
+            dir: None,
+            wait: 0,
+            pending: false,
+            target: None,
+            misread_last: false,
+        }
+    }
+
+    /// Returns a short description of what the predictor is doing.
+    pub fn decision(&self) -> &'static str {
+        match (self.wait > 0, self.target) {
+            (true, _) => "reacting",
+            (false, Some(_)) if self.misread_last => "misread",
+            (false, Some(_)) => "intercepting",
+            (false, None) => "waiting",
+        }
+    }
+
+    /// Returns the height the racket should move to, or `None` if the ball
+    /// is not approaching as far as the predictor knows.
+    pub fn update(&mut self, obs: &Observation) -> Option<f32> {
+        let dir = Some((obs.ball_dir_x < 0., obs.ball_dir_y < 0.));
+        if dir != self.dir {
+            self.dir = dir;
+            self.wait = self.latency;
+            self.pending = true;
+        }
+        if self.wait > 0 {
+            self.wait -= 1;
+            return self.target;
+        }
+        if self.pending {
+            self.pending = false;
+            self.misread_last = false;
+            self.target = (obs.ball_dir_x < 0.).then(|| {
+                self.misread_last = self.rng.next_f32() < self.misread;
+                let error = (self.rng.next_f32() * 2. - 1.) * self.noise;
+                intercept(obs, self.misread_last) + error
+            });
+        }
+        self.target
+    }
+}
+
+/// Moves to where it predicts the ball will arrive, with human-like mistakes,
+/// and waits at the center otherwise.
+pub struct Predictive {
+    predictor: Predictor,
+    telemetry: Telemetry,
+}
+
+impl Predictive {
+    pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    fn with_seed(seed: u64) -> Self {
+        Self {
+            predictor: Predictor::new(30., 12, 0.15, seed),
+            telemetry: Telemetry::default(),
+        }
+    }
+}
+
+impl Default for Predictive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PongBot for Predictive {
+    fn act(&mut self, obs: &Observation) -> Motion {
+        let intercept = self.predictor.update(obs);
+        let target_y = intercept.unwrap_or(obs.court_height * 0.5);
+        self.telemetry = Telemetry {
+            intercept,
+            target_y,
+            decision: self.predictor.decision(),
+            skill: None,
+        };
+        move_towards(obs.racket_y, target_y)
+    }
+
+    fn telemetry(&self) -> Option<Telemetry> {
+        Some(self.telemetry)
+    }
+
+    fn reset(&mut self, seed: u64) {
+        *self = Self::with_seed(seed);
+    }
+}
@@ -0,0 +1,141 @@
+//! Debug view of the bots, to help tuning them.
+//!
+//! The decisions of every bot that reports its [`Telemetry`] are drawn over
+//! the court and summed up in the log after every point.
+use std::fmt::Write;
+
+use macroquad::prelude::*;
+use pong::{
+    bot::Telemetry,
+    entity::{Entity, RACKET_MARGIN, RACKET_SIZE},
+    runner::Runner,
+    sim::{MatchState, Side},
+};
+
+const INTERCEPT_COLOR: Color = MAGENTA;
+const TARGET_COLOR: Color = YELLOW;
+
+/// Decisions of a bot during the current point.
+#[derive(Default)]
+struct PointLog {
+    /// Number of frames spent on every decision.
+    decisions: Vec<(&'static str, u32)>,
+    intercept: Option<f32>,
+}
+
+#[derive(Default)]
+pub struct BotDebug {
+    scores: (i32, i32),
+    /// Height of the ball on the previous frame.
+    ball_y: f32,
+    logs: [PointLog; 2],
+}
+
+impl BotDebug {
+    pub fn update(&mut self, runner: &Runner) {
+        let sim = runner.sim();
+        if sim.scores != self.scores {
+            // A new match resets the scores, which is not worth a log.
+            if sim.scores.0 >= self.scores.0 && sim.scores.1 >= self.scores.1 {
+                let winner = if sim.scores.0 > self.scores.0 {
+                    Side::Left
+                } else {
+                    Side::Right
+                };
+                self.log_point(winner);
+            }
+            self.scores = sim.scores;
+            self.logs = Default::default();
+        }
+
+        for (side, log) in [Side::Left, Side::Right].into_iter().zip(&mut self.logs) {
+            let Some(telemetry) = runner.telemetry(side) else {
+                continue;
+            };
+            match log
+                .decisions
+                .iter_mut()
+                .find(|(decision, _)| *decision == telemetry.decision)
+            {
+                Some((_, frames)) => *frames += 1,
+                None => log.decisions.push((telemetry.decision, 1)),
+            }
+            log.intercept = telemetry.intercept.or(log.intercept);
+        }
+        for entity in &sim.entities {
+            if let Entity::Ball(ball) = entity {
+                self.ball_y = ball.center().y;
+            }
+        }
+    }
+
+    fn log_point(&self, winner: Side) {
+        for (side, log) in [Side::Left, Side::Right].into_iter().zip(&self.logs) {
+            if log.decisions.is_empty() {
+                continue;
+            }
+            let total: u32 = log.decisions.iter().map(|(_, frames)| frames).sum();
+            let mut line = format!("bot {side}: point for {winner},");
+            for (decision, frames) in &log.decisions {
+                write!(line, " {decision} {}%", frames * 100 / total).expect("format decision");
+            }
+            if let Some(intercept) = log.intercept {
+                write!(
+                    line,
+                    ", last intercept {intercept:.0} ball {:.0}",
+                    self.ball_y
+                )
+                .expect("format intercept");
+            }
+            eprintln!("{line}");
+        }
+    }
+
+    /// Draws the intercept and target of every bot next to its racket, with
+    /// its decision and skill.
+    pub fn draw(&self, runner: &Runner) {
+        let sim = runner.sim();
+        if sim.state != MatchState::Playing {
+            return;
+        }
+        for side in [Side::Left, Side::Right] {
+            let Some(telemetry) = runner.telemetry(side) else {
+                continue;
+            };
+            let face_x = match side {
+                Side::Left => RACKET_MARGIN + RACKET_SIZE.0,
+                Side::Right => sim.court.width - RACKET_MARGIN - RACKET_SIZE.0,
+            };
+            draw_telemetry(&telemetry, side, face_x);
+        }
+    }
+}
+
+fn draw_telemetry(telemetry: &Telemetry, side: Side, face_x: f32) {
+    // Towards the center of the court.
+    let dir = match side {
+        Side::Left => 1.,
+        Side::Right => -1.,
+    };
+    draw_line(
+        face_x,
+        telemetry.target_y,
+        face_x + dir * 30.,
+        telemetry.target_y,
+        2.,
+        TARGET_COLOR,
+    );
+    if let Some(y) = telemetry.intercept {
+        draw_circle_lines(face_x + dir * 10., y, 6., 2., INTERCEPT_COLOR);
+    }
+
+    let mut text = telemetry.decision.to_owned();
+    if let Some(skill) = telemetry.skill {
+        write!(text, " {skill:.2}").expect("format skill");
+    }
+    let x = match side {
+        Side::Left => face_x + 10.,
+        Side::Right => face_x - 10. - measure_text(&text, None, 16, 1.).width,
+    };
+    draw_text(&text, x, telemetry.target_y - 10., 16., TARGET_COLOR);
+}
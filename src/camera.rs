@@ -0,0 +1,214 @@
+//! Cameras showing part of the court: one that can be zoomed and panned
+//! around with the mouse, and one that follows the action by itself, along
+//! with a minimap of the whole court.
+use macroquad::prelude::*;
+use pong::{
+    court::Court,
+    entity::{Entity, Object},
+    sim::Simulation,
+};
+
+use crate::draw::{self, FOREGROUND_COLOR};
+
+/// Highest zoom of the camera.
+const MAX_ZOOM: f32 = 6.;
+/// How much every notch of the mouse wheel zooms.
+const WHEEL_ZOOM_STEP: f32 = 1.15;
+
+pub struct FreeCamera {
+    pub zoom: f32,
+    /// Point of the court the camera is centered on when it doesn't follow
+    /// anything.
+    pub focus: Vec2,
+    /// Position of the mouse over the court while it drags the camera.
+    drag: Option<Vec2>,
+}
+
+impl FreeCamera {
+    /// Returns a camera showing the whole `court`.
+    pub fn new(court: &Court) -> Self {
+        Self {
+            zoom: 1.,
+            focus: court.rect().center(),
+            drag: None,
+        }
+    }
+
+    /// Returns the part of `court` shown by the camera, centered on `target`
+    /// if it follows something.
+    pub fn view(&self, court: &Court, target: Option<Vec2>) -> Rect {
+        let court = court.rect();
+        let size = court.size() / self.zoom;
+        let focus = target.unwrap_or(self.focus);
+        let pos = (focus - size * 0.5).clamp(Vec2::ZERO, court.size() - size);
+        Rect::new(pos.x, pos.y, size.x, size.y)
+    }
+
+    /// Returns the point of `court` under the mouse.
+    fn mouse_on_court(&self, court: &Court, target: Option<Vec2>) -> Vec2 {
+        let viewport = draw::viewport(court);
+        let view = self.view(court, target);
+        let pos = (Vec2::from(mouse_position()) - viewport.point()) / viewport.size();
+        view.point() + pos * view.size()
+    }
+
+    /// Zooms the camera with the mouse wheel and pans it by dragging the
+    /// mouse. Dragging takes the camera over from `target`, so it returns
+    /// whether the camera was dragged.
+    pub fn update_mouse(&mut self, court: &Court, target: Option<Vec2>) -> bool {
+        let wheel = mouse_wheel().1;
+        if wheel != 0. {
+            // The point under the mouse stays in place.
+            let anchor = self.mouse_on_court(court, target);
+            let step = if wheel > 0. {
+                WHEEL_ZOOM_STEP
+            } else {
+                1. / WHEEL_ZOOM_STEP
+            };
+            let zoom = (self.zoom * step).clamp(1., MAX_ZOOM);
+            let view = self.view(court, target);
+            self.focus = anchor + (view.center() - anchor) * self.zoom / zoom;
+            self.zoom = zoom;
+        }
+
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.drag = None;
+            return false;
+        }
+        let pos = self.mouse_on_court(court, target);
+        let Some(start) = self.drag else {
+            self.drag = Some(pos);
+            return false;
+        };
+        if target.is_some() {
+            self.focus = self.view(court, target).center();
+        }
+        // The focus stays where the view can be centered.
+        self.focus += start - pos;
+        self.focus = self.view(court, None).center();
+        // The view moved, so the point under the mouse is measured again.
+        self.drag = Some(self.mouse_on_court(court, None));
+        true
+    }
+}
+
+/// Zoom of the dynamic camera during the rallies.
+const DYNAMIC_ZOOM: f32 = 1.25;
+/// Rate at which the dynamic camera eases towards the action, per second.
+const DYNAMIC_EASE_RATE: f32 = 3.;
+/// Time the dynamic camera shows the whole court after a point, for the
+/// serve.
+const SERVE_SECS: f32 = 1.;
+
+/// Camera that zooms in slightly on the action, between the ball and the
+/// racket closest to it, and eases back to the whole court for the serves.
+#[derive(Default)]
+pub struct DynamicCamera {
+    zoom: f32,
+    focus: Vec2,
+    /// Time left showing the whole court for the serve.
+    serve_secs: f32,
+}
+
+impl DynamicCamera {
+    /// Shows the whole `court` at once, ready for a serve.
+    pub fn reset(&mut self, court: &Court) {
+        self.zoom = 1.;
+        self.focus = court.rect().center();
+        self.serve_secs = SERVE_SECS;
+    }
+
+    /// Eases the camera towards the action in `sim`, `dt` seconds after the
+    /// previous update. `point` tells whether a point was scored since.
+    pub fn update(&mut self, sim: &Simulation, point: bool, dt: f32) {
+        if point {
+            self.serve_secs = SERVE_SECS;
+        }
+        self.serve_secs = (self.serve_secs - dt).max(0.);
+        let center = sim.court.rect().center();
+        let ball = sim.entities.iter().find_map(|entity| match entity {
+            Entity::Ball(ball) => Some(ball.center()),
+            _ => None,
+        });
+        let (zoom, focus) = match ball {
+            Some(ball) if self.serve_secs <= 0. => {
+                let racket = sim
+                    .entities
+                    .iter()
+                    .filter_map(|entity| match entity {
+                        Entity::Racket(racket) => Some(racket.rect().center()),
+                        _ => None,
+                    })
+                    .min_by(|a, b| a.distance(ball).total_cmp(&b.distance(ball)));
+                (
+                    DYNAMIC_ZOOM,
+                    racket.map_or(ball, |racket| (ball + racket) * 0.5),
+                )
+            }
+            _ => (1., center),
+        };
+        let ease = 1. - (-DYNAMIC_EASE_RATE * dt).exp();
+        self.zoom += (zoom - self.zoom) * ease;
+        self.focus += (focus - self.focus) * ease;
+    }
+
+    /// Returns the part of `court` shown by the camera.
+    pub fn view(&self, court: &Court) -> Rect {
+        let court = court.rect();
+        let size = court.size() / self.zoom;
+        let pos = (self.focus - size * 0.5).clamp(Vec2::ZERO, court.size() - size);
+        Rect::new(pos.x, pos.y, size.x, size.y)
+    }
+}
+
+/// Size of the minimap relative to the court.
+const MINIMAP_SCALE: f32 = 0.2;
+/// Space between the minimap and the corner of the court.
+const MINIMAP_MARGIN: f32 = 10.;
+const MINIMAP_ALPHA: f32 = 0.8;
+
+/// Small image of the whole court, shown in a corner while a camera shows
+/// only part of it.
+pub struct Minimap {
+    target: RenderTarget,
+    /// Camera showing the whole court on the minimap.
+    pub camera: Camera2D,
+}
+
+impl Minimap {
+    pub fn new(court: &Court) -> Self {
+        let size = court.rect().size() * MINIMAP_SCALE;
+        let target = render_target(size.x as u32, size.y as u32);
+        let mut camera = Camera2D::from_display_rect(court.rect());
+        camera.render_target = Some(target.clone());
+        Self { target, camera }
+    }
+
+    /// Draws the minimap in the bottom right corner of `court`, framing the
+    /// part of it in `view`.
+    pub fn draw(&self, court: &Court, view: Rect) {
+        let court = court.rect();
+        let size = court.size() * MINIMAP_SCALE;
+        let pos = court.size() - size - MINIMAP_MARGIN;
+        draw_texture_ex(
+            &self.target.texture,
+            pos.x,
+            pos.y,
+            Color::new(1., 1., 1., MINIMAP_ALPHA),
+            DrawTextureParams {
+                dest_size: Some(size),
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+        draw_rectangle_lines(pos.x, pos.y, size.x, size.y, 2., FOREGROUND_COLOR);
+        draw_rectangle_lines(
+            pos.x + view.x * MINIMAP_SCALE,
+            pos.y + view.y * MINIMAP_SCALE,
+            view.w * MINIMAP_SCALE,
+            view.h * MINIMAP_SCALE,
+            1.,
+            FOREGROUND_COLOR,
+        );
+    }
+}
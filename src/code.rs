@@ -0,0 +1,208 @@
+//! Shareable match codes.
+//!
+//! A code describes everything needed to play a match again: the court, the
+//! physics, the lineup and the seed. Codes are short words separated by
+//! dashes, like `garden-realistic-coop-2n9c3f7wq`:
+//!
+//! - the court, as returned by [`Court::spec`];
+//! - the physics preset, if it is not the default one;
+//! - the shape of the ball, if it differs from the one of the preset;
+//! - `slow` followed by the sides with the slow-ball handicap, if any;
+//! - the lineup, if it is not a single match;
+//! - the rules preset, if it is not the classic one, or `tt` if the match
+//!   is served as in table tennis by custom rules;
+//! - `w` followed by the motion of the walls, if they move;
+//! - the seed in base 36.
+//!
+//! Like traces, custom physics cannot be described and are shared as the
+//! default ones. Unlike traces, custom rules cannot be described either,
+//! and are shared as the classic ones with their service.
+use std::{fmt, str::FromStr};
+
+use crate::{
+    court::{Court, WallMotion},
+    physics::{BallShape, Physics},
+    rules::{self, Rules},
+    sim::{Lineup, Service},
+};
+
+const SEED_RADIX: u32 = 36;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchCode {
+    pub court: Court,
+    pub physics: Physics,
+    pub lineup: Lineup,
+    pub rules: Rules,
+    pub seed: u64,
+}
+
+impl fmt::Display for MatchCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.court.spec())?;
+        let preset = self.physics.preset_name().unwrap_or("arcade");
+        if preset != "arcade" {
+            write!(f, "-{preset}")?;
+        }
+        let preset = Physics::parse(preset).unwrap_or_default();
+        if self.physics.ball_shape != preset.ball_shape {
+            write!(f, "-{}", self.physics.ball_shape)?;
+        }
+        if let Some(sides) = self.physics.slow_ball_spec() {
+            write!(f, "-slow{sides}")?;
+        }
+        if self.lineup != Lineup::Single {
+            write!(f, "-{}", self.lineup)?;
+        }
+        match self.rules.preset_name() {
+            Some("classic") => {}
+            Some(preset) => write!(f, "-{preset}")?,
+            None if self.rules.service == Service::TableTennis => write!(f, "-tt")?,
+            None => {}
+        }
+        if let Some(motion) = self.court.wall_motion {
+            write!(f, "-w{motion}")?;
+        }
+        write!(f, "-{}", to_base36(self.seed))
+    }
+}
+
+/// Error returned when a match code cannot be parsed.
+#[derive(Debug, PartialEq)]
+pub struct ParseCodeError {
+    /// Part of the code that is not valid.
+    pub part: String,
+}
+
+impl fmt::Display for ParseCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid part of match code: {:?}", self.part)
+    }
+}
+
+impl std::error::Error for ParseCodeError {}
+
+impl FromStr for MatchCode {
+    type Err = ParseCodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |part: &str| ParseCodeError {
+            part: part.to_owned(),
+        };
+
+        let s = s.trim().to_lowercase();
+        let mut parts: Vec<_> = s.split('-').collect();
+        let seed = parts.pop().unwrap_or_default();
+        let seed = u64::from_str_radix(seed, SEED_RADIX).map_err(|_| err(seed))?;
+        let (&court, parts) = parts.split_first().ok_or_else(|| err(&s))?;
+        let mut court = Court::parse(court).ok_or_else(|| err(court))?;
+
+        let mut physics = Physics::default();
+        let mut ball_shape = None;
+        let mut slow_ball = None;
+        let mut lineup = Lineup::Single;
+        let mut rules = Rules::CLASSIC;
+        for &part in parts {
+            if let Some(preset) = Physics::parse(part) {
+                physics = preset;
+            } else if let Some(shape) = BallShape::parse(part) {
+                ball_shape = Some(shape);
+            } else if let Some(sides) = part.strip_prefix("slow").and_then(Physics::parse_slow_ball)
+            {
+                slow_ball = Some(sides);
+            } else if let Some(parsed) = Lineup::parse(part) {
+                lineup = parsed;
+            } else if let Some(preset) = rules::PRESETS.iter().find(|preset| preset.name == part) {
+                rules = preset.rules;
+            } else if part == "tt" {
+                rules.service = Service::TableTennis;
+            } else if let Some(motion) = part.strip_prefix('w').and_then(WallMotion::parse) {
+                court.wall_motion = Some(motion);
+            } else {
+                return Err(err(part));
+            }
+        }
+        if let Some(shape) = ball_shape {
+            physics.ball_shape = shape;
+        }
+        if let Some(sides) = slow_ball {
+            physics.slow_ball = sides;
+        }
+
+        Ok(Self {
+            court,
+            physics,
+            lineup,
+            rules,
+            seed,
+        })
+    }
+}
+
+/// Match left halfway, described by its code and how far it went, to resume
+/// it. It is written as lines of a report:
+///
+/// ```text
+/// code classic-tt11-2n9c3f7wq
+/// scores 7 4
+/// sets 1 0
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveState {
+    pub code: MatchCode,
+    /// Points of the left and right sides in the current set.
+    pub scores: (i32, i32),
+    /// Sets won by the left and right sides.
+    pub sets: (u32, u32),
+}
+
+impl SaveState {
+    /// Reads a save-state from the lines of `text`, skipping the ones of the
+    /// rest of the report. States saved before the sets were have none won.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut code = None;
+        let mut scores = None;
+        let mut sets = (0, 0);
+        for line in text.lines() {
+            match line.split_once(' ') {
+                Some(("code", value)) => code = value.parse().ok(),
+                Some(("scores", value)) => scores = parse_pair(value),
+                Some(("sets", value)) => sets = parse_pair(value)?,
+                _ => {}
+            }
+        }
+        Some(Self {
+            code: code?,
+            scores: scores?,
+            sets,
+        })
+    }
+}
+
+impl fmt::Display for SaveState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "code {}", self.code)?;
+        writeln!(f, "scores {} {}", self.scores.0, self.scores.1)?;
+        writeln!(f, "sets {} {}", self.sets.0, self.sets.1)
+    }
+}
+
+/// Parses two numbers separated by a space.
+fn parse_pair<T: FromStr>(s: &str) -> Option<(T, T)> {
+    let (left, right) = s.split_once(' ')?;
+    Some((left.parse().ok()?, right.parse().ok()?))
+}
+
+fn to_base36(mut n: u64) -> String {
+    let mut digits = Vec::new();
+    loop {
+        let digit = char::from_digit((n % SEED_RADIX as u64) as u32, SEED_RADIX)
+            .expect("digit within radix");
+        digits.push(digit);
+        n /= SEED_RADIX as u64;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
@@ -0,0 +1,203 @@
+//! Playing areas.
+//!
+//! A [`Court`] describes everything about the geometry of a match: its size,
+//! what happens when the ball reaches the top and bottom walls, whether they
+//! move and the obstacles placed on it. A few named [presets](PRESETS) are
+//! provided.
+use std::{f32::consts::TAU, fmt};
+
+use macroquad::math::Rect;
+
+use crate::{
+    entity::{BALL_SIZE, RACKET_MARGIN, RACKET_SIZE},
+    math,
+    sim::TICK_RATE,
+};
+
+/// What happens when the ball reaches the top or bottom of the court.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Walls {
+    /// The ball bounces off them.
+    #[default]
+    Bounce,
+    /// The ball leaves the court and comes back from the opposite side.
+    Wrap,
+}
+
+/// Oscillation of the top and bottom walls. Both walls move towards the
+/// center of the court and back at the same time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WallMotion {
+    /// Furthest the walls move into the court.
+    pub amplitude: f32,
+    /// Oscillations per second.
+    pub frequency: f32,
+}
+
+impl WallMotion {
+    /// Parses an amplitude and a frequency separated by a comma, like
+    /// `60,0.25`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (amplitude, frequency) = s.split_once(',')?;
+        let motion = Self {
+            amplitude: amplitude.parse().ok()?,
+            frequency: frequency.parse().ok()?,
+        };
+        (motion.amplitude >= 0. && motion.frequency >= 0.).then_some(motion)
+    }
+}
+
+impl fmt::Display for WallMotion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.amplitude, self.frequency)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Court {
+    pub width: f32,
+    pub height: f32,
+    pub walls: Walls,
+    /// How the top and bottom walls move, if they do. Only bouncing walls
+    /// move.
+    pub wall_motion: Option<WallMotion>,
+    /// Rectangles the ball bounces off.
+    pub obstacles: Vec<Rect>,
+}
+
+impl Court {
+    pub const DEFAULT: Court = Court {
+        width: 800.,
+        height: 600.,
+        walls: Walls::Bounce,
+        wall_motion: None,
+        obstacles: Vec::new(),
+    };
+
+    /// Returns an empty court of the given size, or `None` if the rackets and
+    /// the ball don't fit in it.
+    pub fn new(width: f32, height: f32) -> Option<Self> {
+        let min_width = (RACKET_MARGIN + RACKET_SIZE.0) * 2. + BALL_SIZE * 4.;
+        let min_height = RACKET_SIZE.1 + BALL_SIZE * 2.;
+        (width >= min_width && height >= min_height).then_some(Self {
+            width,
+            height,
+            ..Self::DEFAULT
+        })
+    }
+
+    /// Parses either the name of a preset or a size like `800x600`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        if let Some(preset) = PRESETS.iter().find(|preset| preset.name == spec) {
+            return Some(preset.court());
+        }
+        let (width, height) = spec.split_once('x')?;
+        Self::new(width.parse().ok()?, height.parse().ok()?)
+    }
+
+    /// Returns the name of the preset matching the court or, if there is
+    /// none, its size. The result can be passed to [`Court::parse`].
+    ///
+    /// Courts of a custom size but with obstacles or wrapping walls cannot be
+    /// described, and only their size is kept. The motion of the walls is
+    /// not part of the result.
+    pub fn spec(&self) -> String {
+        let still = Court {
+            wall_motion: None,
+            ..self.clone()
+        };
+        match PRESETS.iter().find(|preset| preset.court() == still) {
+            Some(preset) => preset.name.to_owned(),
+            None => format!("{}x{}", self.width, self.height),
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(0., 0., self.width, self.height)
+    }
+
+    /// Returns how far the top and bottom walls are into the court at
+    /// `tick`.
+    pub fn wall_offset(&self, tick: u64) -> f32 {
+        match (self.walls, self.wall_motion) {
+            (Walls::Bounce, Some(motion)) => {
+                // Never squeeze the court below the size of a racket.
+                let amplitude = motion
+                    .amplitude
+                    .min((self.height - RACKET_SIZE.1 - BALL_SIZE * 2.) * 0.5);
+                let phase = TAU * motion.frequency * tick as f32 / TICK_RATE as f32;
+                amplitude * (1. - math::cos(phase)) * 0.5
+            }
+            _ => 0.,
+        }
+    }
+}
+
+impl Default for Court {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// A named court.
+pub struct Preset {
+    pub name: &'static str,
+    pub width: f32,
+    pub height: f32,
+    pub walls: Walls,
+    pub obstacles: &'static [Rect],
+}
+
+impl Preset {
+    pub fn court(&self) -> Court {
+        Court {
+            width: self.width,
+            height: self.height,
+            walls: self.walls,
+            wall_motion: None,
+            obstacles: self.obstacles.to_vec(),
+        }
+    }
+}
+
+const fn rect(x: f32, y: f32, w: f32, h: f32) -> Rect {
+    Rect { x, y, w, h }
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "classic",
+        width: 800.,
+        height: 600.,
+        walls: Walls::Bounce,
+        obstacles: &[],
+    },
+    Preset {
+        name: "long",
+        width: 1200.,
+        height: 500.,
+        walls: Walls::Wrap,
+        obstacles: &[],
+    },
+    Preset {
+        name: "tiny",
+        width: 480.,
+        height: 360.,
+        walls: Walls::Bounce,
+        obstacles: &[],
+    },
+    Preset {
+        name: "garden",
+        width: 800.,
+        height: 600.,
+        walls: Walls::Bounce,
+        obstacles: &[
+            rect(250., 130., 40., 40.),
+            rect(510., 130., 40., 40.),
+            rect(380., 60., 40., 80.),
+            rect(250., 430., 40., 40.),
+            rect(510., 430., 40., 40.),
+            rect(380., 460., 40., 80.),
+        ],
+    },
+];
@@ -0,0 +1,162 @@
+//! Crash reports.
+//!
+//! A panic hook saves a report with the build, the panic, the match being
+//! played and its latest events before the game goes away. The next launch
+//! shows the report and offers to resume the match from its score and sets.
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    panic::{self, PanicHookInfo},
+    sync::Mutex,
+};
+
+use pong::{
+    code::{MatchCode, SaveState},
+    sim::{Event, Simulation},
+};
+
+use crate::storage::{self, Dir};
+
+/// Name of the entry where the report of a crash is saved.
+const CRASH_NAME: &str = "crash.txt";
+
+/// Name of the entry where the report of the last crash is kept once it has
+/// been shown, to attach it to bug reports.
+pub const LAST_CRASH_NAME: &str = "last-crash.txt";
+
+/// Number of events kept for the report.
+const EVENT_LOG_SIZE: usize = 32;
+
+/// What the game was doing, kept up to date for the report.
+struct Log {
+    /// Match being played, if any, and how far it went.
+    save: Option<SaveState>,
+    tick: u64,
+    /// Latest events of the match and the step they happened on.
+    events: VecDeque<(u64, Event)>,
+}
+
+static LOG: Mutex<Log> = Mutex::new(Log {
+    save: None,
+    tick: 0,
+    events: VecDeque::new(),
+});
+
+/// Report saved by a previous run that crashed.
+pub struct Report {
+    /// Panic that crashed the game.
+    pub panic: String,
+    /// Match being played when the game crashed, and how far it went.
+    pub resume: Option<SaveState>,
+}
+
+/// Returns a one-line description of the build of the game.
+pub fn build_info() -> String {
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    format!(
+        "pong {} ({}, {profile}, {} {})",
+        env!("CARGO_PKG_VERSION"),
+        env!("PONG_GIT_HASH"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+/// Installs the panic hook saving the reports. The default hook still runs
+/// afterwards.
+pub fn install() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        save_report(info);
+        default_hook(info);
+    }));
+}
+
+/// Starts logging a new match described by `code`.
+pub fn start(code: Option<&MatchCode>) {
+    let Ok(mut log) = LOG.lock() else {
+        return;
+    };
+    log.save = code.map(|code| SaveState {
+        code: code.clone(),
+        scores: (0, 0),
+        sets: (0, 0),
+    });
+    log.events.clear();
+}
+
+/// Logs the state of `sim` after a frame and the `events` that happened
+/// during it.
+pub fn record(sim: &Simulation, events: &[Event]) {
+    let Ok(mut log) = LOG.lock() else {
+        return;
+    };
+    if let Some(save) = &mut log.save {
+        save.scores = sim.scores;
+        save.sets = sim.sets;
+    }
+    log.tick = sim.tick;
+    for &event in events {
+        if log.events.len() == EVENT_LOG_SIZE {
+            log.events.pop_front();
+        }
+        log.events.push_back((sim.tick, event));
+    }
+}
+
+fn save_report(info: &PanicHookInfo) {
+    let mut report = String::new();
+    writeln!(report, "build {}", build_info()).expect("format build");
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    let location = info
+        .location()
+        .map_or_else(|| "unknown location".to_owned(), ToString::to_string);
+    // Every entry of the report takes a single line.
+    let message = message.replace('\n', " ");
+    writeln!(report, "panic {message} at {location}").expect("format panic");
+    // The panic may have happened while logging, and the lock must not be
+    // waited for.
+    if let Ok(log) = LOG.try_lock() {
+        if let Some(save) = &log.save {
+            write!(report, "{save}").expect("format save-state");
+            writeln!(report, "tick {}", log.tick).expect("format tick");
+        }
+        for (tick, event) in &log.events {
+            writeln!(report, "event {tick} {event:?}").expect("format event");
+        }
+    }
+    if let Err(err) = storage::save(Dir::Saves, CRASH_NAME, &report) {
+        eprintln!("error: save crash report: {err}");
+    }
+}
+
+/// Returns the report of the last crash, if the previous run crashed, and
+/// moves it to [`LAST_CRASH_NAME`] so it is only shown once.
+pub fn take_report() -> Option<Report> {
+    let text = storage::load(Dir::Saves, CRASH_NAME)?;
+    if let Err(err) = storage::save(Dir::Saves, LAST_CRASH_NAME, &text) {
+        eprintln!("error: keep crash report: {err}");
+    }
+    if let Err(err) = storage::remove(Dir::Saves, CRASH_NAME) {
+        eprintln!("error: remove crash report: {err}");
+    }
+
+    let panic = text
+        .lines()
+        .find_map(|line| line.strip_prefix("panic "))
+        .unwrap_or_default()
+        .to_owned();
+    Some(Report {
+        panic,
+        resume: SaveState::parse(&text),
+    })
+}
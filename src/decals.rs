@@ -0,0 +1,93 @@
+//! Marks left where the ball hit the top and bottom walls.
+//!
+//! The marks of a rally fade slowly, so the path of the ball can still be
+//! traced at the end of it, and are cleared after every point.
+use macroquad::prelude::*;
+use pong::{
+    entity::{Entity, BALL_SIZE},
+    sim::Simulation,
+};
+
+use crate::draw::FOREGROUND_COLOR;
+
+/// Time the marks take to fade away.
+const DECAL_SECS: f32 = 4.;
+const DECAL_ALPHA: f32 = 0.5;
+/// Width of the marks, and how far they reach into the court.
+const DECAL_SIZE: (f32, f32) = (BALL_SIZE * 1.5, 4.);
+/// Distance from a wall at which a ball is taken to have hit it.
+const HIT_DISTANCE: f32 = 1.;
+
+struct Decal {
+    /// Center of the mark, on the edge of the wall.
+    pos: Vec2,
+    /// Whether the mark is on the top wall, so it reaches down.
+    top: bool,
+    /// Remaining life in seconds.
+    life: f32,
+}
+
+#[derive(Default)]
+pub struct Decals {
+    decals: Vec<Decal>,
+}
+
+impl Decals {
+    /// Leaves a mark where the balls of `sim` touch the top or bottom walls,
+    /// after a bounce off a wall.
+    pub fn hit(&mut self, sim: &Simulation) {
+        let offset = sim.court.wall_offset(sim.tick);
+        for entity in &sim.entities {
+            let Entity::Ball(ball) = entity else {
+                continue;
+            };
+            let x = ball.pos.0 + BALL_SIZE * 0.5;
+            // Bounces off the goal lines leave no marks.
+            let (y, top) = if ball.pos.1 <= offset + HIT_DISTANCE {
+                (offset, true)
+            } else if ball.pos.1 + BALL_SIZE >= sim.court.height - offset - HIT_DISTANCE {
+                (sim.court.height - offset, false)
+            } else {
+                continue;
+            };
+            self.decals.push(Decal {
+                pos: vec2(x, y),
+                top,
+                life: DECAL_SECS,
+            });
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for decal in &mut self.decals {
+            decal.life -= dt;
+        }
+        self.decals.retain(|decal| decal.life > 0.);
+    }
+
+    pub fn clear(&mut self) {
+        self.decals.clear();
+    }
+
+    pub fn draw(&self) {
+        let (width, height) = DECAL_SIZE;
+        for decal in &self.decals {
+            let y = if decal.top {
+                decal.pos.y
+            } else {
+                decal.pos.y - height
+            };
+            let alpha = DECAL_ALPHA * decal.life / DECAL_SECS;
+            draw_rectangle(
+                decal.pos.x - width * 0.5,
+                y,
+                width,
+                height,
+                Color {
+                    a: alpha,
+                    ..FOREGROUND_COLOR
+                },
+            );
+        }
+    }
+}
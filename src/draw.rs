@@ -0,0 +1,302 @@
+//! Drawing of the objects on the court.
+use macroquad::prelude::*;
+
+use pong::{
+    court::Court,
+    entity::{Ball, Entity, Obstacle, Racket, Target, BALL_SIZE, RACKET_SIZE},
+    physics::BallShape,
+    sim::Side,
+};
+
+use crate::{pulses::Pulses, settings::Palette};
+
+pub const BACKGROUND_COLOR: Color = DARKGRAY;
+pub const FOREGROUND_COLOR: Color = WHITE;
+pub const WALL_COLOR: Color = GRAY;
+/// Colors of the things that belong to the left and right players.
+pub const LEFT_COLOR: Color = SKYBLUE;
+pub const RIGHT_COLOR: Color = ORANGE;
+/// Outline around the ball and the rackets, dark whatever the colors are so
+/// they stand out on bright backgrounds and at the curved edges of the CRT
+/// effect.
+const OUTLINE_COLOR: Color = BLACK;
+const OUTLINE_WIDTH: f32 = 3.;
+/// Colors of the heatmaps of where the ball went and where points were won.
+pub const HEATMAP_BALL_COLOR: Color = Color::new(0.2, 0.6, 1., 0.6);
+pub const HEATMAP_POINT_COLOR: Color = Color::new(1., 0.2, 0.2, 0.8);
+/// Colors of the retro palettes, in the order of their rows in the palette
+/// LUT of the CRT shader.
+const RETRO_PALETTES: [[u32; 4]; 3] = [
+    [0x0f380f, 0x306230, 0x8bac0f, 0x9bbc0f],
+    [0x000000, 0xff55ff, 0x55ffff, 0xffffff],
+    [0x000000, 0x6b3a00, 0xc47a00, 0xffb000],
+];
+
+/// Returns the area of the screen where `court` is shown. The court is
+/// scaled to fit the screen while keeping its aspect ratio.
+pub fn viewport(court: &Court) -> Rect {
+    let scale = (screen_width() / court.width).min(screen_height() / court.height);
+    let (width, height) = (court.width * scale, court.height * scale);
+    Rect::new(
+        (screen_width() - width) * 0.5,
+        (screen_height() - height) * 0.5,
+        width,
+        height,
+    )
+}
+
+/// Returns whether the screen is in portrait and `court` in landscape, or
+/// the other way around, which leaves the court too small to play on. This
+/// only happens on phones and tablets held the wrong way.
+pub fn is_rotated(court: &Court) -> bool {
+    (court.width > court.height) != (screen_width() > screen_height())
+}
+
+/// Covers the screen with a prompt to rotate the device.
+pub fn draw_rotate_prompt() {
+    const TEXT: &str = "ROTATE YOUR DEVICE";
+    clear_background(BLACK);
+    let font_size = (screen_width().min(screen_height()) / 12.) as u16;
+    let dims = measure_text(TEXT, None, font_size, 1.);
+    draw_text(
+        TEXT,
+        (screen_width() - dims.width) * 0.5,
+        (screen_height() - dims.height) * 0.5 + dims.offset_y,
+        font_size as f32,
+        FOREGROUND_COLOR,
+    );
+}
+
+/// Off-screen image the court is drawn on before applying the CRT effect.
+/// It matches the size of the court, so court coordinates map to pixels.
+pub struct Canvas {
+    pub size: Vec2,
+    pub target: RenderTarget,
+    pub camera: Camera2D,
+}
+
+impl Canvas {
+    pub fn new(court: &Court) -> Self {
+        Self::with_size(court.rect().size())
+    }
+
+    /// Returns a canvas of `size`, for a court of that size.
+    pub fn with_size(size: Vec2) -> Self {
+        let target = render_target(size.x as u32, size.y as u32);
+        let mut camera = Camera2D::from_display_rect(Rect::new(0., 0., size.x, size.y));
+        camera.render_target = Some(target.clone());
+        Self {
+            size,
+            target,
+            camera,
+        }
+    }
+
+    /// Returns a camera that shows `rect` of the court on the whole canvas.
+    pub fn zoomed(&self, rect: Rect) -> Camera2D {
+        let mut camera = Camera2D::from_display_rect(rect);
+        camera.render_target = Some(self.target.clone());
+        camera
+    }
+}
+
+/// Strength of every part of the CRT effect, from 0 (none) to 1 (full).
+#[derive(Clone, Copy, PartialEq)]
+pub struct Crt {
+    /// Bulge of the screen.
+    pub curvature: f32,
+    pub scanlines: f32,
+    /// Darkening of the corners.
+    pub vignette: f32,
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self {
+            curvature: 1.,
+            scanlines: 1.,
+            vignette: 1.,
+        }
+    }
+}
+
+impl Crt {
+    /// No effect at all, for images that only go through a retro palette.
+    pub const NONE: Crt = Crt {
+        curvature: 0.,
+        scanlines: 0.,
+        vignette: 0.,
+    };
+
+    /// Draws with `material`, the CRT shader, until the default material is
+    /// used again. The scanlines roll as much as `motion`, the colors are
+    /// reduced to `palette`, the image collapses into a horizontal line as
+    /// much as `collapse`, from 0 to 1, and `pulses` fade over it.
+    pub fn use_material(
+        &self,
+        material: &Material,
+        motion: f32,
+        palette: Palette,
+        collapse: f32,
+        pulses: &Pulses,
+    ) {
+        let row = match palette {
+            Palette::Off => 0.,
+            Palette::GameBoy => 1.,
+            Palette::Cga => 2.,
+            Palette::Amber => 3.,
+        };
+        material.set_uniform("Palette", row);
+        material.set_uniform("Collapse", collapse);
+        pulses.set_uniforms(material);
+        material.set_uniform("Motion", motion);
+        material.set_uniform("Curvature", self.curvature);
+        material.set_uniform("Scanlines", self.scanlines);
+        material.set_uniform("Vignette", self.vignette);
+        gl_use_material(material);
+    }
+}
+
+/// Returns the LUT of the retro palettes for the CRT shader, with a row of
+/// four pixels for every palette.
+pub fn palette_lut() -> Texture2D {
+    let bytes: Vec<u8> = RETRO_PALETTES
+        .iter()
+        .flatten()
+        .flat_map(|&rgb| {
+            let [_, r, g, b] = rgb.to_be_bytes();
+            [r, g, b, 255]
+        })
+        .collect();
+    let lut = Texture2D::from_rgba8(4, RETRO_PALETTES.len() as u16, &bytes);
+    lut.set_filter(FilterMode::Nearest);
+    lut
+}
+
+/// Draws the parts of the court taken by the top and bottom walls at
+/// `tick`, if they move.
+pub fn draw_walls(court: &Court, tick: u64) {
+    let offset = court.wall_offset(tick);
+    if offset <= 0. {
+        return;
+    }
+    draw_rectangle(0., 0., court.width, offset, WALL_COLOR);
+    draw_rectangle(0., court.height - offset, court.width, offset, WALL_COLOR);
+}
+
+/// Draws `entities`, with outlines around the ball and the rackets if
+/// `outline` is set.
+pub fn draw_entities(entities: &[Entity], outline: bool) {
+    if outline {
+        for entity in entities {
+            entity.draw_outline();
+        }
+    }
+    for entity in entities {
+        entity.draw();
+    }
+}
+
+pub trait Draw {
+    fn draw(&self);
+
+    /// Draws a high-visibility outline that the object is drawn over.
+    fn draw_outline(&self) {}
+}
+
+impl Draw for Entity {
+    fn draw(&self) {
+        match self {
+            Entity::Racket(racket) => racket.draw(),
+            Entity::Ball(ball) => ball.draw(),
+            Entity::Obstacle(obstacle) => obstacle.draw(),
+            Entity::Target(target) => target.draw(),
+        }
+    }
+
+    fn draw_outline(&self) {
+        match self {
+            Entity::Racket(racket) => racket.draw_outline(),
+            Entity::Ball(ball) => ball.draw_outline(),
+            Entity::Obstacle(_) | Entity::Target(_) => {}
+        }
+    }
+}
+
+impl Draw for Racket {
+    fn draw(&self) {
+        draw_rectangle(
+            self.pos.0,
+            self.pos.1,
+            RACKET_SIZE.0,
+            self.height,
+            FOREGROUND_COLOR,
+        );
+    }
+
+    fn draw_outline(&self) {
+        draw_rectangle(
+            self.pos.0 - OUTLINE_WIDTH,
+            self.pos.1 - OUTLINE_WIDTH,
+            RACKET_SIZE.0 + OUTLINE_WIDTH * 2.,
+            self.height + OUTLINE_WIDTH * 2.,
+            OUTLINE_COLOR,
+        );
+    }
+}
+
+impl Draw for Ball {
+    fn draw(&self) {
+        let color = match self.owner {
+            Some(Side::Left) => LEFT_COLOR,
+            Some(Side::Right) => RIGHT_COLOR,
+            None => FOREGROUND_COLOR,
+        };
+        if self.shape == BallShape::Round {
+            let center = self.center();
+            draw_circle(center.x, center.y, BALL_SIZE * 0.5, color);
+            return;
+        }
+        draw_rectangle(self.pos.0, self.pos.1, BALL_SIZE, BALL_SIZE, color);
+    }
+
+    fn draw_outline(&self) {
+        if self.shape == BallShape::Round {
+            let center = self.center();
+            draw_circle(
+                center.x,
+                center.y,
+                BALL_SIZE * 0.5 + OUTLINE_WIDTH,
+                OUTLINE_COLOR,
+            );
+            return;
+        }
+        draw_rectangle(
+            self.pos.0 - OUTLINE_WIDTH,
+            self.pos.1 - OUTLINE_WIDTH,
+            BALL_SIZE + OUTLINE_WIDTH * 2.,
+            BALL_SIZE + OUTLINE_WIDTH * 2.,
+            OUTLINE_COLOR,
+        );
+    }
+}
+
+impl Draw for Obstacle {
+    fn draw(&self) {
+        draw_rectangle(
+            self.rect.x,
+            self.rect.y,
+            self.rect.w,
+            self.rect.h,
+            FOREGROUND_COLOR,
+        );
+    }
+}
+
+impl Draw for Target {
+    fn draw(&self) {
+        let (x, y) = self.center;
+        draw_circle_lines(x, y, self.radius, 4., RIGHT_COLOR);
+        draw_circle_lines(x, y, self.radius * 0.5, 4., RIGHT_COLOR);
+    }
+}
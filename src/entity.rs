@@ -0,0 +1,411 @@
+//! Objects that live on the court.
+//!
+//! Every object implements [`Object`] and is stored in the simulation as an
+//! [`Entity`]. Adding a new kind of object only requires a new variant here
+//! and a way to draw it.
+use macroquad::math::{vec2, Rect, Vec2};
+
+use crate::{
+    court::Court,
+    physics::{BallShape, Physics, SLOW_BALL_SPEED},
+    sim::{Controls, Event, Motion, Side, TICK_SECS},
+};
+
+/// Part of the width of the court past the net over which the ball slows
+/// down for players with the slow-ball handicap.
+const SLOW_BALL_RAMP: f32 = 0.15;
+
+pub const RACKET_SIZE: (f32, f32) = (20., 100.);
+pub const RACKET_MARGIN: f32 = 40.;
+const RACKET_SPEED: f32 = 500.;
+
+pub const BALL_SIZE: f32 = 20.;
+
+/// Behavior shared by the objects on the court.
+pub trait Object {
+    /// Returns the bounding box of the object.
+    fn rect(&self) -> Rect;
+
+    /// Advances the object by one step.
+    fn update(&mut self, _court: &Court, _physics: &Physics, _controls: &Controls) {}
+
+    /// Bounces `ball` off the object if they touch, returning the resulting
+    /// event.
+    fn collide(&mut self, _physics: &Physics, _ball: &mut Ball) -> Option<Event> {
+        None
+    }
+}
+
+#[derive(Clone)]
+pub enum Entity {
+    Racket(Racket),
+    Ball(Ball),
+    Obstacle(Obstacle),
+    Target(Target),
+}
+
+impl Entity {
+    pub fn object_mut(&mut self) -> &mut dyn Object {
+        match self {
+            Entity::Racket(racket) => racket,
+            Entity::Ball(ball) => ball,
+            Entity::Obstacle(obstacle) => obstacle,
+            Entity::Target(target) => target,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Racket {
+    pub side: Side,
+    /// Whether the racket is moved with [`Controls::partner`] instead of the
+    /// controls of its side.
+    pub partner: bool,
+    pub pos: (f32, f32),
+    /// Vertical speed requested during the last step.
+    pub vel: f32,
+    pub height: f32,
+    /// Top and bottom limits of the movement of the racket.
+    pub range: (f32, f32),
+}
+
+impl Racket {
+    pub fn new(court: &Court, side: Side) -> Self {
+        let pos_x = match side {
+            Side::Left => RACKET_MARGIN,
+            Side::Right => court.width - RACKET_MARGIN - RACKET_SIZE.0,
+        };
+        let pos_y = court.height * 0.5 - RACKET_SIZE.1 * 0.5;
+        Self {
+            side,
+            partner: false,
+            pos: (pos_x, pos_y),
+            vel: 0.,
+            height: RACKET_SIZE.1,
+            range: (0., court.height),
+        }
+    }
+
+    /// Returns one of the two half-height rackets that share a side in
+    /// co-op matches. The partner defends the bottom half of the court and
+    /// the other racket the top one.
+    pub fn coop(court: &Court, side: Side, partner: bool) -> Self {
+        let half = court.height * 0.5;
+        let range = if partner {
+            (half, court.height)
+        } else {
+            (0., half)
+        };
+        let height = RACKET_SIZE.1 * 0.5;
+        Self {
+            partner,
+            pos: (
+                Self::new(court, side).pos.0,
+                (range.0 + range.1) * 0.5 - height * 0.5,
+            ),
+            height,
+            range,
+            ..Self::new(court, side)
+        }
+    }
+
+    /// Returns the half-height racket that defends the goal on `side` in
+    /// goalkeeper matches.
+    pub fn keeper(court: &Court, side: Side) -> Self {
+        let height = RACKET_SIZE.1 * 0.5;
+        let racket = Self::new(court, side);
+        Self {
+            pos: (racket.pos.0, court.height * 0.5 - height * 0.5),
+            height,
+            ..racket
+        }
+    }
+
+    /// Returns the vertical center of the racket.
+    pub fn center_y(&self) -> f32 {
+        self.pos.1 + self.height * 0.5
+    }
+}
+
+impl Racket {
+    /// Transfers part of the motion of the racket to `ball` after a bounce.
+    fn spin(&self, physics: &Physics, ball: &mut Ball) {
+        ball.dir.1 = (ball.dir.1 + self.vel / RACKET_SPEED * physics.spin)
+            .clamp(-physics.max_dir_y, physics.max_dir_y);
+    }
+}
+
+impl Object for Racket {
+    fn rect(&self) -> Rect {
+        Rect::new(self.pos.0, self.pos.1, RACKET_SIZE.0, self.height)
+    }
+
+    fn update(&mut self, _court: &Court, _physics: &Physics, controls: &Controls) {
+        let motion = match (self.partner, self.side) {
+            (true, _) => controls.partner,
+            (false, Side::Left) => controls.left,
+            (false, Side::Right) => controls.right,
+        };
+        self.vel = match motion {
+            Motion::Stop => 0.,
+            Motion::Up => -RACKET_SPEED,
+            Motion::Down => RACKET_SPEED,
+        };
+        self.pos.1 =
+            (self.pos.1 + self.vel * TICK_SECS).clamp(self.range.0, self.range.1 - self.height);
+    }
+
+    fn collide(&mut self, physics: &Physics, ball: &mut Ball) -> Option<Event> {
+        const DX: f32 = 0.1;
+
+        if ball.owner.is_some_and(|owner| owner != self.side) {
+            return None;
+        }
+
+        if ball.shape == BallShape::Round {
+            let rect = self.rect();
+            let (normal, depth) = ball.circle_hit(rect)?;
+            ball.pos.0 += normal.x * depth;
+            ball.pos.1 += normal.y * depth;
+            if normal.y == 0. {
+                // Hits on the face are aimed like with the square ball.
+                ball.dir.0 = ball.dir.0.abs() * normal.x;
+                ball.dir.1 = (ball.center().y - rect.center().y) / (rect.h * 0.5);
+            } else {
+                ball.reflect(normal);
+            }
+            self.spin(physics, ball);
+            ball.bounce(physics, physics.racket_restitution);
+            return Some(Event::RacketBounce);
+        }
+
+        let racket_rect = match self.side {
+            Side::Left => {
+                if ball.dir.0 > 0. {
+                    return None;
+                }
+                Rect::new(
+                    self.pos.0 + RACKET_SIZE.0 - DX,
+                    self.pos.1,
+                    DX * 2.,
+                    self.height,
+                )
+            }
+            Side::Right => {
+                if ball.dir.0 < 0. {
+                    return None;
+                }
+                Rect::new(self.pos.0 - DX, self.pos.1, DX * 2., self.height)
+            }
+        };
+
+        let rect = racket_rect.intersect(ball.rect())?;
+
+        ball.dir.0 = match self.side {
+            Side::Left => ball.dir.0.abs(),
+            Side::Right => -ball.dir.0.abs(),
+        };
+        ball.dir.1 = (rect.center().y - racket_rect.center().y) / (racket_rect.h * 0.5);
+        self.spin(physics, ball);
+        ball.bounce(physics, physics.racket_restitution);
+        Some(Event::RacketBounce)
+    }
+}
+
+#[derive(Clone)]
+pub struct Ball {
+    pub pos: (f32, f32),
+    pub dir: (f32, f32),
+    pub speed: f32,
+    pub shape: BallShape,
+    /// Side the ball is dedicated to, if any. Such a ball is only hit by the
+    /// racket of its side and bounces off the goal line of the other one.
+    pub owner: Option<Side>,
+}
+
+impl Ball {
+    pub fn new(court: &Court, physics: &Physics, dir: (f32, f32)) -> Self {
+        let x = court.width * 0.5 - BALL_SIZE * 0.5;
+        let y = court.height * 0.5 - BALL_SIZE * 0.5;
+        Self {
+            pos: (x, y),
+            dir,
+            speed: physics.init_speed,
+            shape: physics.ball_shape,
+            owner: None,
+        }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        vec2(self.pos.0, self.pos.1) + BALL_SIZE * 0.5
+    }
+
+    /// Returns the normal pointing from `rect` towards the round ball and
+    /// how deep the ball is into `rect`, if they touch and the ball is not
+    /// already moving away.
+    fn circle_hit(&self, rect: Rect) -> Option<(Vec2, f32)> {
+        let radius = BALL_SIZE * 0.5;
+        let center = self.center();
+        let closest = center.clamp(rect.point(), rect.point() + rect.size());
+        let offset = center - closest;
+        let dist = offset.length();
+        if dist >= radius {
+            return None;
+        }
+
+        let normal = if dist > 0. {
+            offset / dist
+        } else {
+            // The center is inside the rectangle, so push the ball out
+            // horizontally, which is where the rackets face.
+            vec2((center.x - rect.center().x).signum(), 0.)
+        };
+        if vec2(self.dir.0, self.dir.1).dot(normal) >= 0. {
+            return None;
+        }
+        Some((normal, radius - dist))
+    }
+
+    /// Returns the speed the ball travels at, which is slower than its
+    /// speed in the half of a player with the slow-ball handicap while it
+    /// goes towards them. It slows down gradually over the first
+    /// [`SLOW_BALL_RAMP`] of the width of the court past the net.
+    fn travel_speed(&self, court: &Court, physics: &Physics) -> f32 {
+        let center = court.width * 0.5;
+        let (handicap, depth) = if self.dir.0 < 0. {
+            (physics.slow_ball.0, center - self.center().x)
+        } else {
+            (physics.slow_ball.1, self.center().x - center)
+        };
+        let cap = physics.init_speed * SLOW_BALL_SPEED;
+        if !handicap || depth <= 0. || self.speed <= cap {
+            return self.speed;
+        }
+        let ramp = (depth / (court.width * SLOW_BALL_RAMP)).min(1.);
+        self.speed + (cap - self.speed) * ramp
+    }
+
+    /// Applies the restitution of the surface the ball bounced off.
+    pub fn bounce(&mut self, physics: &Physics, restitution: f32) {
+        self.speed = (self.speed * restitution).min(physics.max_speed);
+    }
+
+    /// Mirrors the direction of the ball about a surface with the given
+    /// normal.
+    fn reflect(&mut self, normal: Vec2) {
+        let dir = vec2(self.dir.0, self.dir.1);
+        let dir = dir - 2. * dir.dot(normal) * normal;
+        self.dir = (dir.x, dir.y);
+    }
+}
+
+impl Object for Ball {
+    fn rect(&self) -> Rect {
+        Rect::new(self.pos.0, self.pos.1, BALL_SIZE, BALL_SIZE)
+    }
+
+    fn update(&mut self, court: &Court, physics: &Physics, _controls: &Controls) {
+        let delta = self.travel_speed(court, physics) * TICK_SECS;
+        self.pos.0 += self.dir.0 * delta;
+        self.pos.1 += self.dir.1 * delta;
+        self.speed = (self.speed + TICK_SECS * physics.accel).min(physics.max_speed);
+    }
+}
+
+/// Static block the ball bounces off.
+#[derive(Clone)]
+pub struct Obstacle {
+    pub rect: Rect,
+}
+
+impl Obstacle {
+    pub fn new(rect: Rect) -> Self {
+        Self { rect }
+    }
+}
+
+impl Object for Obstacle {
+    fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    fn collide(&mut self, physics: &Physics, ball: &mut Ball) -> Option<Event> {
+        if ball.shape == BallShape::Round {
+            let (normal, depth) = ball.circle_hit(self.rect)?;
+            ball.pos.0 += normal.x * depth;
+            ball.pos.1 += normal.y * depth;
+            ball.reflect(normal);
+            ball.bounce(physics, physics.wall_restitution);
+            return Some(Event::WallBounce);
+        }
+
+        let overlap = self.rect.intersect(ball.rect())?;
+
+        // Push the ball out along the axis it penetrated the least and send
+        // it away from the obstacle.
+        let ball_center = ball.rect().center();
+        let center = self.rect.center();
+        if overlap.w < overlap.h {
+            let sign = (ball_center.x - center.x).signum();
+            ball.pos.0 += overlap.w * sign;
+            ball.dir.0 = ball.dir.0.abs() * sign;
+        } else {
+            let sign = (ball_center.y - center.y).signum();
+            ball.pos.1 += overlap.h * sign;
+            ball.dir.1 = ball.dir.1.abs() * sign;
+        }
+        ball.bounce(physics, physics.wall_restitution);
+        Some(Event::WallBounce)
+    }
+}
+
+/// Ring that scores when a returned ball goes through it.
+#[derive(Clone)]
+pub struct Target {
+    pub center: (f32, f32),
+    pub radius: f32,
+    /// Whether a ball went through the ring during the last step.
+    pub hit: bool,
+}
+
+impl Target {
+    pub fn new(center: (f32, f32), radius: f32) -> Self {
+        Self {
+            center,
+            radius,
+            hit: false,
+        }
+    }
+}
+
+impl Object for Target {
+    fn rect(&self) -> Rect {
+        Rect::new(
+            self.center.0 - self.radius,
+            self.center.1 - self.radius,
+            self.radius * 2.,
+            self.radius * 2.,
+        )
+    }
+
+    fn collide(&mut self, _physics: &Physics, ball: &mut Ball) -> Option<Event> {
+        // Only balls moving away from the left player count, and fast balls
+        // must not go through the ring between two steps unnoticed, so the
+        // whole path of the ball during the step is checked.
+        if ball.dir.0 <= 0. {
+            return None;
+        }
+        let end = ball.center();
+        let start = end - vec2(ball.dir.0, ball.dir.1) * ball.speed * TICK_SECS;
+        let center = vec2(self.center.0, self.center.1);
+        let path = end - start;
+        let t =
+            ((center - start).dot(path) / path.length_squared().max(f32::EPSILON)).clamp(0., 1.);
+        let closest = start + path * t;
+        if closest.distance(center) > self.radius + BALL_SIZE * 0.5 {
+            return None;
+        }
+        self.hit = true;
+        Some(Event::TargetHit)
+    }
+}
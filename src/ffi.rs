@@ -0,0 +1,193 @@
+//! C API over the headless core.
+//!
+//! Build it as a shared library with:
+//!
+//! ```text
+//! cargo rustc --lib --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! The matching declarations are in `include/pong.h`. A match is an opaque
+//! pointer returned by [`pong_new`] that must be released with
+//! [`pong_free`].
+use crate::{
+    bot::Observation,
+    court::Court,
+    entity::{Entity, BALL_SIZE},
+    physics::Physics,
+    sim::{Controls, Event, MatchState, Motion, Side, Simulation},
+};
+
+/// Sides accepted by [`pong_observe`].
+pub const PONG_LEFT: i32 = 0;
+pub const PONG_RIGHT: i32 = 1;
+
+/// Racket motions accepted by [`pong_step`].
+pub const PONG_STOP: i32 = 0;
+pub const PONG_UP: i32 = 1;
+pub const PONG_DOWN: i32 = 2;
+
+/// Event flags returned by [`pong_step`].
+pub const PONG_WALL_BOUNCE: u32 = 1 << 0;
+pub const PONG_RACKET_BOUNCE: u32 = 1 << 1;
+pub const PONG_POINT_LEFT: u32 = 1 << 2;
+pub const PONG_POINT_RIGHT: u32 = 1 << 3;
+pub const PONG_MATCH_POINT: u32 = 1 << 4;
+
+/// Snapshot of a match. Positions are the centers of the objects in court
+/// coordinates.
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct PongState {
+    pub tick: u64,
+    pub left_score: i32,
+    pub right_score: i32,
+    /// 0 while playing, 1 if the left player won, 2 if the right one did.
+    pub winner: i32,
+    pub left_y: f32,
+    pub right_y: f32,
+    pub ball_x: f32,
+    pub ball_y: f32,
+    pub ball_dir_x: f32,
+    pub ball_dir_y: f32,
+    pub ball_speed: f32,
+}
+
+/// Creates a new match on the default court.
+#[no_mangle]
+pub extern "C" fn pong_new(seed: u64) -> *mut Simulation {
+    Box::into_raw(Box::new(Simulation::new(seed)))
+}
+
+/// Creates a new match on a court of the given size. Returns null if the
+/// rackets and the ball don't fit in it.
+#[no_mangle]
+pub extern "C" fn pong_new_court(seed: u64, width: f32, height: f32) -> *mut Simulation {
+    match Court::new(width, height) {
+        Some(court) => Box::into_raw(Box::new(Simulation::with_rules(
+            court,
+            Physics::default(),
+            seed,
+        ))),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Releases a match created by [`pong_new`]. Null pointers are ignored.
+///
+/// # Safety
+///
+/// `pong` must be null or a pointer returned by [`pong_new`] that has not
+/// been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn pong_free(pong: *mut Simulation) {
+    if !pong.is_null() {
+        drop(Box::from_raw(pong));
+    }
+}
+
+/// Starts a new match with the given seed.
+///
+/// # Safety
+///
+/// `pong` must be a valid pointer returned by [`pong_new`].
+#[no_mangle]
+pub unsafe extern "C" fn pong_reset(pong: *mut Simulation, seed: u64) {
+    (*pong).reset(seed);
+}
+
+/// Advances the match by one step and returns the flags of the events that
+/// happened during it. Unknown motions stop the racket.
+///
+/// # Safety
+///
+/// `pong` must be a valid pointer returned by [`pong_new`].
+#[no_mangle]
+pub unsafe extern "C" fn pong_step(pong: *mut Simulation, left: i32, right: i32) -> u32 {
+    let sim = &mut *pong;
+    sim.step(Controls {
+        left: motion(left),
+        right: motion(right),
+        ..Default::default()
+    });
+
+    let flags = sim.events().iter().fold(0, |flags, event| {
+        flags
+            | match event {
+                Event::WallBounce => PONG_WALL_BOUNCE,
+                Event::RacketBounce => PONG_RACKET_BOUNCE,
+                Event::Point(Side::Left) => PONG_POINT_LEFT,
+                Event::Point(Side::Right) => PONG_POINT_RIGHT,
+                Event::MatchPoint(_) => PONG_MATCH_POINT,
+                Event::SetWon(_) | Event::Winner(_) | Event::TargetHit => 0,
+            }
+    });
+    sim.clear_events();
+    flags
+}
+
+/// Writes a snapshot of the match into `state`.
+///
+/// # Safety
+///
+/// `pong` must be a valid pointer returned by [`pong_new`] and `state` must
+/// point to writable memory for a [`PongState`].
+#[no_mangle]
+pub unsafe extern "C" fn pong_state(pong: *const Simulation, state: *mut PongState) {
+    let sim = &*pong;
+
+    let mut s = PongState {
+        tick: sim.tick,
+        left_score: sim.scores.0,
+        right_score: sim.scores.1,
+        winner: match sim.state {
+            MatchState::Playing => 0,
+            MatchState::Finished(Side::Left) => 1,
+            MatchState::Finished(Side::Right) => 2,
+        },
+        ..Default::default()
+    };
+    for entity in &sim.entities {
+        match entity {
+            Entity::Racket(racket) => {
+                let y = racket.center_y();
+                match racket.side {
+                    Side::Left => s.left_y = y,
+                    Side::Right => s.right_y = y,
+                }
+            }
+            Entity::Ball(ball) => {
+                s.ball_x = ball.pos.0 + BALL_SIZE * 0.5;
+                s.ball_y = ball.pos.1 + BALL_SIZE * 0.5;
+                s.ball_dir_x = ball.dir.0;
+                s.ball_dir_y = ball.dir.1;
+                s.ball_speed = ball.speed;
+            }
+            Entity::Obstacle(_) | Entity::Target(_) => {}
+        }
+    }
+    state.write(s);
+}
+
+/// Writes into `obs` what a bot playing on `side` would see of the match.
+/// Unknown sides are treated as the left one.
+///
+/// # Safety
+///
+/// `pong` must be a valid pointer returned by [`pong_new`] and `obs` must
+/// point to writable memory for an [`Observation`].
+#[no_mangle]
+pub unsafe extern "C" fn pong_observe(pong: *const Simulation, side: i32, obs: *mut Observation) {
+    let side = match side {
+        PONG_RIGHT => Side::Right,
+        _ => Side::Left,
+    };
+    obs.write(Observation::new(&*pong, side));
+}
+
+fn motion(motion: i32) -> Motion {
+    match motion {
+        PONG_UP => Motion::Up,
+        PONG_DOWN => Motion::Down,
+        _ => Motion::Stop,
+    }
+}
@@ -0,0 +1,115 @@
+//! Ghost of the personal best.
+//!
+//! When a player beats a bot faster than ever before, the trace of the match
+//! is saved for its speedrun category. Later matches in the same category
+//! replay the motions of the player's racket from that trace and draw it
+//! translucent next to the live one, so the player can race against their
+//! best run.
+use macroquad::prelude::*;
+use pong::{
+    entity::{Object, Racket, RACKET_SIZE},
+    sim::{Controls, Side},
+    trace::Trace,
+};
+
+use crate::{
+    draw::FOREGROUND_COLOR,
+    storage::{self, Dir},
+};
+
+const GHOST_ALPHA: f32 = 0.3;
+
+/// Racket replaying the best run of the current category.
+pub struct Ghost {
+    trace: Option<Trace>,
+    side: Side,
+    racket: Option<Racket>,
+    /// Number of steps of the trace replayed so far.
+    tick: u64,
+}
+
+impl Ghost {
+    pub fn new() -> Self {
+        Self {
+            trace: None,
+            side: Side::Left,
+            racket: None,
+            tick: 0,
+        }
+    }
+
+    /// Loads the best run of `category` for a new match where the player
+    /// is on `side`. There is no ghost without a category.
+    pub fn start(&mut self, category: Option<&str>, side: Option<Side>) {
+        self.trace = None;
+        self.racket = None;
+        self.tick = 0;
+        let (Some(category), Some(side)) = (category, side) else {
+            return;
+        };
+        let Some(contents) = storage::load(Dir::Replays, &entry_name(category)) else {
+            return;
+        };
+        match contents.parse::<Trace>() {
+            Ok(trace) => {
+                self.racket = Some(Racket::new(&trace.court, side));
+                self.side = side;
+                self.trace = Some(trace);
+            }
+            Err(err) => eprintln!("error: parse ghost: {err}"),
+        }
+    }
+
+    /// Replays the trace up to `tick` of the live match.
+    pub fn update(&mut self, tick: u64) {
+        let (Some(trace), Some(racket)) = (&self.trace, &mut self.racket) else {
+            return;
+        };
+        while self.tick < tick {
+            let Some(controls) = trace.controls().get(self.tick as usize) else {
+                break;
+            };
+            // Only the racket of the player is replayed.
+            let controls = match self.side {
+                Side::Left => Controls {
+                    left: controls.left,
+                    ..Default::default()
+                },
+                Side::Right => Controls {
+                    right: controls.right,
+                    ..Default::default()
+                },
+            };
+            racket.update(&trace.court, &trace.physics, &controls);
+            self.tick += 1;
+        }
+    }
+
+    /// Saves `trace`, a run of the player on `category` that has just set a
+    /// personal best.
+    pub fn save(category: &str, trace: &Trace) {
+        if let Err(err) = storage::save(Dir::Replays, &entry_name(category), &trace.to_string()) {
+            eprintln!("error: save ghost: {err}");
+        }
+    }
+
+    pub fn draw(&self) {
+        let Some(racket) = &self.racket else {
+            return;
+        };
+        let mut color = FOREGROUND_COLOR;
+        color.a = GHOST_ALPHA;
+        draw_rectangle(
+            racket.pos.0,
+            racket.pos.1,
+            RACKET_SIZE.0,
+            racket.height,
+            color,
+        );
+    }
+}
+
+/// Returns the name of the entry where the ghost of `category` is kept.
+fn entry_name(category: &str) -> String {
+    format!("ghost-{}.trace", category.replace(' ', "-"))
+}
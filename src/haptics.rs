@@ -0,0 +1,31 @@
+//! Vibration of the device on racket hits and points.
+//!
+//! Only the web version vibrates, through the Vibration API of the browser,
+//! which phones support. Elsewhere the pulses do nothing.
+use pong::sim::Event;
+
+/// Length of the pulses for racket hits and points, in milliseconds.
+const HIT_MILLIS: u32 = 15;
+const POINT_MILLIS: u32 = 60;
+
+/// Vibrates the device for `event`, if it calls for it.
+pub fn play(event: Event) {
+    match event {
+        Event::RacketBounce => vibrate(HIT_MILLIS),
+        Event::Point(_) | Event::SetWon(_) | Event::Winner(_) => vibrate(POINT_MILLIS),
+        Event::WallBounce | Event::TargetHit | Event::MatchPoint(_) => {}
+    }
+}
+
+#[cfg(target_family = "wasm")]
+fn vibrate(millis: u32) {
+    extern "C" {
+        fn pong_vibrate(millis: u32);
+    }
+
+    // SAFETY: The function takes no pointers.
+    unsafe { pong_vibrate(millis) }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn vibrate(_millis: u32) {}
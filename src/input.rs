@@ -0,0 +1,465 @@
+//! Player input.
+use std::fmt;
+
+use macroquad::prelude::*;
+
+use pong::{
+    court::Court,
+    sim::{Motion, Side, Simulation},
+};
+
+use crate::draw;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Input {
+    Up(Side),
+    Down(Side),
+    /// The player on the side is ready to end the warmup.
+    Ready(Side),
+    Pause,
+    Heatmap,
+    ExportJson,
+    ExportCsv,
+    ExportEvents,
+    Timer,
+    Serve,
+    Diagnostics,
+    OpenFolder,
+    ExportData,
+    ImportData,
+    SlowMotion,
+    Follow,
+    StepFrame,
+    Cycle,
+    Delete,
+    Replays,
+    Rewind,
+    Photo,
+    MainMenu,
+    /// The key combination hiding or showing the HUD was pressed.
+    ToggleHud,
+    /// The game was hidden or cannot be played as the screen is, so the
+    /// matches must be paused.
+    Suspend,
+    Quit,
+    Unknown,
+}
+
+/// Side of the button pausing the matches on touch screens, in court units.
+const PAUSE_BUTTON_SIZE: f32 = 60.;
+
+/// Keys that can be bound to actions, besides the modifiers.
+const BINDABLE_KEYS: [(&str, KeyCode); 48] = [
+    ("a", KeyCode::A),
+    ("b", KeyCode::B),
+    ("c", KeyCode::C),
+    ("d", KeyCode::D),
+    ("e", KeyCode::E),
+    ("f", KeyCode::F),
+    ("g", KeyCode::G),
+    ("h", KeyCode::H),
+    ("i", KeyCode::I),
+    ("j", KeyCode::J),
+    ("k", KeyCode::K),
+    ("l", KeyCode::L),
+    ("m", KeyCode::M),
+    ("n", KeyCode::N),
+    ("o", KeyCode::O),
+    ("p", KeyCode::P),
+    ("q", KeyCode::Q),
+    ("r", KeyCode::R),
+    ("s", KeyCode::S),
+    ("t", KeyCode::T),
+    ("u", KeyCode::U),
+    ("v", KeyCode::V),
+    ("w", KeyCode::W),
+    ("x", KeyCode::X),
+    ("y", KeyCode::Y),
+    ("z", KeyCode::Z),
+    ("0", KeyCode::Key0),
+    ("1", KeyCode::Key1),
+    ("2", KeyCode::Key2),
+    ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6),
+    ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8),
+    ("9", KeyCode::Key9),
+    ("f1", KeyCode::F1),
+    ("f2", KeyCode::F2),
+    ("f3", KeyCode::F3),
+    ("f4", KeyCode::F4),
+    ("f5", KeyCode::F5),
+    ("f6", KeyCode::F6),
+    ("f7", KeyCode::F7),
+    ("f8", KeyCode::F8),
+    ("f9", KeyCode::F9),
+    ("f10", KeyCode::F10),
+    ("f11", KeyCode::F11),
+    ("f12", KeyCode::F12),
+];
+
+/// Key pressed while holding some modifiers, like `ctrl+h`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyCombo {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub key: KeyCode,
+}
+
+impl KeyCombo {
+    /// Parses the modifiers and a key separated by `+`, like `ctrl+shift+f1`.
+    /// The key is a letter, a digit or a function key.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts: Vec<_> = s.split('+').collect();
+        let key = parts.pop()?;
+        let (_, key) = BINDABLE_KEYS.iter().find(|(name, _)| *name == key)?;
+        let mut combo = KeyCombo {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            key: *key,
+        };
+        for modifier in parts {
+            match modifier {
+                "ctrl" => combo.ctrl = true,
+                "alt" => combo.alt = true,
+                "shift" => combo.shift = true,
+                _ => return None,
+            }
+        }
+        Some(combo)
+    }
+
+    /// Returns whether the combination was pressed during the current
+    /// frame. Other modifiers may be held too.
+    fn is_pressed(self) -> bool {
+        let down = |left, right| is_key_down(left) || is_key_down(right);
+        is_key_pressed(self.key)
+            && (!self.ctrl || down(KeyCode::LeftControl, KeyCode::RightControl))
+            && (!self.alt || down(KeyCode::LeftAlt, KeyCode::RightAlt))
+            && (!self.shift || down(KeyCode::LeftShift, KeyCode::RightShift))
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.ctrl, "ctrl"),
+            (self.alt, "alt"),
+            (self.shift, "shift"),
+        ] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        let (name, _) = BINDABLE_KEYS
+            .iter()
+            .find(|(_, key)| *key == self.key)
+            .expect("bindable key");
+        write!(f, "{name}")
+    }
+}
+
+/// Pair of keys moving a racket.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Device {
+    /// The W and S keys.
+    Wasd,
+    /// The up and down arrow keys.
+    Arrows,
+}
+
+impl Device {
+    pub const ALL: [Device; 2] = [Device::Wasd, Device::Arrows];
+
+    /// Returns the keys moving the racket up and down.
+    fn keys(self) -> (KeyCode, KeyCode) {
+        match self {
+            Device::Wasd => (KeyCode::W, KeyCode::S),
+            Device::Arrows => (KeyCode::Up, KeyCode::Down),
+        }
+    }
+
+    /// Returns the key telling the player is ready, the one next to the
+    /// others pointing to the net.
+    fn ready_key(self) -> KeyCode {
+        match self {
+            Device::Wasd => KeyCode::D,
+            Device::Arrows => KeyCode::Left,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Device::Wasd => "W S",
+            Device::Arrows => "UP DOWN",
+        }
+    }
+
+    pub fn ready_name(self) -> &'static str {
+        match self {
+            Device::Wasd => "D",
+            Device::Arrows => "LEFT",
+        }
+    }
+}
+
+/// Device moving the racket of every side. Touches always move the racket
+/// on the half of the screen touched.
+#[derive(Clone, Copy)]
+pub struct Devices {
+    pub left: Device,
+    pub right: Device,
+}
+
+impl Default for Devices {
+    fn default() -> Self {
+        Self {
+            left: Device::Wasd,
+            right: Device::Arrows,
+        }
+    }
+}
+
+impl Devices {
+    /// Returns the side whose racket `device` moves, if any.
+    fn side(&self, device: Device) -> Option<Side> {
+        if self.left == device {
+            Some(Side::Left)
+        } else if self.right == device {
+            Some(Side::Right)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the first device with a key pressed during the current frame.
+pub fn device_pressed() -> Option<Device> {
+    Device::ALL.into_iter().find(|device| {
+        let (up, down) = device.keys();
+        is_key_pressed(up) || is_key_pressed(down)
+    })
+}
+
+/// Way the players control the game.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scheme {
+    Keyboard,
+    Touch,
+}
+
+/// Returns the scheme used during the current frame, if the players did
+/// anything.
+pub fn scheme_used() -> Option<Scheme> {
+    if !touches().is_empty() {
+        Some(Scheme::Touch)
+    } else if get_last_key_pressed().is_some() {
+        Some(Scheme::Keyboard)
+    } else {
+        None
+    }
+}
+
+/// Returns where the screen was tapped during the current frame, relative to
+/// its size.
+pub fn tapped() -> Option<Vec2> {
+    touches()
+        .into_iter()
+        .find(|touch| touch.phase == TouchPhase::Started)
+        .map(|touch| touch.position / vec2(screen_width(), screen_height()))
+}
+
+/// Returns where `court` was tapped during the current frame, in court
+/// units.
+pub fn tapped_court(court: &Court) -> Option<Vec2> {
+    let viewport = draw::viewport(court);
+    tapped().map(|pos| {
+        (pos * vec2(screen_width(), screen_height()) - viewport.point()) * court.rect().size()
+            / viewport.size()
+    })
+}
+
+/// Returns the area of the court tapped to pause the matches on touch
+/// screens, which have no pause key. It is in the top left corner, away from
+/// the score and the time left.
+pub fn pause_button() -> Rect {
+    Rect::new(0., 0., PAUSE_BUTTON_SIZE, PAUSE_BUTTON_SIZE)
+}
+
+/// Replaces the contents of `inputs` with the inputs of the current frame.
+/// Keys move the racket of the side their device is assigned to in
+/// `devices`, touches are interpreted relative to the rackets in `sim` and
+/// `hud_key` hides or shows the HUD.
+pub fn read_inputs(
+    inputs: &mut Vec<Input>,
+    devices: &Devices,
+    sim: &Simulation,
+    hud_key: KeyCombo,
+) {
+    inputs.clear();
+
+    for key in get_keys_down() {
+        match key {
+            #[cfg(not(target_family = "wasm"))]
+            KeyCode::Q => inputs.push(Input::Quit),
+
+            // Handled below.
+            KeyCode::W | KeyCode::S | KeyCode::Up | KeyCode::Down => {}
+
+            // Handled below, so holding them doesn't count.
+            KeyCode::P
+            | KeyCode::Escape
+            | KeyCode::H
+            | KeyCode::J
+            | KeyCode::C
+            | KeyCode::L
+            | KeyCode::T
+            | KeyCode::O
+            | KeyCode::X
+            | KeyCode::I
+            | KeyCode::M
+            | KeyCode::Z
+            | KeyCode::Period
+            | KeyCode::Tab
+            | KeyCode::Delete
+            | KeyCode::Backspace
+            | KeyCode::R
+            | KeyCode::B
+            | KeyCode::F
+            | KeyCode::D
+            | KeyCode::N
+            | KeyCode::Left
+            | KeyCode::Space => {}
+
+            _ => inputs.push(Input::Unknown),
+        }
+    }
+
+    for device in Device::ALL {
+        let Some(side) = devices.side(device) else {
+            continue;
+        };
+        let (up, down) = device.keys();
+        if is_key_down(up) {
+            inputs.push(Input::Up(side));
+        }
+        if is_key_down(down) {
+            inputs.push(Input::Down(side));
+        }
+        if is_key_pressed(device.ready_key()) {
+            inputs.push(Input::Ready(side));
+        }
+    }
+
+    if is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape) {
+        inputs.push(Input::Pause);
+    }
+    if is_key_pressed(KeyCode::H) {
+        inputs.push(Input::Heatmap);
+    }
+    if is_key_pressed(KeyCode::J) {
+        inputs.push(Input::ExportJson);
+    }
+    if is_key_pressed(KeyCode::C) {
+        inputs.push(Input::ExportCsv);
+    }
+    if is_key_pressed(KeyCode::L) {
+        inputs.push(Input::ExportEvents);
+    }
+    if is_key_pressed(KeyCode::O) {
+        inputs.push(Input::OpenFolder);
+    }
+    if is_key_pressed(KeyCode::X) {
+        inputs.push(Input::ExportData);
+    }
+    if is_key_pressed(KeyCode::I) {
+        inputs.push(Input::ImportData);
+    }
+    if is_key_pressed(KeyCode::M) {
+        inputs.push(Input::SlowMotion);
+    }
+    if is_key_pressed(KeyCode::Z) {
+        inputs.push(Input::Follow);
+    }
+    if is_key_pressed(KeyCode::Period) {
+        inputs.push(Input::StepFrame);
+    }
+    if is_key_pressed(KeyCode::Tab) {
+        inputs.push(Input::Cycle);
+    }
+    if is_key_pressed(KeyCode::Delete) || is_key_pressed(KeyCode::Backspace) {
+        inputs.push(Input::Delete);
+    }
+    if is_key_pressed(KeyCode::R) {
+        inputs.push(Input::Replays);
+    }
+    if is_key_pressed(KeyCode::F) {
+        inputs.push(Input::Photo);
+    }
+    if is_key_pressed(KeyCode::N) {
+        inputs.push(Input::MainMenu);
+    }
+    if is_key_down(KeyCode::B) {
+        inputs.push(Input::Rewind);
+    }
+    if is_key_pressed(KeyCode::T) {
+        inputs.push(Input::Timer);
+    }
+    if is_key_pressed(KeyCode::Space) {
+        inputs.push(Input::Serve);
+    }
+    let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+    let alt = is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt);
+    if ctrl && alt && is_key_pressed(KeyCode::D) {
+        inputs.push(Input::Diagnostics);
+    }
+    if hud_key.is_pressed() {
+        inputs.push(Input::ToggleHud);
+    }
+
+    let viewport = draw::viewport(&sim.court);
+    let pause_button = pause_button();
+    for touch in touches() {
+        let touch_pos =
+            (touch.position - viewport.point()) * sim.court.rect().size() / viewport.size();
+        // Touches on the pause button neither serve nor move the rackets.
+        if pause_button.contains(touch_pos) {
+            if touch.phase == TouchPhase::Started {
+                inputs.push(Input::Pause);
+            }
+            continue;
+        }
+        if touch.phase == TouchPhase::Started {
+            inputs.push(Input::Serve);
+        }
+        let side = if touch.position.x < screen_width() * 0.5 {
+            Side::Left
+        } else {
+            Side::Right
+        };
+        let Some(racket) = sim.racket(side) else {
+            continue;
+        };
+        if touch_pos.y < racket.pos.1 + racket.height * 0.25 {
+            inputs.push(Input::Up(side));
+        } else if touch_pos.y > racket.pos.1 + racket.height * 0.75 {
+            inputs.push(Input::Down(side));
+        }
+    }
+}
+
+/// Returns the racket movement requested for `side`.
+pub fn motion(inputs: &[Input], side: Side) -> Motion {
+    match (
+        inputs.contains(&Input::Up(side)),
+        inputs.contains(&Input::Down(side)),
+    ) {
+        (true, false) => Motion::Up,
+        (false, true) => Motion::Down,
+        _ => Motion::Stop,
+    }
+}
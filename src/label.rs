@@ -0,0 +1,69 @@
+use macroquad::prelude::*;
+
+use crate::draw::FOREGROUND_COLOR;
+
+/// Centered text whose layout is only measured when its contents change.
+pub struct Label {
+    text: String,
+    font_size: u16,
+    color: Color,
+    dims: TextDimensions,
+}
+
+impl Label {
+    pub fn new(text: &str, font_size: u16) -> Self {
+        Self {
+            text: text.to_owned(),
+            font_size,
+            color: FOREGROUND_COLOR,
+            dims: measure_text(text, None, font_size, 1.),
+        }
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        if self.text == text {
+            return;
+        }
+        self.text.clear();
+        self.text.push_str(text);
+        self.dims = measure_text(&self.text, None, self.font_size, 1.);
+    }
+
+    pub fn set_font_size(&mut self, font_size: u16) {
+        if self.font_size == font_size {
+            return;
+        }
+        self.font_size = font_size;
+        self.dims = measure_text(&self.text, None, self.font_size, 1.);
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    pub fn width(&self) -> f32 {
+        self.dims.width
+    }
+
+    /// Draws the label centered at (`x`, `y`).
+    pub fn draw_center(&self, x: f32, y: f32) {
+        draw_text(
+            &self.text,
+            x - self.dims.width * 0.5,
+            y - self.dims.height * 0.5 + self.dims.offset_y,
+            self.font_size as f32,
+            self.color,
+        );
+    }
+
+    /// Draws the label starting at `x` and centered vertically at `y`.
+    pub fn draw_left(&self, x: f32, y: f32) {
+        draw_text(
+            &self.text,
+            x,
+            y - self.dims.height * 0.5 + self.dims.offset_y,
+            self.font_size as f32,
+            self.color,
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Audio latency compensation.
+//!
+//! Sounds reach the players some time after they are played, which is
+//! noticeable on the web and with wireless headphones. To make up for it, the
+//! sounds of a match are played ahead of time: every frame a copy of the
+//! simulation is stepped ahead by the latency with the current controls, and
+//! the sounds of the events found are played right away. Events the
+//! prediction missed are still heard when they happen, late.
+use std::collections::VecDeque;
+
+use pong::sim::{Controls, Event, Simulation, TICK_SECS};
+
+use crate::audio::Sounds;
+
+/// Highest latency that can be compensated, in seconds.
+pub const MAX_LATENCY_SECS: f32 = 0.4;
+
+/// How many steps apart a predicted event and the actual one can happen and
+/// still be the same event.
+const MATCH_TICKS: u64 = 6;
+
+/// Events played ahead of time.
+#[derive(Default)]
+pub struct Lookahead {
+    /// Step the simulation has been predicted up to.
+    predicted_until: u64,
+    /// Step of the simulation on the previous prediction.
+    last_tick: u64,
+    /// Events whose sound has been played, and the step they were predicted
+    /// to happen on.
+    played: VecDeque<(u64, Event)>,
+}
+
+impl Lookahead {
+    /// Plays the sounds of the events predicted to happen within `latency`
+    /// seconds of game time after `sim` if `controls` are kept.
+    pub fn predict(&mut self, sim: &Simulation, controls: Controls, latency: f32, sounds: &Sounds) {
+        // A new match starts over.
+        if sim.tick < self.last_tick {
+            *self = Self::default();
+        }
+        self.last_tick = sim.tick;
+
+        let steps = (latency.min(MAX_LATENCY_SECS) / TICK_SECS).round() as u64;
+        if steps == 0 {
+            return;
+        }
+        let mut ahead = sim.clone();
+        ahead.clear_events();
+        for _ in 0..steps {
+            ahead.step(controls);
+            if ahead.tick > self.predicted_until {
+                for &event in ahead.events() {
+                    if sounds.play(event, &ahead) {
+                        self.played.push_back((ahead.tick, event));
+                    }
+                }
+            }
+            // The next serve may be held by a countdown.
+            let point = ahead
+                .events()
+                .iter()
+                .any(|event| matches!(event, Event::Point(_)));
+            ahead.clear_events();
+            if point {
+                break;
+            }
+        }
+        self.predicted_until = self.predicted_until.max(ahead.tick);
+    }
+
+    /// Returns whether the sound of `event`, which has just happened by step
+    /// `tick`, must still be played because it was not predicted.
+    pub fn is_pending(&mut self, event: Event, tick: u64) -> bool {
+        while self
+            .played
+            .front()
+            .is_some_and(|&(played, _)| played + MATCH_TICKS < tick)
+        {
+            self.played.pop_front();
+        }
+        let found = self
+            .played
+            .iter()
+            .position(|&(played, other)| other == event && played.abs_diff(tick) <= MATCH_TICKS);
+        match found {
+            Some(pos) => {
+                self.played.remove(pos);
+                false
+            }
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,17 @@
+//! Headless core of the game: the simulation and everything needed to drive
+//! and record it, without depending on a window.
+pub mod archive;
+pub mod bot;
+pub mod code;
+pub mod court;
+pub mod entity;
+pub mod math;
+pub mod physics;
+pub mod rules;
+pub mod runner;
+pub mod script;
+pub mod sim;
+pub mod trace;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
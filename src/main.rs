@@ -1,532 +1,813 @@
 //! The classic table tennis–themed video game.
-use std::fmt;
-
-use macroquad::{
-    audio::{load_sound_from_bytes, play_sound_once, Sound},
-    prelude::*,
+mod assets;
+mod audio;
+#[cfg(debug_assertions)]
+mod bot_debug;
+mod camera;
+mod crash;
+mod decals;
+mod draw;
+mod ghost;
+mod haptics;
+mod input;
+mod label;
+mod latency;
+mod menu;
+mod momentum;
+#[cfg(not(target_family = "wasm"))]
+mod overlay;
+mod page;
+mod particles;
+mod party;
+mod power;
+mod prompt;
+mod pulses;
+mod render;
+mod replay;
+mod replays;
+mod rulesets;
+mod settings;
+#[cfg(not(target_family = "wasm"))]
+mod site;
+mod speedrun;
+mod state;
+mod stats;
+mod storage;
+mod timeline;
+mod tournament;
+mod trail;
+mod vision;
+
+use std::{collections::VecDeque, path::PathBuf};
+
+use macroquad::{miniquad, miniquad::conf::Platform, prelude::*, Window};
+
+use assets::Assets;
+use draw::{Canvas, Crt, BACKGROUND_COLOR};
+use input::{Devices, Input, Scheme};
+use party::Party;
+use pong::{
+    bot,
+    code::MatchCode,
+    court::{self, Court, WallMotion},
+    physics::{self, BallShape, Physics},
+    rules::{self, Rules},
+    runner::{self, Runner},
+    script::InputScript,
+    sim::{Lineup, Service, Side, Simulation},
+    trace::Trace,
+};
+use power::PowerOff;
+use pulses::Pulses;
+use settings::{Autosave, Effect, Palette, Settings};
+use speedrun::Speedrun;
+use state::{
+    Calibration, Context, Crashed, Diagnostics, GameState, MainMenu, Playback, ReplayBrowser,
+    RulesEditor, Setup, StateStack, Summary, Tutorial,
 };
+use stats::History;
 
 const WINDOW_WIDTH: f32 = 800.;
 const WINDOW_HEIGHT: f32 = 600.;
 
-const BACKGROUND_COLOR: Color = DARKGRAY;
-const FOREGROUND_COLOR: Color = WHITE;
-
-const RACKET_SIZE: (f32, f32) = (20., 100.);
-const RACKET_MARGIN: f32 = 40.;
-const RACKET_SPEED: f32 = 500.;
-
-const BALL_SIZE: f32 = 20.;
-const BALL_INIT_SPEED: f32 = 150.;
-const BALL_ACCEL: f32 = 10.;
-
-const WIN_SCORE: i32 = 5;
-const WIN_SCREEN_SECS: f64 = 1.;
-
-const WALL_WAV_BYTES: &[u8] = include_bytes!("../assets/sounds/wall.wav");
-const RACKET_WAV_BYTES: &[u8] = include_bytes!("../assets/sounds/racket.wav");
-const POINT_WAV_BYTES: &[u8] = include_bytes!("../assets/sounds/point.wav");
-
-#[derive(Clone, Copy, PartialEq)]
-enum Side {
-    Left,
-    Right,
+const IDLE_FPS: u32 = 10;
+
+/// Number of frames the time between frames is averaged over.
+const PACING_FRAMES: usize = 8;
+
+/// How far the time between two frames can be from the average and still be
+/// smoothed, relative to the average.
+const PACING_TOLERANCE: f32 = 0.25;
+
+/// Options choosing what to play, which skip the main menu.
+const MATCH_OPTIONS: &[&str] = &[
+    "--left-bot",
+    "--right-bot",
+    "--coop",
+    "--goalkeeper",
+    "--juggler",
+    "--targets",
+    "--tutorial",
+    "--mirror",
+    "--code",
+    "--inputs",
+    "--party",
+];
+
+/// Bot played against in co-op matches unless another one is chosen.
+const COOP_BOT: &str = "angler";
+
+/// Bot played against in the tutorial unless another one is chosen.
+const TUTORIAL_BOT: &str = "lazy";
+
+/// Command line options.
+struct Args {
+    max_fps: Option<u32>,
+    sim_thread: bool,
+    assets: Option<PathBuf>,
+    left_bot: Option<String>,
+    right_bot: Option<String>,
+    tournament: bool,
+    seeds: u64,
+    record: Option<PathBuf>,
+    script: Option<InputScript>,
+    record_inputs: Option<PathBuf>,
+    court: Court,
+    physics: Physics,
+    lineup: Lineup,
+    rules: Rules,
+    tutorial: bool,
+    /// Recorded match to play back instead of playing.
+    replay: Option<Trace>,
+    /// Recorded match to render as images, and the directory they are saved
+    /// in.
+    render_replay: Option<(Trace, PathBuf)>,
+    /// Whether the audio latency is measured before playing.
+    calibrate_audio: bool,
+    /// Whether to start in the replay browser.
+    replays: bool,
+    /// Whether the first match starts with a warmup.
+    warmup: bool,
+    /// Whether the rules are chosen before the first match.
+    edit_rules: bool,
+    /// Seed of the first match, when replicating a shared code.
+    seed: Option<u64>,
+    /// Port the scoreboard for streams is served on.
+    overlay: Option<u16>,
+    /// Directory the files of the installable web version are written to.
+    write_site: Option<PathBuf>,
+    /// Players taking turns in party mode.
+    party: Option<Party>,
+    /// Whether the right racket mirrors the left one.
+    mirror: bool,
+    /// Whether the main menu is shown before the first match.
+    menu: bool,
 }
 
-impl Side {
-    fn toggle(self) -> Self {
-        match self {
-            Side::Left => Side::Right,
-            Side::Right => Side::Left,
-        }
-    }
-}
+impl Args {
+    fn parse() -> Self {
+        let mut args = Args {
+            max_fps: None,
+            sim_thread: cfg!(not(target_family = "wasm")),
+            assets: None,
+            left_bot: None,
+            right_bot: None,
+            tournament: false,
+            seeds: 100,
+            record: None,
+            script: None,
+            record_inputs: None,
+            court: Court::DEFAULT,
+            physics: Physics::default(),
+            lineup: Lineup::Single,
+            rules: Rules::CLASSIC,
+            tutorial: false,
+            replay: None,
+            render_replay: None,
+            calibrate_audio: false,
+            replays: false,
+            warmup: false,
+            edit_rules: false,
+            seed: None,
+            overlay: None,
+            write_site: None,
+            party: None,
+            mirror: false,
+            menu: true,
+        };
 
-impl fmt::Display for Side {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Side::Left => write!(f, "LEFT"),
-            Side::Right => write!(f, "RIGHT"),
+        // The shape of the ball overrides the one of the physics preset.
+        let mut ball_shape = None;
+        let mut slow_ball = None;
+        let mut wall_motion = None;
+        // The service overrides the one of the rules.
+        let mut service = None;
+        let mut code = None;
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            if MATCH_OPTIONS.contains(&arg.as_str()) {
+                args.menu = false;
+            }
+            match arg.as_str() {
+                "--max-fps" => {
+                    let fps = iter
+                        .next()
+                        .and_then(|fps| fps.parse().ok())
+                        .filter(|&fps| fps > 0)
+                        .unwrap_or_else(|| usage());
+                    args.max_fps = Some(fps);
+                }
+                "--no-sim-thread" => args.sim_thread = false,
+                "--left-bot" => args.left_bot = Some(bot_name(iter.next())),
+                "--right-bot" => args.right_bot = Some(bot_name(iter.next())),
+                "--coop" => args.lineup = Lineup::Coop,
+                "--goalkeeper" => args.lineup = Lineup::Goalkeeper,
+                "--juggler" => args.lineup = Lineup::Juggler,
+                "--targets" => args.lineup = Lineup::Targets,
+                "--tutorial" => args.tutorial = true,
+                "--mirror" => args.mirror = true,
+                "--calibrate-audio" => args.calibrate_audio = true,
+                "--warmup" => args.warmup = true,
+                "--edit-rules" => args.edit_rules = true,
+                "--replays" => args.replays = true,
+                "--tournament" => args.tournament = true,
+                "--seeds" => {
+                    args.seeds = iter
+                        .next()
+                        .and_then(|seeds| seeds.parse().ok())
+                        .unwrap_or_else(|| usage());
+                }
+                "--rules" => {
+                    args.rules = iter
+                        .next()
+                        .and_then(|name| rulesets::find(&name))
+                        .unwrap_or_else(|| usage());
+                }
+                "--service" => {
+                    service = iter
+                        .next()
+                        .and_then(|name| Service::parse(&name))
+                        .or_else(|| usage());
+                }
+                "--court" => {
+                    args.court = iter
+                        .next()
+                        .and_then(|spec| Court::parse(&spec))
+                        .unwrap_or_else(|| usage());
+                }
+                "--moving-walls" => {
+                    wall_motion = iter
+                        .next()
+                        .and_then(|motion| WallMotion::parse(&motion))
+                        .or_else(|| usage());
+                }
+                "--physics" => {
+                    args.physics = iter
+                        .next()
+                        .and_then(|name| Physics::parse(&name))
+                        .unwrap_or_else(|| usage());
+                }
+                "--ball" => {
+                    ball_shape = iter
+                        .next()
+                        .and_then(|shape| BallShape::parse(&shape))
+                        .or_else(|| usage());
+                }
+                "--slow-ball" => {
+                    slow_ball = iter
+                        .next()
+                        .and_then(|sides| Physics::parse_slow_ball(&sides))
+                        .or_else(|| usage());
+                }
+                "--code" => {
+                    code = iter
+                        .next()
+                        .and_then(|code| code.parse::<MatchCode>().ok())
+                        .or_else(|| usage());
+                }
+                "--replay" => {
+                    let path = iter.next().unwrap_or_else(|| usage());
+                    args.replay = Some(load_trace(&path));
+                }
+                "--render-replay" => {
+                    let path = iter.next().unwrap_or_else(|| usage());
+                    let out = iter.next().unwrap_or_else(|| usage());
+                    args.render_replay = Some((load_trace(&path), out.into()));
+                }
+                "--record" => args.record = Some(iter.next().unwrap_or_else(|| usage()).into()),
+                "--inputs" => {
+                    let path = iter.next().unwrap_or_else(|| usage());
+                    args.script = Some(load_script(&path));
+                }
+                "--record-inputs" => {
+                    args.record_inputs = Some(iter.next().unwrap_or_else(|| usage()).into());
+                }
+                #[cfg(not(target_family = "wasm"))]
+                "--overlay" => {
+                    let port = iter
+                        .next()
+                        .and_then(|port| port.parse().ok())
+                        .unwrap_or_else(|| usage());
+                    args.overlay = Some(port);
+                }
+                #[cfg(not(target_family = "wasm"))]
+                "--write-site" => {
+                    args.write_site = Some(iter.next().unwrap_or_else(|| usage()).into());
+                }
+                "--party" => {
+                    args.party = iter
+                        .next()
+                        .and_then(|names| Party::parse(&names))
+                        .or_else(|| usage());
+                }
+                "--assets" => args.assets = Some(iter.next().unwrap_or_else(|| usage()).into()),
+                _ => usage(),
+            }
         }
-    }
-}
-
-struct Racket {
-    side: Side,
-    pos: (f32, f32),
-}
-
-impl Racket {
-    fn new(side: Side) -> Self {
-        let pos_x = match side {
-            Side::Left => RACKET_MARGIN,
-            Side::Right => WINDOW_WIDTH - RACKET_MARGIN - RACKET_SIZE.0,
-        };
-        let pos_y = WINDOW_HEIGHT * 0.5 - RACKET_SIZE.1 * 0.5;
-        Self {
-            side,
-            pos: (pos_x, pos_y),
+        if let Some(ball_shape) = ball_shape {
+            args.physics.ball_shape = ball_shape;
+        }
+        if let Some(slow_ball) = slow_ball {
+            args.physics.slow_ball = slow_ball;
+        }
+        if let Some(service) = service {
+            args.rules.service = service;
+        }
+        args.court.wall_motion = wall_motion;
+        // Codes describe the whole setup of the match.
+        if let Some(code) = code {
+            args.court = code.court;
+            args.physics = code.physics;
+            args.lineup = code.lineup;
+            args.rules = code.rules;
+            args.seed = Some(code.seed);
+        }
+        match args.lineup {
+            // The players share the left side against a bot.
+            Lineup::Coop => {
+                args.left_bot = None;
+                args.right_bot.get_or_insert_with(|| COOP_BOT.to_owned());
+            }
+            // The ball machine plays on the right and nobody plays on the
+            // right in target practice.
+            Lineup::Goalkeeper | Lineup::Targets => args.right_bot = None,
+            Lineup::Single | Lineup::Juggler => {}
+        }
+        // Replays are played on the court they were recorded on.
+        if let Some(trace) = &args.replay {
+            args.court = trace.court.clone();
+        }
+        // Parties are played by people, one on each side, and the mirrored
+        // racket has nobody on the right.
+        if args.party.is_some() || args.mirror {
+            args.lineup = Lineup::Single;
+            args.left_bot = None;
+            args.right_bot = None;
+        }
+        // The tutorial is played on the left against a bot.
+        if args.tutorial {
+            args.lineup = Lineup::Single;
+            args.left_bot = None;
+            args.right_bot
+                .get_or_insert_with(|| TUTORIAL_BOT.to_owned());
         }
-    }
-
-    fn slide(&mut self, speed: f32) {
-        self.pos.1 += speed * get_frame_time();
-    }
 
-    fn draw(&self) {
-        draw_rectangle(
-            self.pos.0,
-            self.pos.1,
-            RACKET_SIZE.0,
-            RACKET_SIZE.1,
-            FOREGROUND_COLOR,
-        );
+        args
     }
 }
 
-struct Ball {
-    pos: (f32, f32),
-    dir: (f32, f32),
-    speed: f32,
+/// Loads the trace at `path`, or exits if it cannot be loaded.
+fn load_trace(path: &str) -> Trace {
+    let trace = std::fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|contents| contents.parse::<Trace>().map_err(|err| err.to_string()));
+    trace.unwrap_or_else(|err| {
+        eprintln!("error: load replay {path}: {err}");
+        std::process::exit(1);
+    })
 }
 
-impl Ball {
-    fn new(side: Option<Side>) -> Self {
-        let x = WINDOW_WIDTH * 0.5 - BALL_SIZE * 0.5;
-        let y = WINDOW_HEIGHT * 0.5 - BALL_SIZE * 0.5;
-        let rnddir = || -> f32 { ((((get_time() * 1e6) as i32) & 1) * 2 - 1) as f32 };
-        let dir_x = if let Some(side) = side {
-            match side {
-                Side::Left => -1.,
-                Side::Right => 1.,
-            }
-        } else {
-            rnddir()
-        };
-        Self {
-            pos: (x, y),
-            dir: (dir_x, rnddir()),
-            speed: BALL_INIT_SPEED,
-        }
-    }
-
-    fn fly(&mut self) {
-        let ft = get_frame_time();
-        let delta = self.speed * ft;
-        self.pos.0 += self.dir.0 * delta;
-        self.pos.1 += self.dir.1 * delta;
-        self.speed += ft * BALL_ACCEL;
-    }
-
-    fn draw(&self) {
-        draw_rectangle(
-            self.pos.0,
-            self.pos.1,
-            BALL_SIZE,
-            BALL_SIZE,
-            FOREGROUND_COLOR,
-        );
-    }
+/// Loads the input script at `path`, or exits if it cannot be loaded.
+fn load_script(path: &str) -> InputScript {
+    let script = std::fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|contents| {
+            contents
+                .parse::<InputScript>()
+                .map_err(|err| err.to_string())
+        });
+    script.unwrap_or_else(|err| {
+        eprintln!("error: load inputs {path}: {err}");
+        std::process::exit(1);
+    })
 }
 
-#[derive(Clone, Copy)]
-enum PongState {
-    NewRound(Side),
-    Playing,
-    WallBounce,
-    RacketBounce,
-    Point(Side),
-    Winner(Side, f64),
-    Exit,
+fn bot_name(name: Option<String>) -> String {
+    name.filter(|name| bot::BOT_NAMES.contains(&name.as_str()))
+        .unwrap_or_else(|| usage())
 }
 
-#[derive(Clone, Copy, PartialEq)]
-enum Input {
-    Up(Side),
-    Down(Side),
-    Quit,
-    Unknown,
+fn usage() -> ! {
+    eprintln!(
+        "usage: pong [--max-fps N] [--no-sim-thread] [--assets DIR] [--left-bot BOT] [--right-bot BOT] [--coop | --goalkeeper | --juggler | --targets | --tutorial | --mirror] [--rules RULES] [--service classic|table-tennis] [--edit-rules] [--court COURT] [--moving-walls AMPLITUDE,FREQUENCY] [--physics PHYSICS] [--ball square|round] [--slow-ball left|right|both] [--code CODE] [--record FILE] [--inputs FILE] [--record-inputs FILE] [--calibrate-audio] [--warmup] [--overlay PORT] [--party NAME,NAME,NAME...]"
+    );
+    eprintln!("       pong --replays");
+    eprintln!("       pong --replay FILE");
+    eprintln!("       pong --render-replay FILE DIR");
+    eprintln!("       pong --tournament [--seeds N]");
+    eprintln!("       pong --write-site DIR");
+    eprintln!("bots: {}", bot::BOT_NAMES.join(", "));
+    let presets: Vec<_> = court::PRESETS.iter().map(|preset| preset.name).collect();
+    eprintln!("courts: {} or WxH", presets.join(", "));
+    let presets: Vec<_> = physics::PRESETS.iter().map(|preset| preset.name).collect();
+    eprintln!("physics: {}", presets.join(", "));
+    let presets: Vec<_> = rules::PRESETS.iter().map(|preset| preset.name).collect();
+    eprintln!(
+        "rules: {}, a saved ruleset or WIN,SETS,deuce|no-deuce,SERVICE,SECS",
+        presets.join(", ")
+    );
+    std::process::exit(2);
 }
 
-struct Pong {
-    rackets: (Racket, Racket),
-    scores: (i32, i32),
-    ball: Ball,
-    state: PongState,
-    point_sound: Sound,
-    racket_sound: Sound,
-    wall_sound: Sound,
+/// Caps the rate at which the game is updated and drawn, and paces the
+/// frames.
+///
+/// On native platforms the limiter sleeps until the next frame is due. On
+/// wasm the browser drives the loop through `requestAnimationFrame`, so early
+/// frames are skipped instead and only present the last rendered image.
+///
+/// Frames are due at a steady cadence rather than a fixed time after the
+/// previous one, so late wake-ups don't add up. The time between frames is
+/// smoothed too: its jitter would otherwise make the simulation take one
+/// more or one less step in some frames, which shows as stutter.
+struct FrameLimiter {
+    last_frame: f64,
+    /// When the next frame is due, if the frame rate is capped.
+    next_due: Option<f64>,
+    /// Time between the latest frames, in seconds.
+    recent_dts: VecDeque<f32>,
 }
 
-impl Pong {
-    async fn new() -> Self {
+impl FrameLimiter {
+    fn new() -> Self {
         Self {
-            rackets: (Racket::new(Side::Left), Racket::new(Side::Right)),
-            ball: Ball::new(None),
-            scores: (0, 0),
-            state: PongState::Playing,
-            point_sound: load_sound_from_bytes(POINT_WAV_BYTES)
-                .await
-                .expect("load point sound file"),
-            racket_sound: load_sound_from_bytes(RACKET_WAV_BYTES)
-                .await
-                .expect("load racket sound file"),
-            wall_sound: load_sound_from_bytes(WALL_WAV_BYTES)
-                .await
-                .expect("load wall sound file"),
-        }
-    }
-
-    fn reset(&mut self) {
-        self.rackets = (Racket::new(Side::Left), Racket::new(Side::Right));
-        self.ball = Ball::new(None);
-        self.scores = (0, 0);
-        self.state = PongState::Playing;
-    }
-
-    fn update_racket_collisions(&mut self) {
-        for racket in [&mut self.rackets.0, &mut self.rackets.1] {
-            racket.pos.1 = racket.pos.1.clamp(0., WINDOW_HEIGHT - RACKET_SIZE.1);
+            last_frame: get_time(),
+            next_due: None,
+            recent_dts: VecDeque::with_capacity(PACING_FRAMES),
         }
     }
 
-    fn update_ball_collisions(&mut self) {
-        const DX: f32 = 0.1;
-
-        if self.ball.pos.0 < 0. {
-            self.state = PongState::Point(Side::Right);
-            return;
-        }
-
-        if self.ball.pos.0 + BALL_SIZE > WINDOW_WIDTH {
-            self.state = PongState::Point(Side::Left);
-            return;
-        }
-
-        if self.ball.pos.1 < 0. {
-            self.ball.pos.1 = 0.;
-            self.ball.dir.1 = self.ball.dir.1.abs();
-            self.state = PongState::WallBounce;
-            return;
-        }
-
-        if self.ball.pos.1 + BALL_SIZE > WINDOW_HEIGHT {
-            self.ball.pos.1 = WINDOW_HEIGHT - BALL_SIZE;
-            self.ball.dir.1 = -self.ball.dir.1.abs();
-            self.state = PongState::WallBounce;
-            return;
-        }
+    /// Waits for the next frame to be due with a frame rate of `max_fps`
+    /// and returns the time elapsed since the previous one. `None` means
+    /// that the frame must be skipped.
+    fn next_frame(&mut self, max_fps: Option<u32>, idle: bool) -> Option<f32> {
+        let fps = match (max_fps, idle) {
+            (Some(fps), true) => Some(fps.min(IDLE_FPS)),
+            (None, true) => Some(IDLE_FPS),
+            (fps, false) => fps,
+        };
 
-        let ball_rect = Rect::new(self.ball.pos.0, self.ball.pos.1, BALL_SIZE, BALL_SIZE);
-        for racket in [&self.rackets.0, &self.rackets.1] {
-            let racket_rect = match racket.side {
-                Side::Left => {
-                    if self.ball.dir.0 > 0. {
-                        continue;
-                    }
-                    Rect::new(
-                        racket.pos.0 + RACKET_SIZE.0 - DX,
-                        racket.pos.1,
-                        DX * 2.,
-                        RACKET_SIZE.1,
-                    )
-                }
-                Side::Right => {
-                    if self.ball.dir.0 < 0. {
-                        continue;
-                    }
-                    Rect::new(racket.pos.0 - DX, racket.pos.1, DX * 2., RACKET_SIZE.1)
+        match fps {
+            Some(fps) => {
+                let period = 1. / fps as f64;
+                // After a stall or a change of rate the cadence starts over.
+                let due = self
+                    .next_due
+                    .filter(|&due| (due - get_time()).abs() <= period)
+                    .unwrap_or(self.last_frame + period);
+                let remaining = due - get_time();
+                if remaining > 0. {
+                    #[cfg(target_family = "wasm")]
+                    return None;
+
+                    #[cfg(not(target_family = "wasm"))]
+                    runner::sleep_until(
+                        std::time::Instant::now() + std::time::Duration::from_secs_f64(remaining),
+                    );
                 }
-            };
-
-            let Some(rect) = racket_rect.intersect(ball_rect) else {
-                continue;
-            };
-
-            self.ball.dir.0 = match racket.side {
-                Side::Left => self.ball.dir.0.abs(),
-                Side::Right => -self.ball.dir.0.abs(),
-            };
-            self.ball.dir.1 = (rect.center().y - racket_rect.center().y) / (racket_rect.h * 0.5);
-            self.state = PongState::RacketBounce;
+                self.next_due = Some(due + period);
+            }
+            None => self.next_due = None,
         }
-    }
 
-    fn update_score(&mut self, point_side: Side) {
-        let score = match point_side {
-            Side::Left => &mut self.scores.0,
-            Side::Right => &mut self.scores.1,
-        };
+        let now = get_time();
+        let dt = (now - self.last_frame) as f32;
+        self.last_frame = now;
+        Some(self.smooth(dt))
+    }
 
-        *score += 1;
-        self.state = if *score >= WIN_SCORE {
-            PongState::Winner(point_side, get_time())
+    /// Returns the average time between the latest frames instead of `dt`,
+    /// unless `dt` is far from it, like after a stall.
+    fn smooth(&mut self, dt: f32) -> f32 {
+        let average = if self.recent_dts.is_empty() {
+            dt
         } else {
-            PongState::NewRound(point_side.toggle())
+            self.recent_dts.iter().sum::<f32>() / self.recent_dts.len() as f32
         };
-    }
-
-    fn update(&mut self) {
-        let inputs = self.read_inputs();
-
-        if inputs.contains(&Input::Quit) {
-            self.state = PongState::Exit
+        if self.recent_dts.len() == PACING_FRAMES {
+            self.recent_dts.pop_front();
         }
-
-        match self.state {
-            PongState::NewRound(side) => {
-                self.ball = Ball::new(Some(side));
-                self.state = PongState::Playing;
-            }
-            PongState::Playing => {
-                if inputs.contains(&Input::Up(Side::Left)) {
-                    self.rackets.0.slide(-RACKET_SPEED);
-                }
-                if inputs.contains(&Input::Down(Side::Left)) {
-                    self.rackets.0.slide(RACKET_SPEED);
-                }
-                if inputs.contains(&Input::Up(Side::Right)) {
-                    self.rackets.1.slide(-RACKET_SPEED);
-                }
-                if inputs.contains(&Input::Down(Side::Right)) {
-                    self.rackets.1.slide(RACKET_SPEED);
-                }
-                self.update_racket_collisions();
-                self.ball.fly();
-                self.update_ball_collisions();
-            }
-            PongState::WallBounce | PongState::RacketBounce => {
-                self.state = PongState::Playing;
-            }
-            PongState::Point(side) => {
-                self.update_score(side);
-            }
-            PongState::Winner(_, at) => {
-                if get_time() - at > WIN_SCREEN_SECS && !inputs.is_empty() {
-                    self.reset();
-                }
-            }
-            PongState::Exit => {}
+        self.recent_dts.push_back(dt);
+        if (dt - average).abs() <= average * PACING_TOLERANCE {
+            average
+        } else {
+            self.recent_dts.clear();
+            dt
         }
     }
+}
 
-    fn read_inputs(&mut self) -> Vec<Input> {
-        let mut inputs = Vec::new();
-
-        for key in get_keys_down() {
-            match key {
-                KeyCode::W => inputs.push(Input::Up(Side::Left)),
-                KeyCode::S => inputs.push(Input::Down(Side::Left)),
-                KeyCode::Up => inputs.push(Input::Up(Side::Right)),
-                KeyCode::Down => inputs.push(Input::Down(Side::Right)),
-
-                #[cfg(not(target_family = "wasm"))]
-                KeyCode::Q => inputs.push(Input::Quit),
+#[cfg(debug_assertions)]
+struct FpsCounter {
+    fps: i32,
+    text: String,
+}
 
-                _ => inputs.push(Input::Unknown),
-            }
+#[cfg(debug_assertions)]
+impl FpsCounter {
+    fn new() -> Self {
+        Self {
+            fps: -1,
+            text: String::new(),
         }
+    }
 
-        let scale_y = screen_height() / WINDOW_HEIGHT;
-        for touch in touches() {
-            let (side, racket_y) = if touch.position.x < screen_width() * 0.5 {
-                (Side::Left, self.rackets.0.pos.1)
-            } else {
-                (Side::Right, self.rackets.1.pos.1)
-            };
-            if touch.position.y < (racket_y + RACKET_SIZE.1 * 0.25) * scale_y {
-                inputs.push(Input::Up(side));
-            } else if touch.position.y > (racket_y + RACKET_SIZE.1 * 0.75) * scale_y {
-                inputs.push(Input::Down(side));
-            }
-        }
+    fn draw(&mut self) {
+        use std::fmt::Write;
 
-        inputs
+        let fps = get_fps();
+        if fps != self.fps {
+            self.fps = fps;
+            self.text.clear();
+            write!(self.text, "{fps:3} FPS").expect("format FPS");
+        }
+        draw_text(&self.text, 10., 20., 20., GREEN);
     }
+}
 
-    fn draw_scores(&self) {
-        draw_text_center(
-            &format!("{} - {}", self.scores.0, self.scores.1),
-            75.0,
-            30.0,
-        );
+fn window_conf(settings: &Settings) -> Conf {
+    Conf {
+        window_title: "PONG".to_owned(),
+        window_width: WINDOW_WIDTH as i32,
+        window_height: WINDOW_HEIGHT as i32,
+        icon: Some(assets::icon()),
+        platform: Platform {
+            swap_interval: Some(if settings.vsync { 1 } else { 0 }),
+            ..Default::default()
+        },
+        ..Default::default()
     }
+}
 
-    fn draw_winner(&self, side: Side) {
-        draw_text_center(&format!("{side} WON!"), 150.0, WINDOW_HEIGHT * 0.5);
-        draw_text_center(
-            "(Press any key to play again)",
-            40.,
-            WINDOW_HEIGHT * 0.5 + 100.,
-        );
-    }
+fn main() {
+    crash::install();
+    let args = Args::parse();
 
-    fn draw(&self) {
-        match self.state {
-            PongState::Winner(side, _) => self.draw_winner(side),
-            _ => {
-                self.draw_scores();
-                self.rackets.0.draw();
-                self.rackets.1.draw();
-                self.ball.draw();
-            }
-        }
+    if args.tournament {
+        tournament::run(args.seeds);
+        return;
     }
-
-    fn play_sounds(&self) {
-        match self.state {
-            PongState::WallBounce => play_sound_once(&self.wall_sound),
-            PongState::RacketBounce => play_sound_once(&self.racket_sound),
-            PongState::Point(_) => play_sound_once(&self.point_sound),
-            _ => {}
+    #[cfg(not(target_family = "wasm"))]
+    if let Some(dir) = &args.write_site {
+        if let Err(err) = site::write(dir) {
+            eprintln!("error: write site to {}: {err}", dir.display());
+            std::process::exit(1);
         }
+        return;
     }
 
-    fn state(&self) -> PongState {
-        self.state
+    // The settings are chosen on first launch.
+    let saved_settings = Settings::load();
+    let conf = window_conf(&saved_settings.unwrap_or_default());
+    if let Some((trace, out)) = args.render_replay {
+        let settings = saved_settings.unwrap_or_default();
+        Window::from_config(conf, render_replay(trace, out, args.assets, settings));
+        return;
     }
+    Window::from_config(conf, game(args, saved_settings));
 }
 
-#[cfg(debug_assertions)]
-fn draw_fps() {
-    let fps = format!("{:3} FPS", get_fps());
-    draw_text(&fps, 10., 20., 20., GREEN);
+async fn render_replay(trace: Trace, out: PathBuf, assets: Option<PathBuf>, settings: Settings) {
+    let assets = Assets::load(assets).await;
+    match render::run(&trace, &out, &assets, &settings).await {
+        Ok(frames) => println!(
+            "{frames} frames at {} FPS saved to {}",
+            render::FRAME_RATE,
+            out.display()
+        ),
+        Err(err) => eprintln!("error: render replay to {}: {err}", out.display()),
+    }
 }
 
-fn draw_text_center(text: &str, font_size: f32, y: f32) {
-    let text_sz = measure_text(text, None, font_size as u16, 1.);
-    draw_text(
-        text,
-        WINDOW_WIDTH * 0.5 - text_sz.width * 0.5,
-        y - text_sz.height * 0.5 + text_sz.offset_y,
-        font_size,
-        FOREGROUND_COLOR,
+async fn game(args: Args, saved_settings: Option<Settings>) {
+    let sim = Simulation::with_rules(
+        args.court.clone(),
+        args.physics,
+        (miniquad::date::now() * 1e6) as u64,
     );
-}
-
-fn window_conf() -> Conf {
-    Conf {
-        window_title: "PONG".to_owned(),
-        window_width: WINDOW_WIDTH as i32,
-        window_height: WINDOW_HEIGHT as i32,
-        ..Default::default()
+    let mut runner = match args.sim_thread {
+        #[cfg(not(target_family = "wasm"))]
+        true => Runner::threaded(sim),
+        _ => Runner::inline(sim),
+    };
+    let bots = (args.left_bot, args.right_bot);
+    for (side, name) in [(Side::Left, &bots.0), (Side::Right, &bots.1)] {
+        runner.set_bot(side, name.as_deref().and_then(bot::by_name));
     }
-}
-
-#[macroquad::main(window_conf)]
-async fn main() {
-    let render_target = render_target(WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32);
-    let mut render_camera =
-        Camera2D::from_display_rect(Rect::new(0., 0., WINDOW_WIDTH, WINDOW_HEIGHT));
-    render_camera.render_target = Some(render_target.clone());
-
-    let material = load_material(
-        ShaderSource::Glsl {
-            vertex: VERTEX_SHADER,
-            fragment: FRAGMENT_SHADER,
-        },
-        Default::default(),
-    )
-    .unwrap();
-
-    let mut pong = Pong::new().await;
+    let settings = saved_settings.unwrap_or_default();
+    let mut assets = Assets::load(args.assets).await;
+    assets.sounds.mixer = settings.mixer();
+    let mut ctx = Context {
+        runner,
+        assets,
+        canvas: Canvas::new(&args.court),
+        crt: Crt::default(),
+        pulses: Pulses::default(),
+        inputs: Vec::new(),
+        scheme: settings.scheme,
+        devices: Devices::default(),
+        court: args.court,
+        physics: args.physics,
+        lineup: args.lineup,
+        rules: args.rules,
+        bots,
+        record: args.record,
+        script: args.script,
+        record_inputs: args.record_inputs,
+        history: History::load(),
+        speedrun: Speedrun::load(),
+        settings,
+        autosave: Autosave::new(saved_settings),
+        seed: args.seed,
+        code: None,
+        resume: None,
+        party: args.party,
+        mirror: args.mirror,
+        warmup: args.warmup,
+        hud: true,
+        dt: 0.,
+    };
+    let mut initial: Box<dyn GameState> = if let Some(trace) = args.replay {
+        Box::new(Playback::new(trace))
+    } else if args.replays {
+        Box::new(ReplayBrowser::new())
+    } else if args.tutorial {
+        Box::new(Tutorial::new())
+    } else {
+        // The players choose what to play unless the options already say.
+        let mut first: Box<dyn GameState> = if args.menu {
+            Box::new(MainMenu::new())
+        } else {
+            state::first_match(&ctx)
+        };
+        if args.edit_rules {
+            first = Box::new(RulesEditor::new(first));
+        }
+        first
+    };
+    if let Some(report) = crash::take_report() {
+        initial = Box::new(Crashed::new(report, initial));
+    }
+    if args.calibrate_audio {
+        initial = Box::new(Calibration::new(initial));
+    }
+    if saved_settings.is_none() {
+        initial = Box::new(Setup::new(initial));
+    }
+    #[cfg(not(target_family = "wasm"))]
+    let mut overlay = args.overlay.and_then(|port| {
+        overlay::Overlay::start(port)
+            .map_err(|err| eprintln!("error: start overlay on port {port}: {err}"))
+            .ok()
+    });
+    let mut states = StateStack::new(initial, &mut ctx);
+    let mut power_off = PowerOff::new(ctx.canvas.size);
+    let mut frame_limiter = FrameLimiter::new();
+    let mut quitting = false;
+    // Whether the prompt to rotate the device is shown.
+    let mut rotated = false;
+
+    #[cfg(debug_assertions)]
+    let mut fps_counter = FpsCounter::new();
+    #[cfg(debug_assertions)]
+    let mut bot_debug = bot_debug::BotDebug::default();
 
     loop {
-        set_camera(&render_camera);
-
-        clear_background(BACKGROUND_COLOR);
+        // Key presses and the start of touches and clicks only last one
+        // frame, so they must never be skipped, and neither must the frames
+        // of the transitions between screens.
+        let idle = states.is_idle()
+            && get_last_key_pressed().is_none()
+            && touches().is_empty()
+            && !is_mouse_button_down(MouseButton::Left)
+            && !power_off.is_active();
+        // The command line overrides the settings.
+        let max_fps = args.max_fps.or(ctx.settings.max_fps);
+        if let Some(dt) = frame_limiter.next_frame(max_fps, idle) {
+            let court = &ctx.runner.sim().court;
+            if court.rect().size() != ctx.canvas.size {
+                ctx.canvas = Canvas::new(court);
+            }
+            set_camera(&ctx.canvas.camera);
+
+            ctx.dt = dt;
+            input::read_inputs(
+                &mut ctx.inputs,
+                &ctx.devices,
+                ctx.runner.sim(),
+                ctx.settings.hud_key,
+            );
+            if let Some(scheme) = input::scheme_used() {
+                ctx.scheme = scheme;
+            }
+            // Touches on a court held the wrong way would only be
+            // accidental, and the match waits for the device to be rotated.
+            rotated = ctx.scheme == Scheme::Touch && draw::is_rotated(&ctx.runner.sim().court);
+            if rotated {
+                ctx.inputs.clear();
+            }
+            if rotated || page::take_hidden() {
+                ctx.inputs.push(Input::Suspend);
+            }
+            // The matches of the session are summed up before quitting.
+            if ctx.inputs.contains(&Input::Quit) && !quitting {
+                if ctx.history.session().is_empty() {
+                    break;
+                }
+                quitting = true;
+                states.push(Box::new(Summary::new(ctx.history.session())), &mut ctx);
+            }
+            // The keys of the combination must not reach the screen below.
+            if ctx.inputs.contains(&Input::Diagnostics) {
+                ctx.inputs.clear();
+                states.push(Box::new(Diagnostics::new()), &mut ctx);
+            }
+            if ctx.inputs.contains(&Input::Timer) {
+                ctx.speedrun.toggle();
+            }
+            // The keys of the combination must not reach the screen below
+            // either, but the rackets keep moving.
+            if ctx.inputs.contains(&Input::ToggleHud) {
+                ctx.hud = !ctx.hud;
+                ctx.inputs
+                    .retain(|input| matches!(input, Input::Up(_) | Input::Down(_)));
+            }
+            states.update(&mut ctx);
+            ctx.autosave.update(ctx.settings, dt);
+            #[cfg(not(target_family = "wasm"))]
+            if let Some(overlay) = &mut overlay {
+                overlay.update(ctx.runner.sim());
+            }
+            if states.is_empty() {
+                break;
+            }
+            // The canvas still has the last image of the replaced screen.
+            if states.take_replaced() && ctx.settings.allows(Effect::PowerOff) {
+                power_off.start(&ctx.canvas);
+            }
+            power_off.update(dt);
+            ctx.pulses.update(dt);
+            set_camera(&ctx.canvas.camera);
+            clear_background(BACKGROUND_COLOR);
+            states.draw(&ctx);
+            if ctx.hud {
+                ctx.speedrun.draw();
+            }
 
-        pong.update();
-        if matches!(pong.state(), PongState::Exit) {
-            break;
+            #[cfg(debug_assertions)]
+            {
+                bot_debug.update(&ctx.runner);
+                bot_debug.draw(&ctx.runner);
+            }
         }
-        pong.draw();
-        pong.play_sounds();
 
         set_default_camera();
-
-        gl_use_material(&material);
+        clear_background(BLACK);
+
+        let viewport = draw::viewport(&ctx.runner.sim().court);
+        let (image, collapse) = power_off.frame(&ctx.canvas);
+        if ctx.settings.crt
+            || ctx.settings.palette != Palette::Off
+            || collapse > 0.
+            || ctx.pulses.is_active()
+        {
+            let motion = if ctx.settings.allows(Effect::Scanlines) {
+                1.
+            } else {
+                0.
+            };
+            let crt = if ctx.settings.crt { ctx.crt } else { Crt::NONE };
+            crt.use_material(
+                &ctx.assets.crt_material,
+                motion,
+                ctx.settings.palette,
+                collapse,
+                &ctx.pulses,
+            );
+        }
         draw_texture_ex(
-            &render_target.texture,
-            0.,
-            0.,
+            &image.target.texture,
+            viewport.x,
+            viewport.y,
             WHITE,
             DrawTextureParams {
-                dest_size: Some(vec2(screen_width(), screen_height())),
+                dest_size: Some(viewport.size()),
                 flip_y: true,
                 ..Default::default()
             },
         );
         gl_use_default_material();
+        if rotated {
+            draw::draw_rotate_prompt();
+        }
 
         #[cfg(debug_assertions)]
-        draw_fps();
+        if ctx.hud {
+            fps_counter.draw();
+        }
 
         next_frame().await;
     }
-}
-
-const VERTEX_SHADER: &str = r#"
-#version 100
-
-attribute vec3 position;
-attribute vec2 texcoord;
-attribute vec4 color0;
-
-varying lowp vec2 uv;
-varying lowp vec4 color;
-
-uniform mat4 Model;
-uniform mat4 Projection;
-
-void main() {
-    gl_Position = Projection * Model * vec4(position, 1);
-    color = color0 / 255.0;
-    uv = texcoord;
-}
-"#;
 
-const FRAGMENT_SHADER: &str = r#"
-// This shader is based on https://www.shadertoy.com/view/XtlSD7
-
-#version 100
-
-precision lowp float;
-
-varying vec2 uv;
-varying vec4 color;
-
-uniform sampler2D Texture;
-uniform vec4 _Time;
-
-vec2 crt_curve_uv(vec2 uv) {
-    uv = uv * 2.0 - 1.0;
-    vec2 offset = abs(uv.yx) / vec2(6.0, 4.0);
-    uv = uv + uv * offset * offset;
-    uv = uv * 0.5 + 0.5;
-    return uv;
-}
-
-void draw_vignette(inout vec3 color, vec2 uv) {
-    float vignette = uv.x * uv.y * (1.0 - uv.x) * (1.0 - uv.y);
-    vignette = clamp(pow(16.0 * vignette, 0.3), 0.0, 1.0);
-    color *= vignette;
-}
-
-void draw_scanline(inout vec3 color, vec2 uv) {
-    float scanline = clamp(0.95 + 0.05 * cos(3.14 * (uv.y + 0.008 * _Time.x) * 240.0 * 1.0), 0.0, 1.0);
-    float grille = 0.85 + 0.15 * clamp(1.5 * cos(3.14 * uv.x * 640.0 * 1.0), 0.0, 1.0);
-    color *= scanline * grille * 1.2;
-}
-
-void main() {
-    vec3 frag_color = texture2D(Texture, uv).rgb * color.rgb;
-    vec2 crt_uv = crt_curve_uv(uv);
-    if (crt_uv.x < 0.0 || crt_uv.x > 1.0 || crt_uv.y < 0.0 || crt_uv.y > 1.0) {
-        frag_color = vec3(0.0, 0.0, 0.0);
-    }
-    draw_vignette(frag_color, crt_uv);
-    draw_scanline(frag_color, uv);
-    gl_FragColor = vec4(frag_color, 1.0);
+    ctx.autosave.flush(ctx.settings);
 }
-"#;
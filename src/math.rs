@@ -0,0 +1,42 @@
+//! Math functions giving the same results on every platform.
+//!
+//! Matches must play the same on native and wasm builds for traces and match
+//! codes to be shared between them. Basic `f32` arithmetic, `sqrt`, `abs`,
+//! `min`, `max`, `round` and `rem_euclid` are exactly rounded by IEEE 754 and
+//! give the same results everywhere, as Rust never fuses or reorders them.
+//! Transcendental functions and `powi`, on the other hand, are left to the
+//! platform and may differ in the last bits, so the simulation uses the
+//! functions of this module instead, which are built on the former.
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// [`TAU`] split into a part with few significant bits, so multiples of it
+/// are exact, and the rest, more precise than `TAU - TAU_HI`.
+const TAU_HI: f32 = 6.28125;
+const TAU_LO: f32 = 0.001_935_307_2;
+
+/// Returns the cosine of `x`, in radians, within a few ULPs.
+pub fn cos(x: f32) -> f32 {
+    // The cosine is even and periodic, so only [0, PI] is needed, and
+    // cos(x) = -cos(PI - x) further reduces it to [0, PI / 2].
+    let turns = (x / TAU).round();
+    let x = (x - turns * TAU_HI - turns * TAU_LO).abs();
+    let (x, sign) = if x > FRAC_PI_2 {
+        (PI - x, -1.)
+    } else {
+        (x, 1.)
+    };
+    // Taylor series up to x^12, whose error on [0, PI / 2] is below the
+    // precision of an f32.
+    let x2 = x * x;
+    let series = 1.
+        - x2 / 2.
+            * (1.
+                - x2 / 12.
+                    * (1. - x2 / 30. * (1. - x2 / 56. * (1. - x2 / 90. * (1. - x2 / 132.)))));
+    sign * series
+}
+
+/// Returns `base` raised to `exp`.
+pub fn powi(base: f32, exp: u32) -> f32 {
+    (0..exp).fold(1., |acc, _| acc * base)
+}
@@ -0,0 +1,103 @@
+//! Lists of entries to choose from.
+//!
+//! The entries are browsed with the keys of either racket and the selected
+//! one is chosen with the serve key. On touch screens they are tapped.
+use pong::{court::Court, sim::Side};
+
+use crate::{
+    input::{self, Input, Scheme},
+    label::Label,
+    prompt::{Action, Prompts},
+};
+
+const ENTRY_FONT_SIZE: u16 = 40;
+/// Distance between the centers of consecutive entries.
+const ENTRY_SPACING: f32 = 56.;
+
+pub struct Menu {
+    entries: Vec<&'static str>,
+    selected: usize,
+    /// Inputs of the previous frame, so held keys only count once.
+    held: Vec<Input>,
+    labels: Vec<Label>,
+    prompts: Prompts,
+}
+
+impl Menu {
+    pub fn new(entries: &[&'static str]) -> Self {
+        let mut menu = Self {
+            entries: entries.to_vec(),
+            selected: 0,
+            held: Vec::new(),
+            labels: entries
+                .iter()
+                .map(|_| Label::new("", ENTRY_FONT_SIZE))
+                .collect(),
+            prompts: Prompts::new(&[Action::Browse, Action::Choose], 20),
+        };
+        menu.select(0);
+        menu
+    }
+
+    /// Ignores the inputs in `inputs` until they are released, like the key
+    /// that opened the menu.
+    pub fn hold(&mut self, inputs: &[Input]) {
+        inputs.clone_into(&mut self.held);
+    }
+
+    /// Moves the selection with `inputs` and returns the index of the entry
+    /// chosen, if any. `top` is where the first entry is drawn on `court`.
+    pub fn update(&mut self, inputs: &[Input], court: &Court, top: f32) -> Option<usize> {
+        let pressed = |input: Input| inputs.contains(&input) && !self.held.contains(&input);
+        let up = pressed(Input::Up(Side::Left)) || pressed(Input::Up(Side::Right));
+        let down = pressed(Input::Down(Side::Left)) || pressed(Input::Down(Side::Right));
+        let confirm = pressed(Input::Serve);
+        inputs.clone_into(&mut self.held);
+
+        // Touches also serve, so a tap only chooses the entry under it.
+        if let Some(pos) = input::tapped_court(court) {
+            let index = ((pos.y - top) / ENTRY_SPACING + 0.5).floor();
+            if index >= 0. && (index as usize) < self.entries.len() {
+                self.select(index as usize);
+                return Some(index as usize);
+            }
+            return None;
+        }
+        let len = self.entries.len();
+        if up {
+            self.select((self.selected + len - 1) % len);
+        }
+        if down {
+            self.select((self.selected + 1) % len);
+        }
+        confirm.then_some(self.selected)
+    }
+
+    /// Tells how to browse and choose the entries with `scheme`.
+    pub fn update_hints(&mut self, scheme: Scheme) {
+        self.prompts.update(scheme);
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index;
+        for (i, (label, entry)) in self.labels.iter_mut().zip(&self.entries).enumerate() {
+            if i == index {
+                label.set_text(&format!("> {entry} <"));
+            } else {
+                label.set_text(entry);
+            }
+        }
+    }
+
+    /// Draws the entries centered on `court`, the first one at `top`, and
+    /// the hints at the bottom.
+    pub fn draw(&self, court: &Court, top: f32) {
+        let x = court.rect().center().x;
+        for (i, label) in self.labels.iter().enumerate() {
+            label.draw_center(x, top + i as f32 * ENTRY_SPACING);
+        }
+        for (i, label) in self.prompts.labels().iter().rev().enumerate() {
+            label.draw_center(x, court.height - 20. - i as f32 * 24.);
+        }
+    }
+}
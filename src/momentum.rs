@@ -0,0 +1,90 @@
+//! Momentum of a match, shown to the spectators as a tug-of-war bar.
+//!
+//! The bar is pulled towards a side by every point it wins and by its
+//! returns, more so the faster the ball leaves the racket or the closer to
+//! the edge of the racket it was saved. The pulls fade with time, so the
+//! bar shows who has been on top lately. It has no effect on the match.
+use macroquad::prelude::*;
+use pong::{
+    court::Court,
+    sim::{Side, Simulation},
+};
+
+use crate::{
+    audio,
+    draw::{FOREGROUND_COLOR, LEFT_COLOR, RIGHT_COLOR},
+    timeline::{Entry, Kind},
+};
+
+/// Pull of a point won, of a return of the ball at the speed it is served
+/// at, and extra pull of a near miss.
+const POINT_PULL: f32 = 0.3;
+const RETURN_PULL: f32 = 0.04;
+const NEAR_MISS_PULL: f32 = 0.1;
+/// How far from the center of the racket a return is a near miss, from 0
+/// at its center to 1 at its edges.
+const NEAR_MISS_CONTACT: f32 = 0.8;
+/// Fraction of the momentum kept after every second.
+const DECAY_PER_SEC: f32 = 0.85;
+/// Rate at which the bar follows the momentum, per second.
+const BAR_RATE: f32 = 4.;
+const BAR_SIZE: (f32, f32) = (200., 8.);
+
+#[derive(Default)]
+pub struct Momentum {
+    /// From -1, all with the left side, to 1, all with the right side.
+    value: f32,
+    /// Momentum shown by the bar, which follows `value`.
+    shown: f32,
+}
+
+impl Momentum {
+    /// Pulls the bar for `entry`, just logged in the timeline of `sim`.
+    pub fn record(&mut self, entry: &Entry, sim: &Simulation) {
+        let (side, pull) = match entry.kind {
+            Kind::Point { side, .. } => (side, POINT_PULL),
+            Kind::RacketBounce(side) => {
+                let mut pull = RETURN_PULL * entry.speed / sim.physics.init_speed;
+                if audio::contact(sim).is_some_and(|contact| contact.abs() > NEAR_MISS_CONTACT) {
+                    pull += NEAR_MISS_PULL;
+                }
+                (side, pull)
+            }
+            Kind::Serve { .. }
+            | Kind::WallBounce
+            | Kind::TargetHit
+            | Kind::SetWon(_)
+            | Kind::Winner(_) => return,
+        };
+        let pull = match side {
+            Side::Left => -pull,
+            Side::Right => pull,
+        };
+        self.value = (self.value + pull).clamp(-1., 1.);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.value *= DECAY_PER_SEC.powf(dt);
+        self.shown += (self.value - self.shown) * (1. - (-BAR_RATE * dt).exp());
+    }
+
+    /// Draws the bar at the top of `court`, filled from its center towards
+    /// the side with the momentum.
+    pub fn draw(&self, court: &Court, y: f32) {
+        let (width, height) = BAR_SIZE;
+        let x = (court.width - width) * 0.5;
+        let center = court.width * 0.5;
+        let fill = self.shown * width * 0.5;
+        let color = if fill < 0. { LEFT_COLOR } else { RIGHT_COLOR };
+        draw_rectangle(center.min(center + fill), y, fill.abs(), height, color);
+        draw_rectangle_lines(x, y, width, height, 2., FOREGROUND_COLOR);
+        draw_line(
+            center,
+            y - 3.,
+            center,
+            y + height + 3.,
+            2.,
+            FOREGROUND_COLOR,
+        );
+    }
+}
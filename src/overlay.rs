@@ -0,0 +1,157 @@
+//! Live scoreboard for streams.
+//!
+//! With `--overlay PORT` the game serves the state of the match over HTTP on
+//! `127.0.0.1:PORT`, so it can be shown in streaming software like OBS with a
+//! browser source instead of capturing the HUD. `/` is a small page showing
+//! the score over a transparent background and `/state.json` the state it
+//! polls:
+//!
+//! ```text
+//! {"lineup": "single", "scores": [3, 1], "sets": [1, 0], "time_left": null, "winner": null}
+//! ```
+//!
+//! `time_left` is in whole seconds, rounded up, and `winner` is the side that won the match,
+//! once it is over. Only the native builds can serve the overlay.
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use pong::sim::{Lineup, MatchState, Simulation};
+
+use crate::stats::side_name;
+
+/// Time a client has to send its request before the connection is dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>PONG</title>
+<style>
+body { margin: 0; background: transparent; color: #fff; font: bold 64px monospace; text-shadow: 0 0 8px #000; }
+#sets, #time { font-size: 24px; }
+</style>
+</head>
+<body>
+<div id="score"></div>
+<div id="sets"></div>
+<div id="time"></div>
+<script>
+async function poll() {
+  try {
+    const state = await (await fetch("/state.json")).json();
+    document.getElementById("score").textContent = state.winner
+      ? state.winner.toUpperCase() + " WINS"
+      : state.scores[0] + " - " + state.scores[1];
+    document.getElementById("sets").textContent =
+      state.sets[0] + state.sets[1] > 0 ? "SETS " + state.sets[0] + " - " + state.sets[1] : "";
+    document.getElementById("time").textContent =
+      state.time_left === null ? "" : Math.ceil(state.time_left) + "s";
+  } catch (err) {}
+  setTimeout(poll, 250);
+}
+poll();
+</script>
+</body>
+</html>
+"#;
+
+/// Part of the match shown by the overlay.
+#[derive(Clone, Copy, PartialEq)]
+struct Shown {
+    lineup: Lineup,
+    scores: (i32, i32),
+    sets: (u32, u32),
+    state: MatchState,
+    /// Whole seconds left, rounded up.
+    time_left: Option<u32>,
+}
+
+/// Server of the overlay, running on its own thread.
+pub struct Overlay {
+    /// State of the match as served in `/state.json`.
+    state: Arc<Mutex<String>>,
+    /// Part of the match the state was written for.
+    shown: Option<Shown>,
+}
+
+impl Overlay {
+    /// Starts serving the overlay on `port` of the loopback interface.
+    pub fn start(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let state = Arc::new(Mutex::new(String::from("{}")));
+        let served = Arc::clone(&state);
+        thread::Builder::new()
+            .name("overlay".to_owned())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    if let Err(err) = serve(stream, &served) {
+                        eprintln!("error: serve overlay: {err}");
+                    }
+                }
+            })?;
+        Ok(Self { state, shown: None })
+    }
+
+    /// Publishes the state of `sim`, if it changed since the last time.
+    pub fn update(&mut self, sim: &Simulation) {
+        let shown = Shown {
+            lineup: sim.lineup,
+            scores: sim.scores,
+            sets: sim.sets,
+            state: sim.state,
+            time_left: sim.time_left().map(|secs| secs.max(0.).ceil() as u32),
+        };
+        if self.shown == Some(shown) {
+            return;
+        }
+        self.shown = Some(shown);
+        let winner = match shown.state {
+            MatchState::Playing => "null".to_owned(),
+            MatchState::Finished(side) => format!(r#""{}""#, side_name(side)),
+        };
+        let time_left = shown
+            .time_left
+            .map_or("null".to_owned(), |secs| secs.to_string());
+        let json = format!(
+            r#"{{"lineup": "{}", "scores": [{}, {}], "sets": [{}, {}], "time_left": {time_left}, "winner": {winner}}}"#,
+            shown.lineup, shown.scores.0, shown.scores.1, shown.sets.0, shown.sets.1
+        );
+        *self.state.lock().expect("lock overlay state") = json;
+    }
+}
+
+/// Answers the request read from `stream`.
+fn serve(stream: TcpStream, state: &Mutex<String>) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Only the path of the request line matters, but the headers are read
+    // anyway: closing the connection with unread data resets it, and the
+    // client could lose the response.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let (status, content_type, body) = match path {
+        "/" => ("200 OK", "text/html", PAGE.to_owned()),
+        "/state.json" => (
+            "200 OK",
+            "application/json",
+            state.lock().expect("lock overlay state").clone(),
+        ),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_owned()),
+    };
+    write!(
+        &stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
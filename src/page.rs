@@ -0,0 +1,41 @@
+//! Page the web version is played in.
+//!
+//! Browsers stop drawing hidden tabs, which freezes the game until the tab
+//! is shown again. Matches are paused then, so they don't resume under the
+//! players' feet.
+//!
+//! The page also caches the game to play it offline, and tells when a new
+//! version has been cached. See `src/site.rs`.
+#[cfg(target_family = "wasm")]
+mod js {
+    extern "C" {
+        pub fn pong_take_hidden() -> i32;
+        pub fn pong_update_available() -> i32;
+    }
+}
+
+/// Returns whether the page has been hidden since the last call.
+#[cfg(target_family = "wasm")]
+pub fn take_hidden() -> bool {
+    // SAFETY: The function takes no arguments.
+    unsafe { js::pong_take_hidden() != 0 }
+}
+
+/// Only the web version is played in a page.
+#[cfg(not(target_family = "wasm"))]
+pub fn take_hidden() -> bool {
+    false
+}
+
+/// Returns whether a new version of the game is ready to be played after
+/// reloading the page.
+#[cfg(target_family = "wasm")]
+pub fn update_available() -> bool {
+    // SAFETY: The function takes no arguments.
+    unsafe { js::pong_update_available() != 0 }
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub fn update_available() -> bool {
+    false
+}
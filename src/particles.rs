@@ -0,0 +1,62 @@
+//! Simple particle effects.
+use macroquad::{prelude::*, rand::gen_range};
+
+const GRAVITY: f32 = 400.;
+const CONFETTI_SIZE: f32 = 8.;
+const CONFETTI_COLORS: &[Color] = &[RED, YELLOW, GREEN, SKYBLUE, PINK, ORANGE];
+
+struct Particle {
+    pos: Vec2,
+    vel: Vec2,
+    color: Color,
+    /// Remaining life in seconds.
+    life: f32,
+}
+
+#[derive(Default)]
+pub struct Particles {
+    particles: Vec<Particle>,
+}
+
+impl Particles {
+    /// Throws `count` pieces of confetti from `origin`, mostly towards `dir`.
+    pub fn confetti(&mut self, origin: Vec2, dir: Vec2, count: usize) {
+        for _ in 0..count {
+            let angle = gen_range(-0.6, 0.6);
+            let speed = gen_range(200., 600.);
+            self.particles.push(Particle {
+                pos: origin,
+                vel: Vec2::from_angle(angle).rotate(dir.normalize_or_zero()) * speed,
+                color: CONFETTI_COLORS[gen_range(0, CONFETTI_COLORS.len())],
+                life: gen_range(1.5, 3.),
+            });
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for particle in &mut self.particles {
+            particle.vel.y += GRAVITY * dt;
+            particle.pos += particle.vel * dt;
+            particle.life -= dt;
+        }
+        self.particles.retain(|particle| particle.life > 0.);
+    }
+
+    pub fn draw(&self) {
+        for particle in &self.particles {
+            let mut color = particle.color;
+            color.a = particle.life.min(1.);
+            draw_rectangle(
+                particle.pos.x,
+                particle.pos.y,
+                CONFETTI_SIZE,
+                CONFETTI_SIZE * 0.5,
+                color,
+            );
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+}
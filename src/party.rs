@@ -0,0 +1,67 @@
+//! Party mode, where more than two players take turns at the rackets: the
+//! winner of every match stays on, and the loser hands their controls to
+//! the next player waiting and joins the back of the queue.
+use std::collections::VecDeque;
+
+use pong::sim::Side;
+
+/// Fewest players in a party. With two, nobody would wait.
+pub const MIN_PLAYERS: usize = 3;
+
+pub struct Party {
+    /// Who plays on the left and right sides.
+    pub players: (String, String),
+    /// Who waits to play, in order.
+    pub queue: VecDeque<String>,
+    /// Side that won the last match and how many matches in a row the
+    /// player there has won.
+    pub streak: Option<(Side, u32)>,
+}
+
+impl Party {
+    /// Parses a comma-separated list of at least [`MIN_PLAYERS`] names,
+    /// without spaces. The first two players play the first match.
+    pub fn parse(names: &str) -> Option<Self> {
+        let mut queue: VecDeque<_> = names.split(',').map(str::to_uppercase).collect();
+        if queue.len() < MIN_PLAYERS
+            || queue
+                .iter()
+                .any(|name| name.is_empty() || name.contains(char::is_whitespace))
+        {
+            return None;
+        }
+        let left = queue.pop_front()?;
+        let right = queue.pop_front()?;
+        Some(Self {
+            players: (left, right),
+            queue,
+            streak: None,
+        })
+    }
+
+    /// Returns who plays on `side`.
+    pub fn player(&self, side: Side) -> &str {
+        match side {
+            Side::Left => &self.players.0,
+            Side::Right => &self.players.1,
+        }
+    }
+
+    /// Sends the loser of a match won by `winner` to the back of the queue,
+    /// replaced by the first player waiting.
+    pub fn finish(&mut self, winner: Side) {
+        let streak = match self.streak {
+            Some((side, streak)) if side == winner => streak + 1,
+            _ => 1,
+        };
+        self.streak = Some((winner, streak));
+        let Some(next) = self.queue.pop_front() else {
+            return;
+        };
+        let loser = match winner.toggle() {
+            Side::Left => &mut self.players.0,
+            Side::Right => &mut self.players.1,
+        };
+        self.queue.push_back(std::mem::replace(loser, next));
+    }
+}
@@ -0,0 +1,160 @@
+//! Tuning of the ball physics.
+use std::fmt;
+
+/// Shape of the ball used for collisions and drawing.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BallShape {
+    /// The classic square ball.
+    #[default]
+    Square,
+    /// A round ball. It deflects off the corners of the rackets and the
+    /// obstacles.
+    Round,
+}
+
+impl BallShape {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "square" => Some(BallShape::Square),
+            "round" => Some(BallShape::Round),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BallShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BallShape::Square => write!(f, "square"),
+            BallShape::Round => write!(f, "round"),
+        }
+    }
+}
+
+/// Tuning knobs of the ball physics. [`Physics::ARCADE`] keeps the classic
+/// feel and is the default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Physics {
+    pub ball_shape: BallShape,
+    /// Speed of the ball when served, in units per second.
+    pub init_speed: f32,
+    /// Speed gained by the ball every second.
+    pub accel: f32,
+    /// Speed the ball never goes beyond.
+    pub max_speed: f32,
+    /// Fraction of the vertical speed of a racket, relative to its top speed,
+    /// added to the direction of the ball when they bounce.
+    pub spin: f32,
+    /// Maximum vertical component of the direction of the ball after a
+    /// racket bounce. The horizontal component is 1, so 1 means 45°.
+    pub max_dir_y: f32,
+    /// Factor applied to the speed of the ball when it bounces off the walls
+    /// and the obstacles. Below 1 the walls absorb part of the energy.
+    pub wall_restitution: f32,
+    /// Factor applied to the speed of the ball when it bounces off a racket.
+    pub racket_restitution: f32,
+    /// Whether the left and right players have the slow-ball handicap: the
+    /// ball slows down in their half while it travels towards them, to at
+    /// most [`SLOW_BALL_SPEED`] times its serve speed, and goes back to its
+    /// speed once returned.
+    pub slow_ball: (bool, bool),
+}
+
+/// Speed the ball slows down to for players with the slow-ball handicap,
+/// relative to the speed it is served at.
+pub const SLOW_BALL_SPEED: f32 = 1.2;
+
+impl Physics {
+    pub const ARCADE: Physics = Physics {
+        ball_shape: BallShape::Square,
+        init_speed: 150.,
+        accel: 10.,
+        max_speed: f32::INFINITY,
+        spin: 0.,
+        max_dir_y: f32::INFINITY,
+        wall_restitution: 1.,
+        racket_restitution: 1.,
+        slow_ball: (false, false),
+    };
+
+    pub const REALISTIC: Physics = Physics {
+        ball_shape: BallShape::Round,
+        init_speed: 200.,
+        accel: 6.,
+        max_speed: 700.,
+        spin: 0.4,
+        max_dir_y: 1.2,
+        wall_restitution: 0.95,
+        racket_restitution: 1.05,
+        slow_ball: (false, false),
+    };
+
+    /// Returns the preset called `name`.
+    pub fn parse(name: &str) -> Option<Self> {
+        PRESETS
+            .iter()
+            .find(|preset| preset.name == name)
+            .map(|preset| preset.physics)
+    }
+
+    /// Returns the name of the preset these physics are based on, ignoring
+    /// the shape of the ball and the handicaps, which can be chosen
+    /// independently.
+    pub fn preset_name(&self) -> Option<&'static str> {
+        PRESETS
+            .iter()
+            .find(|preset| {
+                Physics {
+                    ball_shape: preset.physics.ball_shape,
+                    slow_ball: preset.physics.slow_ball,
+                    ..*self
+                } == preset.physics
+            })
+            .map(|preset| preset.name)
+    }
+
+    /// Returns the sides with the slow-ball handicap, `left`, `right` or
+    /// `both`, or `None` if neither has it.
+    pub fn slow_ball_spec(&self) -> Option<&'static str> {
+        match self.slow_ball {
+            (false, false) => None,
+            (true, false) => Some("left"),
+            (false, true) => Some("right"),
+            (true, true) => Some("both"),
+        }
+    }
+
+    /// Parses the sides with the slow-ball handicap, as returned by
+    /// [`Physics::slow_ball_spec`].
+    pub fn parse_slow_ball(s: &str) -> Option<(bool, bool)> {
+        match s {
+            "left" => Some((true, false)),
+            "right" => Some((false, true)),
+            "both" => Some((true, true)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self::ARCADE
+    }
+}
+
+/// A named set of physics.
+pub struct Preset {
+    pub name: &'static str,
+    pub physics: Physics,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "arcade",
+        physics: Physics::ARCADE,
+    },
+    Preset {
+        name: "realistic",
+        physics: Physics::REALISTIC,
+    },
+];
@@ -0,0 +1,69 @@
+//! Transition of an old television being switched off and on again, shown
+//! when a screen replaces another: the last image of the old screen
+//! collapses into a bright horizontal line, and the new one opens from it.
+use macroquad::prelude::*;
+
+use crate::draw::Canvas;
+
+/// Time the image takes to collapse, and the new one to open.
+const COLLAPSE_SECS: f32 = 0.15;
+
+pub struct PowerOff {
+    /// Last image of the old screen, shown while it collapses.
+    snapshot: Canvas,
+    /// Time since the screen was replaced, while the transition is shown.
+    secs: Option<f32>,
+}
+
+impl PowerOff {
+    pub fn new(size: Vec2) -> Self {
+        Self {
+            snapshot: Canvas::with_size(size),
+            secs: None,
+        }
+    }
+
+    /// Starts collapsing the image of `canvas`, the last one of the screen
+    /// being replaced.
+    pub fn start(&mut self, canvas: &Canvas) {
+        if self.snapshot.size != canvas.size {
+            self.snapshot = Canvas::with_size(canvas.size);
+        }
+        set_camera(&self.snapshot.camera);
+        draw_texture_ex(
+            &canvas.target.texture,
+            0.,
+            0.,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(canvas.size),
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+        self.secs = Some(0.);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.secs = self
+            .secs
+            .map(|secs| secs + dt)
+            .filter(|&secs| secs < COLLAPSE_SECS * 2.);
+    }
+
+    /// Returns whether the transition is being shown.
+    pub fn is_active(&self) -> bool {
+        self.secs.is_some()
+    }
+
+    /// Returns the image to show instead of `canvas`, the old one while it
+    /// collapses, and how collapsed it is, from 0 (not at all) to 1 (into a
+    /// line).
+    pub fn frame<'a>(&'a self, canvas: &'a Canvas) -> (&'a Canvas, f32) {
+        match self.secs {
+            Some(secs) if secs < COLLAPSE_SECS => (&self.snapshot, secs / COLLAPSE_SECS),
+            Some(secs) => (canvas, 2. - secs / COLLAPSE_SECS),
+            None => (canvas, 0.),
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! Control prompts.
+//!
+//! Prompts name the controls of the device the players are using, so they
+//! change as soon as the players switch from the keyboard to the touch screen
+//! or back.
+use crate::{input::Scheme, label::Label};
+
+/// Something the players are told how to do.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Move,
+    Serve,
+    Start,
+    Resume,
+    PlayAgain,
+    Heatmap,
+    Export,
+    Quit,
+    Change,
+    Confirm,
+    Recover,
+    Dismiss,
+    OpenFolder,
+    Retry,
+    TogglePause,
+    StepFrame,
+    SlowMotion,
+    Follow,
+    WatchAgain,
+    Leave,
+    Replays,
+    Select,
+    Watch,
+    Sort,
+    Delete,
+    Rewind,
+    Photo,
+    Tune,
+    NextKnob,
+    HideControls,
+    TakePhoto,
+    TrySuggested,
+    NextField,
+    LeaveMatch,
+    ExportData,
+    ImportData,
+    Browse,
+    Choose,
+    MainMenu,
+}
+
+impl Action {
+    fn description(self) -> &'static str {
+        match self {
+            Action::Move => "move",
+            Action::Serve => "serve",
+            Action::Start => "start a match",
+            Action::Resume => "resume",
+            Action::PlayAgain => "play again",
+            Action::Heatmap => "toggle the heatmap",
+            Action::Export => "export the stats to JSON or CSV, or the events to JSON Lines",
+            Action::Quit => "quit",
+            Action::Change => "change",
+            Action::Confirm => "confirm",
+            Action::Recover => "resume the match",
+            Action::Dismiss => "continue",
+            Action::OpenFolder => "open the data folder",
+            Action::Retry => "measure again",
+            Action::TogglePause => "pause or resume",
+            Action::StepFrame => "step one frame",
+            Action::SlowMotion => "toggle slow motion",
+            Action::Follow => "follow the ball",
+            Action::WatchAgain => "watch again",
+            Action::Leave => "leave",
+            Action::Replays => "watch the replays",
+            Action::Select => "choose a replay",
+            Action::Watch => "watch it",
+            Action::Sort => "change the order",
+            Action::Delete => "delete it",
+            Action::Rewind => "rewind",
+            Action::Photo => "enter photo mode",
+            Action::Tune => "adjust the CRT effect",
+            Action::NextKnob => "choose what to adjust",
+            Action::HideControls => "hide the controls",
+            Action::TakePhoto => "take a photo",
+            Action::TrySuggested => "play against it",
+            Action::NextField => "choose what to change",
+            Action::LeaveMatch => "leave the match",
+            Action::ExportData => "export all the saved data",
+            Action::ImportData => "import it",
+            Action::Browse => "browse the entries",
+            Action::Choose => "choose",
+            Action::MainMenu => "go back to the main menu",
+        }
+    }
+}
+
+/// Returns the glyph of the control of `action` with `scheme`, or `None` if
+/// the action cannot be done with it.
+pub fn glyph(action: Action, scheme: Scheme) -> Option<&'static str> {
+    let glyph = match (scheme, action) {
+        (Scheme::Keyboard, Action::Move) => "W S",
+        (Scheme::Keyboard, Action::Serve | Action::Start) => "SPACE",
+        (Scheme::Keyboard, Action::Resume) => "P",
+        (Scheme::Keyboard, Action::PlayAgain | Action::Quit) => "any key",
+        (Scheme::Keyboard, Action::Heatmap) => "H",
+        (Scheme::Keyboard, Action::Export) => "J, C or L",
+        (
+            Scheme::Keyboard,
+            Action::Change | Action::Retry | Action::Select | Action::Tune | Action::Browse,
+        ) => "W S",
+        (Scheme::Keyboard, Action::Confirm | Action::Recover | Action::Choose) => "SPACE",
+        (Scheme::Keyboard, Action::Dismiss) => "any other key",
+        (Scheme::Keyboard, Action::OpenFolder) => "O",
+        (
+            Scheme::Keyboard,
+            Action::TogglePause | Action::WatchAgain | Action::Watch | Action::TakePhoto,
+        ) => "SPACE",
+        (Scheme::Keyboard, Action::StepFrame) => ".",
+        (Scheme::Keyboard, Action::SlowMotion) => "M",
+        (Scheme::Keyboard, Action::Follow) => "Z",
+        (Scheme::Keyboard, Action::Leave) => "ESC",
+        (Scheme::Keyboard, Action::Replays) => "R",
+        (Scheme::Keyboard, Action::Sort) => "TAB",
+        (Scheme::Keyboard, Action::Delete) => "DELETE",
+        (Scheme::Keyboard, Action::Rewind) => "B",
+        (Scheme::Keyboard, Action::Photo) => "F",
+        (Scheme::Keyboard, Action::NextKnob | Action::TrySuggested | Action::NextField) => "TAB",
+        (Scheme::Keyboard, Action::HideControls) => "H",
+        (Scheme::Keyboard, Action::LeaveMatch) => "Q",
+        (Scheme::Keyboard, Action::ExportData) => "X",
+        (Scheme::Keyboard, Action::ImportData) => "I",
+        (Scheme::Keyboard, Action::MainMenu) => "N",
+        (Scheme::Touch, Action::Select) => "a replay",
+        (Scheme::Touch, Action::Choose) => "an entry",
+        (Scheme::Touch, Action::Watch) => "it again",
+        (Scheme::Touch, Action::Change | Action::Retry) => "the top of the screen",
+        (Scheme::Touch, Action::Confirm) => "the bottom of the screen",
+        (Scheme::Touch, Action::Move) => "above or below your racket",
+        (
+            Scheme::Touch,
+            Action::Serve
+            | Action::Start
+            | Action::Resume
+            | Action::PlayAgain
+            | Action::Quit
+            | Action::Recover
+            | Action::TogglePause
+            | Action::WatchAgain
+            | Action::TakePhoto
+            | Action::LeaveMatch,
+        ) => "the screen",
+        (
+            Scheme::Touch,
+            Action::Heatmap
+            | Action::Export
+            | Action::Dismiss
+            | Action::OpenFolder
+            | Action::StepFrame
+            | Action::SlowMotion
+            | Action::Follow
+            | Action::Leave
+            | Action::Replays
+            | Action::Sort
+            | Action::Delete
+            | Action::Rewind
+            | Action::Photo
+            | Action::Tune
+            | Action::NextKnob
+            | Action::HideControls
+            | Action::TrySuggested
+            | Action::NextField
+            | Action::ExportData
+            | Action::ImportData
+            | Action::Browse
+            | Action::MainMenu,
+        ) => return None,
+    };
+    Some(glyph)
+}
+
+/// Returns the text telling how to do `action` with `scheme`, or `None` if
+/// the action cannot be done with it.
+pub fn prompt(action: Action, scheme: Scheme) -> Option<String> {
+    let glyph = glyph(action, scheme)?;
+    let verb = match (scheme, action) {
+        (Scheme::Keyboard, Action::Rewind) | (Scheme::Touch, Action::LeaveMatch) => "Hold",
+        (Scheme::Keyboard, _) => "Press",
+        (Scheme::Touch, Action::Move) => "Touch",
+        (Scheme::Touch, _) => "Tap",
+    };
+    Some(format!("{verb} {glyph} to {}", action.description()))
+}
+
+/// Returns [`prompt`] between parentheses, as shown under the titles of the
+/// screens, or an empty string if `action` cannot be done with `scheme`.
+pub fn hint(action: Action, scheme: Scheme) -> String {
+    prompt(action, scheme)
+        .map(|prompt| format!("({prompt})"))
+        .unwrap_or_default()
+}
+
+/// Label showing the [`hint`] of an action, only written again when the
+/// players switch to another control scheme.
+pub struct HintLabel {
+    action: Action,
+    scheme: Option<Scheme>,
+    label: Label,
+}
+
+impl HintLabel {
+    pub fn new(action: Action, font_size: u16) -> Self {
+        Self {
+            action,
+            scheme: None,
+            label: Label::new("", font_size),
+        }
+    }
+
+    pub fn update(&mut self, scheme: Scheme) {
+        if self.scheme != Some(scheme) {
+            self.scheme = Some(scheme);
+            self.label.set_text(&hint(self.action, scheme));
+        }
+    }
+
+    pub fn draw_center(&self, x: f32, y: f32) {
+        self.label.draw_center(x, y);
+    }
+}
+
+/// Labels showing the [`prompt`]s of several actions, one per line, leaving
+/// out the ones that cannot be done. They are only written again when the
+/// players switch to another control scheme.
+pub struct Prompts {
+    actions: Vec<Action>,
+    font_size: u16,
+    scheme: Option<Scheme>,
+    labels: Vec<Label>,
+}
+
+impl Prompts {
+    pub fn new(actions: &[Action], font_size: u16) -> Self {
+        Self {
+            actions: actions.to_vec(),
+            font_size,
+            scheme: None,
+            labels: Vec::new(),
+        }
+    }
+
+    /// Replaces the actions prompted, rewriting the labels if they changed.
+    pub fn set_actions(&mut self, actions: &[Action]) {
+        if self.actions != actions {
+            self.actions = actions.to_vec();
+            self.scheme = None;
+        }
+    }
+
+    pub fn update(&mut self, scheme: Scheme) {
+        if self.scheme == Some(scheme) {
+            return;
+        }
+        self.scheme = Some(scheme);
+        self.labels.clear();
+        for &action in &self.actions {
+            if let Some(prompt) = prompt(action, scheme) {
+                self.labels.push(Label::new(&prompt, self.font_size));
+            }
+        }
+    }
+
+    pub fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+}
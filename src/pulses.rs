@@ -0,0 +1,91 @@
+//! Effects of the CRT shader pulsing on the events of a match: the colors
+//! split on hard hits and the edges of the screen darken on match points.
+use macroquad::prelude::*;
+use pong::{
+    entity::Entity,
+    sim::{Event, Simulation},
+};
+
+/// Speed of the ball after a hit, relative to the speed it is served at,
+/// from which the hit is hard.
+const HARD_HIT_SPEED: f32 = 2.;
+/// Length and strength of the pulses.
+const ABERRATION_SECS: f32 = 0.2;
+const ABERRATION_STRENGTH: f32 = 1.;
+const VIGNETTE_SECS: f32 = 1.;
+const VIGNETTE_STRENGTH: f32 = 1.;
+
+/// Uniform of the shader fading from a strength to 0.
+#[derive(Clone, Copy, Default)]
+struct Pulse {
+    secs: f32,
+    length: f32,
+    strength: f32,
+}
+
+impl Pulse {
+    fn start(&mut self, length: f32, strength: f32) {
+        *self = Self {
+            secs: length,
+            length,
+            strength,
+        };
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.secs = (self.secs - dt).max(0.);
+    }
+
+    fn value(&self) -> f32 {
+        if self.secs <= 0. {
+            return 0.;
+        }
+        self.strength * self.secs / self.length
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Pulses {
+    aberration: Pulse,
+    vignette: Pulse,
+}
+
+impl Pulses {
+    /// Starts the pulse of `event`, which has just happened in `sim`, if it
+    /// has one.
+    pub fn trigger(&mut self, event: Event, sim: &Simulation) {
+        match event {
+            Event::RacketBounce => {
+                let hard = sim.entities.iter().any(|entity| match entity {
+                    Entity::Ball(ball) => ball.speed >= sim.physics.init_speed * HARD_HIT_SPEED,
+                    _ => false,
+                });
+                if hard {
+                    self.aberration.start(ABERRATION_SECS, ABERRATION_STRENGTH);
+                }
+            }
+            Event::MatchPoint(_) => self.vignette.start(VIGNETTE_SECS, VIGNETTE_STRENGTH),
+            Event::WallBounce
+            | Event::TargetHit
+            | Event::Point(_)
+            | Event::SetWon(_)
+            | Event::Winner(_) => {}
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.aberration.update(dt);
+        self.vignette.update(dt);
+    }
+
+    /// Returns whether any pulse is fading.
+    pub fn is_active(&self) -> bool {
+        self.aberration.value() > 0. || self.vignette.value() > 0.
+    }
+
+    /// Sets the uniforms of the pulses in `material`, the CRT shader.
+    pub fn set_uniforms(&self, material: &Material) {
+        material.set_uniform("Aberration", self.aberration.value());
+        material.set_uniform("VignettePulse", self.vignette.value());
+    }
+}
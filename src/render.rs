@@ -0,0 +1,132 @@
+//! Offline rendering of replays.
+//!
+//! A replay is simulated again and every frame is saved as a numbered PNG
+//! image, drawn through the CRT effect, so videos can be assembled from them
+//! with other tools. Frames are rendered as fast as possible instead of in
+//! real time, and each one is shown in the window while it is saved.
+use std::{io, path::Path};
+
+use macroquad::prelude::*;
+use pong::{
+    sim::{Simulation, TICK_RATE},
+    trace::Trace,
+};
+
+use crate::{
+    assets::Assets,
+    draw::{self, Canvas, Crt, BACKGROUND_COLOR},
+    label::Label,
+    pulses::Pulses,
+    settings::{Palette, Settings},
+};
+
+/// Frames rendered for every second of the match.
+pub const FRAME_RATE: u32 = 60;
+
+/// Renders the frames of `trace` into `out`, creating it if needed, with
+/// the CRT effect and the outline of `settings`. Returns the number of
+/// frames rendered.
+pub async fn run(
+    trace: &Trace,
+    out: &Path,
+    assets: &Assets,
+    settings: &Settings,
+) -> io::Result<u32> {
+    std::fs::create_dir_all(out)?;
+
+    let mut sim = Simulation::with_rules(trace.court.clone(), trace.physics, trace.seed);
+    sim.lineup = trace.lineup;
+    sim.rules = trace.rules;
+    sim.reset(trace.seed);
+    let court = sim.court.rect();
+    let canvas = Canvas::new(&sim.court);
+    // The frames go through the CRT effect into an image of their own.
+    let output = Canvas::new(&sim.court);
+    let mut score_label = Label::new("", 75);
+
+    let steps_per_frame = (TICK_RATE / FRAME_RATE).max(1) as usize;
+    let mut controls = trace.controls().chunks(steps_per_frame);
+    let mut frame = 0;
+    loop {
+        score_label.set_text(&format!("{} - {}", sim.scores.0, sim.scores.1));
+        set_camera(&canvas.camera);
+        clear_background(BACKGROUND_COLOR);
+        draw::draw_walls(&sim.court, sim.tick);
+        score_label.draw_center(court.w * 0.5, 30.);
+        draw::draw_entities(&sim.entities, settings.outline);
+
+        let crt = settings.crt.then_some(Crt::default());
+        draw_through_crt(&canvas, &output, assets, crt, settings.palette);
+        save_png(&output, &out.join(format!("frame-{frame:06}.png")))?;
+        frame += 1;
+
+        set_default_camera();
+        clear_background(BLACK);
+        let viewport = draw::viewport(&sim.court);
+        draw_texture_ex(
+            &output.target.texture,
+            viewport.x,
+            viewport.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(viewport.size()),
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+        next_frame().await;
+
+        let Some(controls) = controls.next() else {
+            break;
+        };
+        for &controls in controls {
+            sim.step(controls);
+        }
+        sim.clear_events();
+    }
+    Ok(frame)
+}
+
+/// Draws the image of `canvas` on the whole of `output`, through the CRT
+/// effect with `crt` if given and reduced to `palette`. The scanlines don't
+/// roll, as they would depend on the time it takes to render.
+pub fn draw_through_crt(
+    canvas: &Canvas,
+    output: &Canvas,
+    assets: &Assets,
+    crt: Option<Crt>,
+    palette: Palette,
+) {
+    set_camera(&output.camera);
+    clear_background(BLACK);
+    if crt.is_some() || palette != Palette::Off {
+        crt.unwrap_or(Crt::NONE).use_material(
+            &assets.crt_material,
+            0.,
+            palette,
+            0.,
+            &Pulses::default(),
+        );
+    }
+    draw_texture_ex(
+        &canvas.target.texture,
+        0.,
+        0.,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(output.size),
+            flip_y: true,
+            ..Default::default()
+        },
+    );
+    gl_use_default_material();
+}
+
+/// Saves the image of `canvas` as a PNG file at `path`.
+pub fn save_png(canvas: &Canvas, path: &Path) -> io::Result<()> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path is not UTF-8"))?;
+    canvas.target.texture.get_texture_data().export_png(path);
+    Ok(())
+}
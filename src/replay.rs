@@ -0,0 +1,66 @@
+//! Recent history of the match.
+use std::collections::VecDeque;
+
+use pong::sim::{Event, Simulation};
+
+/// State of the simulation drawn on a frame and the events that led to it.
+pub struct Frame {
+    pub sim: Simulation,
+    pub events: Vec<Event>,
+}
+
+/// Ring buffer with the last frames of the match.
+pub struct ReplayBuffer {
+    frames: VecDeque<Frame>,
+    capacity: usize,
+}
+
+impl ReplayBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Appends a frame, dropping the oldest one if the buffer is full.
+    pub fn push(&mut self, sim: &Simulation, events: &[Event]) {
+        if self.frames.len() < self.capacity {
+            self.frames.push_back(Frame {
+                sim: sim.clone(),
+                events: events.to_vec(),
+            });
+            return;
+        }
+
+        // Reuse the allocations of the oldest frame.
+        let Some(mut frame) = self.frames.pop_front() else {
+            return;
+        };
+        frame.sim.clone_from(sim);
+        frame.events.clear();
+        frame.events.extend_from_slice(events);
+        self.frames.push_back(frame);
+    }
+
+    /// Drops the frames after `tick`, always keeping the oldest one, and
+    /// returns the latest frame left.
+    pub fn rewind_to(&mut self, tick: u64) -> Option<&Frame> {
+        while self.frames.len() > 1 && self.frames.back().is_some_and(|f| f.sim.tick > tick) {
+            self.frames.pop_back();
+        }
+        self.frames.back()
+    }
+
+    pub fn latest(&self) -> Option<&Frame> {
+        self.frames.back()
+    }
+
+    /// Returns the last frame in which the ball bounced off a racket.
+    pub fn last_hit(&self) -> Option<&Frame> {
+        self.frames
+            .iter()
+            .rev()
+            .find(|frame| frame.events.contains(&Event::RacketBounce))
+    }
+}
@@ -0,0 +1,73 @@
+//! Replays of the finished matches.
+//!
+//! The trace of every finished match is saved in the replays directory,
+//! described with its players, date and score so it can be listed. Only the
+//! latest [`MAX_REPLAYS`] are kept.
+use pong::trace::Trace;
+
+use crate::storage::{self, Dir};
+
+/// Most replays kept. The oldest ones are deleted to make room for new ones.
+const MAX_REPLAYS: usize = 50;
+
+const PREFIX: &str = "replay-";
+pub const EXTENSION: &str = ".trace";
+
+/// Replay saved in the replays directory.
+pub struct Replay {
+    /// Name of the entry the replay is saved as.
+    pub name: String,
+    pub trace: Trace,
+}
+
+/// Saves `trace` as a new replay and deletes the oldest replays beyond
+/// [`MAX_REPLAYS`].
+pub fn save(trace: &Trace) {
+    // Names sort by date, and the seed tells apart matches finished within
+    // the same second.
+    let date = trace.meta.date.unwrap_or_default();
+    let name = format!("{PREFIX}{date:012}-{}{EXTENSION}", trace.seed);
+    if let Err(err) = storage::save(Dir::Replays, &name, &trace.to_string()) {
+        eprintln!("error: save replay: {err}");
+        return;
+    }
+
+    let mut names = names();
+    if names.len() > MAX_REPLAYS {
+        names.sort();
+        for name in &names[..names.len() - MAX_REPLAYS] {
+            remove(name);
+        }
+    }
+}
+
+/// Returns every saved replay that can be loaded.
+pub fn load_all() -> Vec<Replay> {
+    names()
+        .into_iter()
+        .filter_map(|name| {
+            let contents = storage::load(Dir::Replays, &name)?;
+            match contents.parse() {
+                Ok(trace) => Some(Replay { name, trace }),
+                Err(err) => {
+                    eprintln!("error: parse replay {name}: {err}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Deletes the replay saved as `name`.
+pub fn remove(name: &str) {
+    if let Err(err) = storage::remove(Dir::Replays, name) {
+        eprintln!("error: remove replay {name}: {err}");
+    }
+}
+
+fn names() -> Vec<String> {
+    storage::list(Dir::Replays)
+        .into_iter()
+        .filter(|name| name.starts_with(PREFIX) && name.ends_with(EXTENSION))
+        .collect()
+}
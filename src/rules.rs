@@ -0,0 +1,159 @@
+//! Rules of the matches.
+//!
+//! A [`Rules`] combines how many points win a set, how many sets are played,
+//! whether sets must be won by two points, who serves and whether the match
+//! has a time limit. A few named [presets](PRESETS) are provided. The rules
+//! only apply to the lineups played point by point, the single, co-op and
+//! juggler matches, and only single and co-op matches have a service.
+use crate::sim::Service;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rules {
+    /// Points that win a set.
+    pub win_score: i32,
+    /// Most sets played. The first side to win more than half of them wins
+    /// the match.
+    pub best_of: u32,
+    /// Whether sets must be won by two points. Otherwise the first side to
+    /// reach [`Rules::win_score`] wins the set.
+    pub deuce: bool,
+    pub service: Service,
+    /// Length of the match in seconds, if it is timed. When the time is up
+    /// the side ahead wins, and tied matches go on until the next point.
+    pub time_limit: Option<u32>,
+}
+
+impl Rules {
+    pub const CLASSIC: Rules = Rules {
+        win_score: 5,
+        best_of: 1,
+        deuce: false,
+        service: Service::Classic,
+        time_limit: None,
+    };
+
+    pub const TABLE_TENNIS_11: Rules = Rules {
+        win_score: 11,
+        best_of: 3,
+        deuce: true,
+        service: Service::TableTennis,
+        time_limit: None,
+    };
+
+    pub const QUICK_3: Rules = Rules {
+        win_score: 3,
+        best_of: 1,
+        deuce: false,
+        service: Service::Classic,
+        time_limit: Some(120),
+    };
+
+    /// Returns the preset called `spec` or the rules described by it as
+    /// returned by [`Rules::spec`].
+    ///
+    /// Rules are described by the win score, the number of sets, `deuce` or
+    /// `no-deuce`, the service and the time limit in seconds, 0 meaning
+    /// none, separated by commas, like `11,3,deuce,table-tennis,0`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        if let Some(preset) = PRESETS.iter().find(|preset| preset.name == spec) {
+            return Some(preset.rules);
+        }
+        let mut fields = spec.split(',');
+        let rules = Rules {
+            win_score: fields.next()?.parse().ok()?,
+            best_of: fields.next()?.parse().ok()?,
+            deuce: match fields.next()? {
+                "deuce" => true,
+                "no-deuce" => false,
+                _ => return None,
+            },
+            service: Service::parse(fields.next()?)?,
+            time_limit: match fields.next()?.parse().ok()? {
+                0 => None,
+                secs => Some(secs),
+            },
+        };
+        (fields.next().is_none() && rules.is_valid()).then_some(rules)
+    }
+
+    /// Returns the name of the preset matching the rules or, if there is
+    /// none, their description. The result can be passed to
+    /// [`Rules::parse`].
+    pub fn spec(&self) -> String {
+        if let Some(name) = self.preset_name() {
+            return name.to_owned();
+        }
+        format!(
+            "{},{},{},{},{}",
+            self.win_score,
+            self.best_of,
+            if self.deuce { "deuce" } else { "no-deuce" },
+            self.service,
+            self.time_limit.unwrap_or(0)
+        )
+    }
+
+    pub fn preset_name(&self) -> Option<&'static str> {
+        PRESETS
+            .iter()
+            .find(|preset| preset.rules == *self)
+            .map(|preset| preset.name)
+    }
+
+    /// Returns whether a match can be played with the rules: sets are won
+    /// with at least one point and an odd number of them is played, so
+    /// there are no ties.
+    pub fn is_valid(&self) -> bool {
+        self.win_score > 0 && self.best_of % 2 == 1 && self.time_limit != Some(0)
+    }
+
+    /// Returns the sets that win the match.
+    pub fn sets_to_win(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+
+    /// Returns whether a side with `score` points wins the set against
+    /// another one with `other` points.
+    pub fn wins_set(&self, score: i32, other: i32) -> bool {
+        score >= self.win_score && (!self.deuce || score - other >= 2)
+    }
+
+    /// Returns whether a set at `scores` is in deuce, where every point
+    /// counts until a side is two points ahead.
+    pub fn is_deuce(&self, scores: (i32, i32)) -> bool {
+        self.deuce && scores.0 >= self.win_score - 1 && scores.1 >= self.win_score - 1
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+/// A named set of rules.
+pub struct Preset {
+    /// Name used in the command line, the traces and the match codes.
+    pub name: &'static str,
+    /// Name shown to the players.
+    pub title: &'static str,
+    pub rules: Rules,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "classic",
+        title: "CLASSIC",
+        rules: Rules::CLASSIC,
+    },
+    Preset {
+        name: "tt11",
+        title: "TABLE TENNIS 11",
+        rules: Rules::TABLE_TENNIS_11,
+    },
+    Preset {
+        name: "quick3",
+        title: "QUICK 3",
+        rules: Rules::QUICK_3,
+    },
+];
@@ -0,0 +1,72 @@
+//! Rulesets saved by the players.
+//!
+//! Every ruleset is kept in its own line, with its name and its rules as
+//! returned by [`Rules::spec`] separated by a tab. The presets come first in
+//! the list and are never saved.
+use std::io;
+
+use pong::rules::{self, Rules};
+
+use crate::storage::{Dir, Format};
+
+/// Entry where the rulesets are kept.
+const RULESETS: Format = Format {
+    dir: Dir::Saves,
+    name: "rules.txt",
+    migrations: &[],
+};
+
+/// Number of letters of the name of a ruleset.
+pub const NAME_LEN: usize = 3;
+
+#[derive(Clone)]
+pub struct Ruleset {
+    pub name: String,
+    pub rules: Rules,
+}
+
+/// Returns the presets followed by the saved rulesets. Lines that cannot be
+/// parsed are skipped.
+pub fn load() -> Vec<Ruleset> {
+    let mut rulesets: Vec<_> = rules::PRESETS
+        .iter()
+        .map(|preset| Ruleset {
+            name: preset.title.to_owned(),
+            rules: preset.rules,
+        })
+        .collect();
+    for line in RULESETS.load().unwrap_or_default().lines() {
+        let Some((name, spec)) = line.split_once('\t') else {
+            continue;
+        };
+        let Some(rules) = Rules::parse(spec) else {
+            continue;
+        };
+        rulesets.push(Ruleset {
+            name: name.to_owned(),
+            rules,
+        });
+    }
+    rulesets
+}
+
+/// Saves the rulesets in `rulesets` that are not presets.
+pub fn save(rulesets: &[Ruleset]) -> io::Result<()> {
+    let contents: String = rulesets
+        .iter()
+        .skip(rules::PRESETS.len())
+        .map(|ruleset| format!("{}\t{}\n", ruleset.name, ruleset.rules.spec()))
+        .collect();
+    RULESETS.save(&contents)
+}
+
+/// Returns the rules of the ruleset called `name`, ignoring case, or the
+/// ones described by it as accepted by [`Rules::parse`].
+pub fn find(name: &str) -> Option<Rules> {
+    Rules::parse(name).or_else(|| {
+        load()
+            .into_iter()
+            .find(|ruleset| ruleset.name.eq_ignore_ascii_case(name))
+            .map(|ruleset| ruleset.rules)
+    })
+}
@@ -0,0 +1,432 @@
+//! Fixed-step driver for the simulation.
+//!
+//! On native platforms the simulation runs on its own thread, so stalls in
+//! the render loop can't cause physics hitches. Controls and commands are
+//! sent to it over a channel and every step publishes a snapshot back in a
+//! shared slot, which only keeps the latest one. The wasm build has no
+//! threads and steps the simulation from the render loop.
+#[cfg(not(target_family = "wasm"))]
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    bot::{self, Observation, PongBot, Telemetry},
+    court::Court,
+    physics::Physics,
+    rules::Rules,
+    script::InputScript,
+    sim::{Controls, Event, Lineup, MatchState, Side, Simulation, TICK_SECS},
+    trace::Trace,
+};
+
+/// Maximum amount of time the simulation tries to catch up after a stall.
+const MAX_LAG_SECS: f32 = 0.25;
+
+/// Slowest speed the simulation can run at.
+const MIN_TIME_SCALE: f32 = 0.01;
+
+pub struct Runner {
+    latest: Simulation,
+    /// Telemetry of the bots on the left and right sides after the latest
+    /// step.
+    telemetry: BotTelemetry,
+    backend: Backend,
+}
+
+type BotTelemetry = (Option<Telemetry>, Option<Telemetry>);
+
+enum Backend {
+    Inline {
+        driver: Box<Driver>,
+        lag: f32,
+    },
+    #[cfg(not(target_family = "wasm"))]
+    Threaded {
+        commands: Sender<Command>,
+        snapshot: Arc<Mutex<Snapshot>>,
+    },
+}
+
+/// Latest state published by the simulation thread. The render loop swaps
+/// it with its previous one, whose allocations the next step reuses.
+#[cfg(not(target_family = "wasm"))]
+struct Snapshot {
+    sim: Simulation,
+    telemetry: BotTelemetry,
+    /// Whether `sim` was published since the render loop last took it.
+    fresh: bool,
+    /// Events of the steps published since the render loop last took them.
+    events: Vec<Event>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Snapshot {
+    /// Replaces the snapshot with `sim` and adds its events to the ones
+    /// not taken yet.
+    fn publish(&mut self, sim: &Simulation, telemetry: BotTelemetry) {
+        self.sim.clone_from(sim);
+        self.telemetry = telemetry;
+        self.fresh = true;
+        self.events.extend_from_slice(sim.events());
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+enum Command {
+    Sim(Box<dyn FnOnce(&mut Simulation) + Send>),
+    Driver(Box<dyn FnOnce(&mut Driver) + Send>),
+}
+
+/// Everything besides the simulation itself that is needed to step it.
+struct Driver {
+    controls: Controls,
+    left_bot: Option<Box<dyn PongBot>>,
+    right_bot: Option<Box<dyn PongBot>>,
+    trace: Option<Trace>,
+    /// Script replacing the controls of the players.
+    script: Option<InputScript>,
+    /// Controls of the players recorded so far.
+    inputs: Option<InputScript>,
+    paused: bool,
+    time_scale: f32,
+}
+
+impl Default for Driver {
+    fn default() -> Self {
+        Self {
+            controls: Controls::default(),
+            left_bot: None,
+            right_bot: None,
+            trace: None,
+            script: None,
+            inputs: None,
+            paused: false,
+            time_scale: 1.,
+        }
+    }
+}
+
+impl Driver {
+    fn telemetry(&self) -> BotTelemetry {
+        (
+            self.left_bot.as_ref().and_then(|bot| bot.telemetry()),
+            self.right_bot.as_ref().and_then(|bot| bot.telemetry()),
+        )
+    }
+
+    fn step(&mut self, sim: &mut Simulation) {
+        let mut controls = match &self.script {
+            Some(script) => script.controls_at(sim.tick),
+            None => self.controls,
+        };
+        // The inputs are recorded before the bots take over their sides.
+        if let Some(inputs) = &mut self.inputs {
+            if sim.state == MatchState::Playing {
+                inputs.push(sim.tick, controls);
+            }
+        }
+        if let Some(bot) = &mut self.left_bot {
+            controls.left = bot.act(&Observation::new(sim, Side::Left));
+        }
+        if let Some(bot) = &mut self.right_bot {
+            controls.right = bot.act(&Observation::new(sim, Side::Right));
+        }
+
+        if let Some(trace) = &mut self.trace {
+            if sim.state == MatchState::Playing {
+                trace.push(controls);
+            }
+        }
+
+        sim.step(controls);
+    }
+}
+
+impl Runner {
+    /// Creates a runner that steps the simulation from the caller's thread.
+    pub fn inline(sim: Simulation) -> Self {
+        Self {
+            latest: sim,
+            telemetry: (None, None),
+            backend: Backend::Inline {
+                driver: Box::default(),
+                lag: 0.,
+            },
+        }
+    }
+
+    /// Creates a runner that steps the simulation on a background thread.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn threaded(sim: Simulation) -> Self {
+        let (commands, commands_rx) = mpsc::channel();
+        let snapshot = Arc::new(Mutex::new(Snapshot {
+            sim: sim.clone(),
+            telemetry: (None, None),
+            fresh: false,
+            events: Vec::new(),
+        }));
+
+        let thread_sim = sim.clone();
+        let published = Arc::clone(&snapshot);
+        thread::Builder::new()
+            .name("simulation".to_owned())
+            .spawn(move || run(thread_sim, commands_rx, &published))
+            .expect("spawn simulation thread");
+
+        Self {
+            latest: sim,
+            telemetry: (None, None),
+            backend: Backend::Threaded { commands, snapshot },
+        }
+    }
+
+    /// Returns the most recent state of the simulation.
+    pub fn sim(&self) -> &Simulation {
+        &self.latest
+    }
+
+    /// Returns what the bot on `side` was thinking during the latest step, if
+    /// there is a bot and it explains itself.
+    pub fn telemetry(&self, side: Side) -> Option<Telemetry> {
+        match side {
+            Side::Left => self.telemetry.0,
+            Side::Right => self.telemetry.1,
+        }
+    }
+
+    /// Runs `f` on the simulation before its next step.
+    pub fn apply(&mut self, f: impl FnOnce(&mut Simulation) + Send + 'static) {
+        match &mut self.backend {
+            Backend::Inline { .. } => f(&mut self.latest),
+            #[cfg(not(target_family = "wasm"))]
+            Backend::Threaded { commands, .. } => {
+                let _ = commands.send(Command::Sim(Box::new(f)));
+            }
+        }
+    }
+
+    fn apply_driver(&mut self, f: impl FnOnce(&mut Driver) + Send + 'static) {
+        match &mut self.backend {
+            Backend::Inline { driver, lag } => {
+                f(driver);
+                if driver.paused {
+                    *lag = 0.;
+                }
+            }
+            #[cfg(not(target_family = "wasm"))]
+            Backend::Threaded { commands, .. } => {
+                let _ = commands.send(Command::Driver(Box::new(f)));
+            }
+        }
+    }
+
+    /// Sets the controls used by the following steps.
+    pub fn set_controls(&mut self, controls: Controls) {
+        self.apply_driver(move |driver| driver.controls = controls);
+    }
+
+    /// Lets `bot` control the racket on `side`, or gives the control back to
+    /// the player if `None`.
+    pub fn set_bot(&mut self, side: Side, bot: Option<Box<dyn PongBot>>) {
+        self.apply_driver(move |driver| match side {
+            Side::Left => driver.left_bot = bot,
+            Side::Right => driver.right_bot = bot,
+        });
+    }
+
+    /// Tells the bots that a match started from `seed`.
+    pub fn reset_bots(&mut self, seed: u64) {
+        self.apply_driver(move |driver| {
+            for (side, bot) in [
+                (Side::Left, &mut driver.left_bot),
+                (Side::Right, &mut driver.right_bot),
+            ] {
+                if let Some(bot) = bot {
+                    bot.reset(bot::seed(seed, side));
+                }
+            }
+        });
+    }
+
+    /// Stops or resumes stepping the simulation.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.apply_driver(move |driver| driver.paused = paused);
+    }
+
+    /// Sets how fast the simulation runs relative to real time. For example,
+    /// 0.5 plays it at half speed.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        let scale = scale.max(MIN_TIME_SCALE);
+        self.apply_driver(move |driver| driver.time_scale = scale);
+    }
+
+    /// Starts recording the controls of every step into a new trace. The
+    /// arguments must be the ones the current match was started with.
+    pub fn record(
+        &mut self,
+        court: Court,
+        physics: Physics,
+        lineup: Lineup,
+        rules: Rules,
+        seed: u64,
+    ) {
+        self.apply_driver(move |driver| {
+            driver.trace = Some(Trace::new(court, physics, lineup, rules, seed));
+        });
+    }
+
+    /// Stops recording and returns the recorded trace, if any.
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        match &mut self.backend {
+            Backend::Inline { driver, .. } => driver.trace.take(),
+            #[cfg(not(target_family = "wasm"))]
+            Backend::Threaded { .. } => {
+                let (tx, rx) = mpsc::channel();
+                self.apply_driver(move |driver| {
+                    let _ = tx.send(driver.trace.take());
+                });
+                rx.recv().ok().flatten()
+            }
+        }
+    }
+
+    /// Replaces the controls of the players with `script` from the next
+    /// step, or gives them back if `None`. The script must start with the
+    /// match.
+    pub fn play_script(&mut self, script: Option<InputScript>) {
+        self.apply_driver(move |driver| driver.script = script);
+    }
+
+    /// Starts recording the controls of the players into a new input
+    /// script.
+    pub fn record_inputs(&mut self) {
+        self.apply_driver(|driver| driver.inputs = Some(InputScript::default()));
+    }
+
+    /// Stops recording the controls of the players and returns the recorded
+    /// script, if any.
+    pub fn take_inputs(&mut self) -> Option<InputScript> {
+        match &mut self.backend {
+            Backend::Inline { driver, .. } => driver.inputs.take(),
+            #[cfg(not(target_family = "wasm"))]
+            Backend::Threaded { .. } => {
+                let (tx, rx) = mpsc::channel();
+                self.apply_driver(move |driver| {
+                    let _ = tx.send(driver.inputs.take());
+                });
+                rx.recv().ok().flatten()
+            }
+        }
+    }
+
+    /// Catches up with the simulation after `dt` seconds of real time and
+    /// appends the events that happened meanwhile to `events`.
+    pub fn update(&mut self, dt: f32, events: &mut Vec<Event>) {
+        match &mut self.backend {
+            Backend::Inline { driver, lag } => {
+                if driver.paused {
+                    return;
+                }
+                *lag = (*lag + dt * driver.time_scale).min(MAX_LAG_SECS);
+                while *lag >= TICK_SECS {
+                    *lag -= TICK_SECS;
+                    driver.step(&mut self.latest);
+                }
+                events.extend_from_slice(self.latest.events());
+                self.latest.clear_events();
+                self.telemetry = driver.telemetry();
+            }
+            #[cfg(not(target_family = "wasm"))]
+            Backend::Threaded { snapshot, .. } => {
+                let mut snapshot = snapshot.lock().expect("lock snapshot");
+                if snapshot.fresh {
+                    snapshot.fresh = false;
+                    std::mem::swap(&mut self.latest, &mut snapshot.sim);
+                    self.latest.clear_events();
+                    self.telemetry = snapshot.telemetry;
+                }
+                events.append(&mut snapshot.events);
+            }
+        }
+    }
+}
+
+/// Sleeps until `deadline`. The OS scheduler is only trusted with most of the
+/// time and the rest is spent spinning.
+#[cfg(not(target_family = "wasm"))]
+pub fn sleep_until(deadline: Instant) {
+    const SPIN: Duration = Duration::from_millis(2);
+
+    let now = Instant::now();
+    if deadline > now + SPIN {
+        thread::sleep(deadline - now - SPIN);
+    }
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn run(mut sim: Simulation, commands: Receiver<Command>, snapshot: &Mutex<Snapshot>) {
+    let tick = Duration::from_secs_f32(TICK_SECS);
+    let max_lag = Duration::from_secs_f32(MAX_LAG_SECS);
+
+    let mut driver = Driver::default();
+    let mut next_tick = Instant::now();
+    loop {
+        loop {
+            // While paused there is nothing to do until the next command.
+            let command = if driver.paused {
+                commands.recv().map_err(|_| TryRecvError::Disconnected)
+            } else {
+                commands.try_recv()
+            };
+            match command {
+                Ok(Command::Sim(f)) => {
+                    f(&mut sim);
+                    // No step publishes the change while paused.
+                    if driver.paused {
+                        let telemetry = driver.telemetry();
+                        snapshot
+                            .lock()
+                            .expect("lock snapshot")
+                            .publish(&sim, telemetry);
+                        sim.clear_events();
+                    }
+                }
+                Ok(Command::Driver(f)) => {
+                    let was_paused = driver.paused;
+                    f(&mut driver);
+                    if was_paused && !driver.paused {
+                        next_tick = Instant::now();
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+
+        driver.step(&mut sim);
+        let telemetry = driver.telemetry();
+        snapshot
+            .lock()
+            .expect("lock snapshot")
+            .publish(&sim, telemetry);
+        sim.clear_events();
+
+        next_tick += tick.div_f32(driver.time_scale);
+        let now = Instant::now();
+        if now > next_tick + max_lag {
+            next_tick = now;
+        }
+        if next_tick > now {
+            thread::sleep(next_tick - now);
+        }
+    }
+}
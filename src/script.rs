@@ -0,0 +1,138 @@
+//! Input scripts.
+//!
+//! Unlike traces, which describe a whole match, a script only holds the
+//! controls of the players, as read from their keys before the bots take
+//! over their sides, so the same inputs can be fed to any match. They are
+//! meant to be written or edited by hand to reproduce a bug with frame-perfect
+//! inputs, and are stored as text:
+//!
+//! ```text
+//! pong-inputs 1
+//! # Up on the left from step 120, down on the right from 341.
+//! 0 S S
+//! 120 U S
+//! 341 S D
+//! ```
+//!
+//! Every line holds the step of the match from which it applies followed by
+//! the motion of the left and right rackets: `S` (stop), `U` (up) or `D`
+//! (down). A third motion moves the partner racket of co-op matches. The
+//! motions last until the step of the next line, and the ones of the last
+//! line until the end of the match. Steps are counted from the start of the
+//! match and must increase from line to line. Empty lines and lines starting
+//! with `#` are ignored.
+use std::{fmt, str::FromStr};
+
+use crate::{
+    sim::{Controls, Motion},
+    trace::{motion_char, parse_motion},
+};
+
+const MAGIC: &str = "pong-inputs 1";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputScript {
+    /// Steps where the controls change, in increasing order, with the
+    /// controls used from then on.
+    changes: Vec<(u64, Controls)>,
+}
+
+impl InputScript {
+    /// Records that `controls` were used on step `tick`. Steps must be
+    /// pushed in increasing order.
+    pub fn push(&mut self, tick: u64, controls: Controls) {
+        match self.changes.last() {
+            Some(&(last, _)) if last >= tick => {}
+            Some(&(_, last)) if last == controls => {}
+            _ => self.changes.push((tick, controls)),
+        }
+    }
+
+    /// Returns the controls used on step `tick`. Players stand still until
+    /// the first line of the script.
+    pub fn controls_at(&self, tick: u64) -> Controls {
+        let pos = self.changes.partition_point(|&(start, _)| start <= tick);
+        pos.checked_sub(1)
+            .map(|pos| self.changes[pos].1)
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for InputScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{MAGIC}")?;
+        for (tick, controls) in &self.changes {
+            write!(
+                f,
+                "{tick} {} {}",
+                motion_char(controls.left),
+                motion_char(controls.right)
+            )?;
+            if controls.partner != Motion::Stop {
+                write!(f, " {}", motion_char(controls.partner))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when an input script cannot be parsed.
+#[derive(Debug)]
+pub struct ParseScriptError {
+    line: usize,
+    msg: &'static str,
+}
+
+impl fmt::Display for ParseScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl std::error::Error for ParseScriptError {}
+
+impl FromStr for InputScript {
+    type Err = ParseScriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |line, msg| ParseScriptError { line, msg };
+
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line));
+        match lines.next() {
+            Some((_, MAGIC)) => {}
+            _ => return Err(err(1, "missing header")),
+        }
+
+        let mut script = InputScript::default();
+        for (n, line) in lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (Some(tick), Some(left), Some(right)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(err(n, "expected step and two motions"));
+            };
+            let partner = fields.next().unwrap_or("S");
+            if fields.next().is_some() {
+                return Err(err(n, "too many motions"));
+            }
+            let tick: u64 = tick.parse().map_err(|_| err(n, "invalid step"))?;
+            if script.changes.last().is_some_and(|&(last, _)| last >= tick) {
+                return Err(err(n, "steps must increase"));
+            }
+            let controls = Controls {
+                left: parse_motion(left).ok_or(err(n, "invalid motion"))?,
+                right: parse_motion(right).ok_or(err(n, "invalid motion"))?,
+                partner: parse_motion(partner).ok_or(err(n, "invalid motion"))?,
+            };
+            // Lines repeating the previous controls are kept, as they may
+            // document the script.
+            script.changes.push((tick, controls));
+        }
+        Ok(script)
+    }
+}
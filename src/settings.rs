@@ -0,0 +1,507 @@
+//! Preferences of the player.
+//!
+//! The settings are chosen the first time the game is started and kept
+//! between runs afterwards.
+use std::fmt::{self, Write};
+
+use macroquad::input::KeyCode;
+
+use crate::{
+    audio::{Bus, Mixer},
+    input::{KeyCombo, Scheme},
+    latency,
+    storage::{self, Dir, Format},
+};
+
+/// Entry where the settings are kept.
+pub const SETTINGS: Format = Format {
+    dir: Dir::Config,
+    name: "settings.txt",
+    migrations: &[storage::unversioned],
+};
+
+/// Step between the volumes that can be chosen.
+pub const VOLUME_STEP: f32 = 0.1;
+
+/// Frame rates that can be chosen, besides no limit.
+pub const FRAME_RATES: [u32; 4] = [30, 60, 120, 144];
+
+/// How long the settings must stay unchanged before they are saved, in
+/// seconds, so changing them quickly only saves the last ones.
+const AUTOSAVE_DELAY_SECS: f32 = 1.;
+
+/// Language of the texts of the game.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Language {
+    English,
+}
+
+impl Language {
+    /// Every language the game is translated to.
+    pub const ALL: [Language; 1] = [Language::English];
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.to_string() == name)
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Language::English => write!(f, "english"),
+        }
+    }
+}
+
+/// Where the score is shown during the matches.
+#[derive(Clone, Copy, PartialEq)]
+pub enum HudLayout {
+    /// Over the center of the court.
+    Classic,
+    /// Larger and in the bottom left corner, out of the way of the rallies,
+    /// for streams and recordings.
+    Stream,
+}
+
+impl HudLayout {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "classic" => Some(HudLayout::Classic),
+            "stream" => Some(HudLayout::Stream),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HudLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HudLayout::Classic => write!(f, "classic"),
+            HudLayout::Stream => write!(f, "stream"),
+        }
+    }
+}
+
+/// Retro palette the image is reduced to.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Palette {
+    /// The colors are kept.
+    Off,
+    /// Four shades of green, as in the Game Boy.
+    GameBoy,
+    /// Black, magenta, cyan and white, as in the CGA.
+    Cga,
+    /// Shades of amber, as in monochrome monitors.
+    Amber,
+}
+
+impl Palette {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Palette::Off),
+            "gameboy" => Some(Palette::GameBoy),
+            "cga" => Some(Palette::Cga),
+            "amber" => Some(Palette::Amber),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Palette::Off => write!(f, "off"),
+            Palette::GameBoy => write!(f, "gameboy"),
+            Palette::Cga => write!(f, "cga"),
+            Palette::Amber => write!(f, "amber"),
+        }
+    }
+}
+
+/// How the court is framed during the matches.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    /// The whole court is always shown.
+    Fixed,
+    /// The camera zooms in slightly on the action.
+    Dynamic,
+}
+
+impl CameraMode {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "fixed" => Some(CameraMode::Fixed),
+            "dynamic" => Some(CameraMode::Dynamic),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CameraMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CameraMode::Fixed => write!(f, "fixed"),
+            CameraMode::Dynamic => write!(f, "dynamic"),
+        }
+    }
+}
+
+/// Look of the afterimages left by fast rackets.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TrailStyle {
+    Off,
+    White,
+    /// In the colors of the players, like the ball after they hit it.
+    Players,
+}
+
+impl TrailStyle {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(TrailStyle::Off),
+            "white" => Some(TrailStyle::White),
+            "players" => Some(TrailStyle::Players),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TrailStyle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrailStyle::Off => write!(f, "off"),
+            TrailStyle::White => write!(f, "white"),
+            TrailStyle::Players => write!(f, "players"),
+        }
+    }
+}
+
+/// Visual effect that can be uncomfortable for players sensitive to motion
+/// or flashing images.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// Zooming on the winner of a match.
+    Zoom,
+    /// Confetti of many colors.
+    Confetti,
+    /// Tint fading over the court, like the one of slow motion.
+    Tint,
+    /// Scanlines of the CRT effect rolling over the screen.
+    Scanlines,
+    /// Ring closing on the center of the court on every beat of the serve
+    /// countdown.
+    Pulse,
+    /// Afterimages of the fast rackets.
+    Trail,
+    /// Camera moving with the action.
+    Camera,
+    /// Screens collapsing into a bright line when replaced by others.
+    PowerOff,
+    /// Colors splitting on hard hits and the edges of the screen darkening
+    /// on match points.
+    ShaderPulse,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub language: Language,
+    /// Controls the prompts are shown for until the players use others.
+    pub scheme: Scheme,
+    /// Whether the court is drawn through the CRT effect.
+    pub crt: bool,
+    /// Volume of every sound, between 0 and 1.
+    pub volume: f32,
+    /// Volume of every bus, between 0 and 1, in the order of [`Bus::ALL`].
+    pub bus_volumes: [f32; Bus::ALL.len()],
+    /// Whether frames wait for the display to refresh. Changes apply after
+    /// restarting the game.
+    pub vsync: bool,
+    /// Highest frame rate, if any.
+    pub max_fps: Option<u32>,
+    /// Whether effects with motion, flashes or rapid color changes are
+    /// disabled.
+    pub reduced_motion: bool,
+    /// Whether the ball and the rackets have a high-visibility outline.
+    pub outline: bool,
+    /// Whether serves are held for a countdown of three ticks and a tone.
+    pub serve_countdown: bool,
+    /// Whether the score is called as in table tennis, the score of the
+    /// server first, with a marker next to the racket serving.
+    pub table_tennis_score: bool,
+    /// Time the sounds take to be heard, in seconds. Sounds are played this
+    /// much earlier.
+    pub audio_latency: f32,
+    /// Whether the device vibrates on racket hits and points. Only phones
+    /// playing the web version vibrate.
+    pub haptics: bool,
+    /// Whether the HUD shows the momentum of the match.
+    pub momentum_meter: bool,
+    /// Keys hiding and showing the HUD.
+    pub hud_key: KeyCombo,
+    pub hud_layout: HudLayout,
+    /// Whether matches between two sides show the broadcast scoreboard
+    /// instead of the one of `hud_layout`.
+    pub broadcast_hud: bool,
+    pub racket_trail: TrailStyle,
+    pub camera: CameraMode,
+    pub palette: Palette,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: Language::English,
+            scheme: Scheme::Keyboard,
+            crt: true,
+            volume: 1.,
+            bus_volumes: [1.; Bus::ALL.len()],
+            vsync: true,
+            max_fps: None,
+            reduced_motion: false,
+            outline: false,
+            serve_countdown: false,
+            table_tennis_score: false,
+            audio_latency: 0.,
+            haptics: true,
+            momentum_meter: false,
+            hud_key: KeyCombo {
+                ctrl: true,
+                alt: false,
+                shift: false,
+                key: KeyCode::H,
+            },
+            hud_layout: HudLayout::Classic,
+            broadcast_hud: false,
+            racket_trail: TrailStyle::White,
+            camera: CameraMode::Fixed,
+            palette: Palette::Off,
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the saved settings, or returns `None` if they have never been
+    /// saved. Lines that cannot be parsed are skipped.
+    pub fn load() -> Option<Self> {
+        let contents = SETTINGS.load()?;
+        let mut settings = Settings::default();
+        for line in contents.lines() {
+            match line.split_once(' ') {
+                Some(("language", value)) => {
+                    if let Some(language) = Language::parse(value) {
+                        settings.language = language;
+                    }
+                }
+                Some(("controls", "keyboard")) => settings.scheme = Scheme::Keyboard,
+                Some(("controls", "touch")) => settings.scheme = Scheme::Touch,
+                Some(("crt", value)) => settings.crt = value == "on",
+                Some(("volume", value)) => {
+                    if let Ok(volume) = value.parse::<f32>() {
+                        settings.volume = volume.clamp(0., 1.);
+                    }
+                }
+                Some((key, value)) if key.starts_with("volume-") => {
+                    let bus = Bus::ALL
+                        .iter()
+                        .position(|bus| key.strip_prefix("volume-") == Some(bus.name()));
+                    if let (Some(bus), Ok(volume)) = (bus, value.parse::<f32>()) {
+                        settings.bus_volumes[bus] = volume.clamp(0., 1.);
+                    }
+                }
+                Some(("vsync", value)) => settings.vsync = value == "on",
+                Some(("reduced-motion", value)) => settings.reduced_motion = value == "on",
+                Some(("outline", value)) => settings.outline = value == "on",
+                Some(("haptics", value)) => settings.haptics = value == "on",
+                Some(("broadcast-hud", value)) => settings.broadcast_hud = value == "on",
+                Some(("momentum-meter", value)) => settings.momentum_meter = value == "on",
+                Some(("serve-countdown", value)) => settings.serve_countdown = value == "on",
+                Some(("table-tennis-score", value)) => {
+                    settings.table_tennis_score = value == "on";
+                }
+                Some(("audio-latency-ms", value)) => {
+                    if let Ok(ms) = value.parse::<u32>() {
+                        settings.audio_latency = (ms as f32 / 1000.).min(latency::MAX_LATENCY_SECS);
+                    }
+                }
+                Some(("hud-key", value)) => {
+                    if let Some(combo) = KeyCombo::parse(value) {
+                        settings.hud_key = combo;
+                    }
+                }
+                Some(("palette", value)) => {
+                    if let Some(palette) = Palette::parse(value) {
+                        settings.palette = palette;
+                    }
+                }
+                Some(("camera", value)) => {
+                    if let Some(mode) = CameraMode::parse(value) {
+                        settings.camera = mode;
+                    }
+                }
+                Some(("racket-trail", value)) => {
+                    if let Some(style) = TrailStyle::parse(value) {
+                        settings.racket_trail = style;
+                    }
+                }
+                Some(("hud-layout", value)) => {
+                    if let Some(layout) = HudLayout::parse(value) {
+                        settings.hud_layout = layout;
+                    }
+                }
+                Some(("max-fps", "off")) => settings.max_fps = None,
+                Some(("max-fps", value)) => {
+                    if let Ok(fps) = value.parse::<u32>() {
+                        settings.max_fps = Some(fps).filter(|&fps| fps > 0);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(settings)
+    }
+
+    /// Returns whether `effect` can be shown. Every effect that can bother
+    /// sensitive players must be checked here.
+    pub fn allows(&self, effect: Effect) -> bool {
+        match effect {
+            Effect::Zoom
+            | Effect::Confetti
+            | Effect::Tint
+            | Effect::Scanlines
+            | Effect::Pulse
+            | Effect::Trail
+            | Effect::Camera
+            | Effect::PowerOff
+            | Effect::ShaderPulse => !self.reduced_motion,
+        }
+    }
+
+    pub fn save(&self) {
+        let mut contents = String::new();
+        writeln!(contents, "language {}", self.language).expect("format language");
+        let scheme = match self.scheme {
+            Scheme::Keyboard => "keyboard",
+            Scheme::Touch => "touch",
+        };
+        writeln!(contents, "controls {scheme}").expect("format controls");
+        writeln!(contents, "crt {}", if self.crt { "on" } else { "off" }).expect("format crt");
+        writeln!(contents, "volume {:.1}", self.volume).expect("format volume");
+        for (bus, volume) in Bus::ALL.iter().zip(self.bus_volumes) {
+            writeln!(contents, "volume-{} {volume:.1}", bus.name()).expect("format bus volume");
+        }
+        writeln!(contents, "vsync {}", if self.vsync { "on" } else { "off" })
+            .expect("format vsync");
+        match self.max_fps {
+            Some(fps) => writeln!(contents, "max-fps {fps}"),
+            None => writeln!(contents, "max-fps off"),
+        }
+        .expect("format max fps");
+        writeln!(
+            contents,
+            "reduced-motion {}",
+            if self.reduced_motion { "on" } else { "off" }
+        )
+        .expect("format reduced motion");
+        writeln!(
+            contents,
+            "outline {}",
+            if self.outline { "on" } else { "off" }
+        )
+        .expect("format outline");
+        writeln!(
+            contents,
+            "serve-countdown {}",
+            if self.serve_countdown { "on" } else { "off" }
+        )
+        .expect("format serve countdown");
+        writeln!(
+            contents,
+            "table-tennis-score {}",
+            if self.table_tennis_score { "on" } else { "off" }
+        )
+        .expect("format table tennis score");
+        writeln!(
+            contents,
+            "audio-latency-ms {:.0}",
+            self.audio_latency * 1000.
+        )
+        .expect("format audio latency");
+        writeln!(
+            contents,
+            "haptics {}",
+            if self.haptics { "on" } else { "off" }
+        )
+        .expect("format haptics");
+        writeln!(
+            contents,
+            "momentum-meter {}",
+            if self.momentum_meter { "on" } else { "off" }
+        )
+        .expect("format momentum meter");
+        writeln!(contents, "hud-key {}", self.hud_key).expect("format hud key");
+        writeln!(contents, "hud-layout {}", self.hud_layout).expect("format hud layout");
+        writeln!(
+            contents,
+            "broadcast-hud {}",
+            if self.broadcast_hud { "on" } else { "off" }
+        )
+        .expect("format broadcast hud");
+        writeln!(contents, "racket-trail {}", self.racket_trail).expect("format racket trail");
+        writeln!(contents, "camera {}", self.camera).expect("format camera");
+        writeln!(contents, "palette {}", self.palette).expect("format palette");
+        if let Err(err) = SETTINGS.save(&contents) {
+            eprintln!("error: save settings: {err}");
+        }
+    }
+
+    /// Returns the mixer playing the sounds at the volumes of the settings.
+    pub fn mixer(&self) -> Mixer {
+        Mixer::new(self.volume, self.bus_volumes)
+    }
+}
+
+/// Saves the settings shortly after they change, so they are not lost if the
+/// game crashes or is killed.
+pub struct Autosave {
+    /// Settings last saved, or `None` if they have never been saved, which
+    /// only the setup does.
+    saved: Option<Settings>,
+    /// Time left before saving the changed settings.
+    delay: Option<f32>,
+}
+
+impl Autosave {
+    pub fn new(saved: Option<Settings>) -> Self {
+        Self { saved, delay: None }
+    }
+
+    /// Saves `settings` right away.
+    pub fn save_now(&mut self, settings: Settings) {
+        settings.save();
+        self.saved = Some(settings);
+        self.delay = None;
+    }
+
+    /// Saves `settings` once they have been unchanged for a while. `dt` is
+    /// the time elapsed since the previous call.
+    pub fn update(&mut self, settings: Settings, dt: f32) {
+        if self.saved.is_none_or(|saved| saved == settings) {
+            self.delay = None;
+            return;
+        }
+        let delay = self.delay.get_or_insert(AUTOSAVE_DELAY_SECS);
+        *delay -= dt;
+        if *delay <= 0. {
+            self.save_now(settings);
+        }
+    }
+
+    /// Saves `settings` right away if they are waiting to be saved.
+    pub fn flush(&mut self, settings: Settings) {
+        if self.delay.is_some() {
+            self.save_now(settings);
+        }
+    }
+}
@@ -0,0 +1,780 @@
+//! Headless game simulation.
+//!
+//! The simulation advances in fixed time steps and does not depend on the
+//! window, the clock or the input devices, so it can run on its own thread
+//! and always produces the same match from the same seed and controls.
+use std::fmt;
+
+use crate::{
+    court::{Court, Walls},
+    entity::{Ball, Entity, Obstacle, Racket, Target, BALL_SIZE, RACKET_MARGIN},
+    math,
+    physics::Physics,
+    rules::Rules,
+};
+
+/// Points served in a row by every player in table tennis.
+pub const SERVES_PER_TURN: i32 = 2;
+
+/// Duration of a target practice run, in seconds.
+const TARGETS_SECS: u32 = 60;
+const TARGET_RADIUS: f32 = 40.;
+
+/// Goals the ball machine must score to end a goalkeeper match.
+const GOALKEEPER_GOALS: i32 = 3;
+/// Time between the first serves of the ball machine, in seconds. Every
+/// serve comes sooner than the previous one, down to the minimum.
+const MACHINE_INTERVAL_SECS: f32 = 3.;
+const MACHINE_MIN_INTERVAL_SECS: f32 = 0.75;
+const MACHINE_SPEEDUP: f32 = 0.9;
+
+/// Number of simulation steps per second.
+pub const TICK_RATE: u32 = 120;
+
+/// Duration of a simulation step in seconds.
+pub const TICK_SECS: f32 = 1. / TICK_RATE as f32;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn toggle(self) -> Self {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Left => write!(f, "LEFT"),
+            Side::Right => write!(f, "RIGHT"),
+        }
+    }
+}
+
+/// Movement requested for a racket.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Motion {
+    #[default]
+    Stop,
+    Up,
+    Down,
+}
+
+/// Movement requested for each racket during a step.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Controls {
+    pub left: Motion,
+    pub right: Motion,
+    /// Movement of the second racket of the left side in co-op matches.
+    pub partner: Motion,
+}
+
+/// Who plays on each side of the court.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Lineup {
+    /// One racket per side.
+    #[default]
+    Single,
+    /// Two half-height rackets on the left, each defending one half of the
+    /// court, against a single racket on the right. The left players share
+    /// their score.
+    Coop,
+    /// A half-height racket defends the left goal against a ball machine on
+    /// the right that serves balls more and more often. Every ball sent out
+    /// on the right is a save and scores for the left side. The match ends
+    /// when the machine scores [`GOALKEEPER_GOALS`] goals.
+    Goalkeeper,
+    /// One racket per side, each defending its own ball at the same time.
+    /// The balls only bounce off the racket of their side and off the goal
+    /// line of the other one.
+    Juggler,
+    /// A single racket on the left returns the ball against the right goal
+    /// line to send it through a ring on the right half of the court. Every
+    /// ring hit scores for the left side and moves the ring, and every
+    /// missed ball scores for the right one. The run ends after
+    /// [`TARGETS_SECS`] seconds, always won by the left side.
+    Targets,
+}
+
+impl Lineup {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "single" => Some(Lineup::Single),
+            "coop" => Some(Lineup::Coop),
+            "goalkeeper" => Some(Lineup::Goalkeeper),
+            "juggler" => Some(Lineup::Juggler),
+            "targets" => Some(Lineup::Targets),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Lineup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lineup::Single => write!(f, "single"),
+            Lineup::Coop => write!(f, "coop"),
+            Lineup::Goalkeeper => write!(f, "goalkeeper"),
+            Lineup::Juggler => write!(f, "juggler"),
+            Lineup::Targets => write!(f, "targets"),
+        }
+    }
+}
+
+/// Who serves after every point.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Service {
+    /// The ball is served towards the side that lost the point, as in the
+    /// classic game.
+    #[default]
+    Classic,
+    /// The sides take turns to serve [`SERVES_PER_TURN`] points each, and
+    /// every point once the set is in deuce, starting with the left one in
+    /// odd sets and the right one in even sets. The ball is served towards the
+    /// receiver, as in table tennis. Only single and co-op matches support
+    /// it.
+    TableTennis,
+}
+
+impl Service {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "classic" => Some(Service::Classic),
+            "table-tennis" => Some(Service::TableTennis),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Service::Classic => write!(f, "classic"),
+            Service::TableTennis => write!(f, "table-tennis"),
+        }
+    }
+}
+
+/// Noteworthy things that happened during a step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    WallBounce,
+    RacketBounce,
+    Point(Side),
+    /// The player on the given side is one point away from winning.
+    MatchPoint(Side),
+    /// The player on the given side won a set, but not yet the match.
+    SetWon(Side),
+    /// A ball went through a target.
+    TargetHit,
+    Winner(Side),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchState {
+    Playing,
+    Finished(Side),
+}
+
+/// Small deterministic pseudo-random number generator (xorshift64*), so
+/// matches, and the decisions of the bots in them, can be reproduced.
+#[derive(Clone)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // The state must never be zero.
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15 | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1 << 24) as f32
+    }
+
+    /// Returns either -1 or 1.
+    fn sign(&mut self) -> f32 {
+        if self.next_u64() >> 63 == 0 {
+            -1.
+        } else {
+            1.
+        }
+    }
+}
+
+pub struct Simulation {
+    pub court: Court,
+    pub physics: Physics,
+    pub lineup: Lineup,
+    pub rules: Rules,
+    pub entities: Vec<Entity>,
+    /// Points of the left and right sides in the current set.
+    pub scores: (i32, i32),
+    /// Sets won by the left and right sides.
+    pub sets: (u32, u32),
+    pub state: MatchState,
+    /// Number of steps played in the current match.
+    pub tick: u64,
+    /// Whether served balls wait for [`Simulation::serve`] before moving.
+    /// Only single matches support it.
+    pub manual_serve: bool,
+    /// Whether points are played without being scored, to warm up before
+    /// the match. Only single and co-op matches support it.
+    pub warmup: bool,
+    /// Whether the ball is waiting to be served.
+    waiting_serve: bool,
+    /// Step at which the ball machine serves next in goalkeeper matches.
+    next_serve: u64,
+    /// Balls served by the ball machine.
+    serves: u32,
+    rng: Rng,
+    events: Vec<Event>,
+}
+
+impl Clone for Simulation {
+    fn clone(&self) -> Self {
+        Self {
+            court: self.court.clone(),
+            physics: self.physics,
+            lineup: self.lineup,
+            rules: self.rules,
+            entities: self.entities.clone(),
+            scores: self.scores,
+            sets: self.sets,
+            state: self.state,
+            tick: self.tick,
+            manual_serve: self.manual_serve,
+            warmup: self.warmup,
+            waiting_serve: self.waiting_serve,
+            next_serve: self.next_serve,
+            serves: self.serves,
+            rng: self.rng.clone(),
+            events: self.events.clone(),
+        }
+    }
+
+    /// Copies `source` reusing the allocations of the entities and the
+    /// events, and the court, which rarely changes, only if it did. This
+    /// lets snapshots be taken every step.
+    fn clone_from(&mut self, source: &Self) {
+        if self.court != source.court {
+            self.court.clone_from(&source.court);
+        }
+        self.physics = source.physics;
+        self.lineup = source.lineup;
+        self.rules = source.rules;
+        self.entities.clone_from(&source.entities);
+        self.scores = source.scores;
+        self.sets = source.sets;
+        self.state = source.state;
+        self.tick = source.tick;
+        self.manual_serve = source.manual_serve;
+        self.warmup = source.warmup;
+        self.waiting_serve = source.waiting_serve;
+        self.next_serve = source.next_serve;
+        self.serves = source.serves;
+        self.rng.clone_from(&source.rng);
+        self.events.clone_from(&source.events);
+    }
+}
+
+impl Simulation {
+    pub fn new(seed: u64) -> Self {
+        Self::with_rules(Court::DEFAULT, Physics::default(), seed)
+    }
+
+    pub fn with_rules(court: Court, physics: Physics, seed: u64) -> Self {
+        let mut sim = Self {
+            court,
+            physics,
+            lineup: Lineup::Single,
+            rules: Rules::CLASSIC,
+            entities: Vec::new(),
+            scores: (0, 0),
+            sets: (0, 0),
+            state: MatchState::Playing,
+            tick: 0,
+            manual_serve: false,
+            warmup: false,
+            waiting_serve: false,
+            next_serve: 0,
+            serves: 0,
+            rng: Rng::new(seed),
+            events: Vec::new(),
+        };
+        sim.reset(seed);
+        sim
+    }
+
+    /// Starts a new match on [`Simulation::court`]. Matches started with the
+    /// same court, physics, lineup and seed and played with the same controls
+    /// are identical.
+    pub fn reset(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+        self.waiting_serve = self.manual_serve && self.lineup == Lineup::Single;
+        self.entities.clear();
+        let court = &self.court;
+        match self.lineup {
+            Lineup::Single | Lineup::Juggler | Lineup::Targets => {
+                self.entities
+                    .push(Entity::Racket(Racket::new(court, Side::Left)));
+            }
+            Lineup::Coop => {
+                for partner in [false, true] {
+                    self.entities
+                        .push(Entity::Racket(Racket::coop(court, Side::Left, partner)));
+                }
+            }
+            Lineup::Goalkeeper => {
+                self.entities
+                    .push(Entity::Racket(Racket::keeper(court, Side::Left)));
+            }
+        }
+        if !matches!(self.lineup, Lineup::Goalkeeper | Lineup::Targets) {
+            self.entities
+                .push(Entity::Racket(Racket::new(court, Side::Right)));
+        }
+        for &rect in &court.obstacles {
+            self.entities.push(Entity::Obstacle(Obstacle::new(rect)));
+        }
+        self.scores = (0, 0);
+        self.sets = (0, 0);
+        self.state = MatchState::Playing;
+        self.tick = 0;
+        self.serves = 0;
+        match self.lineup {
+            Lineup::Goalkeeper => self.serve_machine_ball(),
+            Lineup::Juggler => {
+                for (owner, dir_x) in [(Side::Left, -1.), (Side::Right, 1.)] {
+                    let ball = Ball::new(&self.court, &self.physics, (dir_x, self.rng.sign()));
+                    self.entities.push(Entity::Ball(Ball {
+                        owner: Some(owner),
+                        ..ball
+                    }));
+                }
+            }
+            Lineup::Targets => {
+                let ball = Ball::new(&self.court, &self.physics, (-1., self.rng.sign()));
+                self.entities.push(Entity::Ball(Ball {
+                    owner: Some(Side::Left),
+                    ..ball
+                }));
+                let target = Target::new(self.target_center(), TARGET_RADIUS);
+                self.entities.push(Entity::Target(target));
+            }
+            Lineup::Single | Lineup::Coop => {
+                let dir_x = match self.rules.service {
+                    Service::Classic => self.rng.sign(),
+                    Service::TableTennis => serve_dir_x(self.table_tennis_server()),
+                };
+                self.entities.push(Entity::Ball(Ball::new(
+                    &self.court,
+                    &self.physics,
+                    (dir_x, self.rng.sign()),
+                )));
+            }
+        }
+    }
+
+    /// Returns a random position for a target on the right half of the
+    /// court.
+    fn target_center(&mut self) -> (f32, f32) {
+        let margin = TARGET_RADIUS + BALL_SIZE;
+        let (width, height) = (self.court.width, self.court.height);
+        (
+            width * 0.5 + margin + self.rng.next_f32() * (width * 0.5 - margin * 2.),
+            margin + self.rng.next_f32() * (height - margin * 2.),
+        )
+    }
+
+    /// Serves a ball from the ball machine towards the left goal at a random
+    /// height and schedules the next serve.
+    fn serve_machine_ball(&mut self) {
+        let dir_y = self.rng.sign() * self.rng.next_f32();
+        let mut ball = Ball::new(&self.court, &self.physics, (-1., dir_y));
+        ball.pos = (
+            self.court.width - RACKET_MARGIN - BALL_SIZE,
+            BALL_SIZE + self.rng.next_f32() * (self.court.height - BALL_SIZE * 3.),
+        );
+        self.entities.push(Entity::Ball(ball));
+
+        let interval = (MACHINE_INTERVAL_SECS * math::powi(MACHINE_SPEEDUP, self.serves))
+            .max(MACHINE_MIN_INTERVAL_SECS);
+        self.serves += 1;
+        self.next_serve = self.tick + (interval * TICK_RATE as f32) as u64;
+    }
+
+    /// Advances the simulation by [`TICK_SECS`].
+    pub fn step(&mut self, controls: Controls) {
+        if self.state != MatchState::Playing {
+            return;
+        }
+
+        self.tick += 1;
+        for entity in &mut self.entities {
+            if self.waiting_serve && matches!(entity, Entity::Ball(_)) {
+                continue;
+            }
+            entity
+                .object_mut()
+                .update(&self.court, &self.physics, &controls);
+        }
+        let mut i = 0;
+        while !self.waiting_serve && i < self.entities.len() {
+            if matches!(self.entities[i], Entity::Ball(_)) && !self.update_ball_collisions(i) {
+                self.entities.remove(i);
+                continue;
+            }
+            i += 1;
+        }
+        if self.lineup == Lineup::Goalkeeper
+            && self.state == MatchState::Playing
+            && self.tick >= self.next_serve
+        {
+            self.serve_machine_ball();
+        }
+        if self.lineup == Lineup::Targets {
+            self.update_targets();
+        }
+        if self.is_time_up() {
+            self.finish_timed_match();
+        }
+    }
+
+    /// Ends the match in favor of the opponent of `side`, who gave up.
+    pub fn forfeit(&mut self, side: Side) {
+        if self.state != MatchState::Playing {
+            return;
+        }
+        let winner = side.toggle();
+        self.state = MatchState::Finished(winner);
+        self.events.push(Event::Winner(winner));
+    }
+
+    /// Releases the ball waiting to be served in matches with
+    /// [`Simulation::manual_serve`].
+    pub fn serve(&mut self) {
+        self.waiting_serve = false;
+    }
+
+    /// Returns whether the ball is waiting to be served.
+    pub fn is_waiting_serve(&self) -> bool {
+        self.waiting_serve
+    }
+
+    /// Scores the targets hit during the step, moves them and ends the run
+    /// when the time is up.
+    fn update_targets(&mut self) {
+        for i in 0..self.entities.len() {
+            let Entity::Target(target) = &self.entities[i] else {
+                continue;
+            };
+            if !target.hit {
+                continue;
+            }
+            self.scores.0 += 1;
+            let center = self.target_center();
+            if let Entity::Target(target) = &mut self.entities[i] {
+                target.center = center;
+                target.hit = false;
+            }
+        }
+        if self.state == MatchState::Playing && self.tick >= (TARGETS_SECS * TICK_RATE) as u64 {
+            self.state = MatchState::Finished(Side::Left);
+            self.events.push(Event::Winner(Side::Left));
+        }
+    }
+
+    /// Returns the seconds left in a timed run, if the lineup or the rules
+    /// have a time limit.
+    pub fn time_left(&self) -> Option<f32> {
+        let secs = match self.lineup {
+            Lineup::Targets => TARGETS_SECS,
+            Lineup::Single | Lineup::Coop | Lineup::Juggler if !self.warmup => {
+                self.rules.time_limit?
+            }
+            Lineup::Single | Lineup::Coop | Lineup::Juggler | Lineup::Goalkeeper => return None,
+        };
+        Some(secs as f32 - self.tick as f32 / TICK_RATE as f32)
+    }
+
+    /// Returns whether the time limit of the rules is over in a match played
+    /// by them.
+    fn is_time_up(&self) -> bool {
+        self.state == MatchState::Playing
+            && self.lineup != Lineup::Targets
+            && self.time_left().is_some_and(|secs| secs <= 0.)
+    }
+
+    /// Ends a match whose time is up in favor of the side ahead, first in
+    /// sets and then in points. Tied matches go on.
+    fn finish_timed_match(&mut self) {
+        let ahead = (self.sets.0, self.scores.0).cmp(&(self.sets.1, self.scores.1));
+        let winner = match ahead {
+            std::cmp::Ordering::Greater => Side::Left,
+            std::cmp::Ordering::Less => Side::Right,
+            std::cmp::Ordering::Equal => return,
+        };
+        self.state = MatchState::Finished(winner);
+        self.events.push(Event::Winner(winner));
+    }
+
+    /// Returns the side serving the next point as in table tennis.
+    pub fn table_tennis_server(&self) -> Side {
+        table_tennis_server(&self.rules, self.scores, self.sets)
+    }
+
+    /// Returns the racket on the given side. In co-op matches it is the one
+    /// that is not the partner.
+    pub fn racket(&self, side: Side) -> Option<&Racket> {
+        self.entities.iter().find_map(|entity| match entity {
+            Entity::Racket(racket) if racket.side == side && !racket.partner => Some(racket),
+            _ => None,
+        })
+    }
+
+    /// Returns the events that happened since they were last cleared.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn clear_events(&mut self) {
+        self.events.clear();
+    }
+
+    /// Bounces the ball at `ball_idx` off everything it touches and scores
+    /// the points. Returns whether the ball is still in play.
+    fn update_ball_collisions(&mut self, ball_idx: usize) -> bool {
+        let Entity::Ball(ball) = &mut self.entities[ball_idx] else {
+            unreachable!("entity is not a ball");
+        };
+
+        // Dedicated balls bounce off the goal line of the other side.
+        match ball.owner {
+            Some(Side::Left) if ball.pos.0 + BALL_SIZE > self.court.width => {
+                ball.pos.0 = self.court.width - BALL_SIZE;
+                ball.dir.0 = -ball.dir.0.abs();
+                ball.bounce(&self.physics, self.physics.wall_restitution);
+                self.events.push(Event::WallBounce);
+                return true;
+            }
+            Some(Side::Right) if ball.pos.0 < 0. => {
+                ball.pos.0 = 0.;
+                ball.dir.0 = ball.dir.0.abs();
+                ball.bounce(&self.physics, self.physics.wall_restitution);
+                self.events.push(Event::WallBounce);
+                return true;
+            }
+            _ => {}
+        }
+
+        if ball.pos.0 < 0. {
+            return self.update_score(Side::Right, ball_idx);
+        }
+
+        if ball.pos.0 + BALL_SIZE > self.court.width {
+            return self.update_score(Side::Left, ball_idx);
+        }
+
+        match self.court.walls {
+            Walls::Bounce => {
+                let offset = self.court.wall_offset(self.tick);
+                if ball.pos.1 < offset {
+                    ball.pos.1 = offset;
+                    ball.dir.1 = ball.dir.1.abs();
+                    ball.bounce(&self.physics, self.physics.wall_restitution);
+                    self.events.push(Event::WallBounce);
+                    return true;
+                }
+
+                if ball.pos.1 + BALL_SIZE > self.court.height - offset {
+                    ball.pos.1 = self.court.height - offset - BALL_SIZE;
+                    ball.dir.1 = -ball.dir.1.abs();
+                    ball.bounce(&self.physics, self.physics.wall_restitution);
+                    self.events.push(Event::WallBounce);
+                    return true;
+                }
+            }
+            Walls::Wrap => {
+                if ball.pos.1 + BALL_SIZE < 0. {
+                    ball.pos.1 = self.court.height;
+                } else if ball.pos.1 > self.court.height {
+                    ball.pos.1 = -BALL_SIZE;
+                }
+            }
+        }
+
+        for other_idx in 0..self.entities.len() {
+            if other_idx == ball_idx {
+                continue;
+            }
+            let (ball, other) = pair_mut(&mut self.entities, ball_idx, other_idx);
+            let Entity::Ball(ball) = ball else {
+                unreachable!("entity is not a ball");
+            };
+            if let Some(event) = other.object_mut().collide(&self.physics, ball) {
+                self.events.push(event);
+            }
+        }
+        true
+    }
+
+    /// Scores a point for `point_side` after the ball at `ball_idx` left the
+    /// court. Returns whether the ball was served again.
+    fn update_score(&mut self, point_side: Side, ball_idx: usize) -> bool {
+        if self.warmup && matches!(self.lineup, Lineup::Single | Lineup::Coop) {
+            self.serve_after_point(point_side, ball_idx);
+            return true;
+        }
+
+        let score = match point_side {
+            Side::Left => &mut self.scores.0,
+            Side::Right => &mut self.scores.1,
+        };
+
+        *score += 1;
+        self.events.push(Event::Point(point_side));
+
+        // Missed balls never end a target practice run.
+        if self.lineup == Lineup::Targets {
+            let dir_x = -1.;
+            let Entity::Ball(ball) = &mut self.entities[ball_idx] else {
+                unreachable!("entity is not a ball");
+            };
+            *ball = Ball {
+                owner: ball.owner,
+                ..Ball::new(&self.court, &self.physics, (dir_x, self.rng.sign()))
+            };
+            return true;
+        }
+
+        // Saves never end a goalkeeper match and the machine keeps its own
+        // pace.
+        if self.lineup == Lineup::Goalkeeper {
+            if point_side == Side::Right {
+                if *score >= GOALKEEPER_GOALS {
+                    self.state = MatchState::Finished(point_side);
+                    self.events.push(Event::Winner(point_side));
+                } else if *score == GOALKEEPER_GOALS - 1 {
+                    self.events.push(Event::MatchPoint(point_side));
+                }
+            }
+            return false;
+        }
+
+        let (score, other, sets) = match point_side {
+            Side::Left => (self.scores.0, self.scores.1, &mut self.sets.0),
+            Side::Right => (self.scores.1, self.scores.0, &mut self.sets.1),
+        };
+        if self.rules.wins_set(score, other) {
+            *sets += 1;
+            if *sets >= self.rules.sets_to_win() {
+                self.state = MatchState::Finished(point_side);
+                self.events.push(Event::Winner(point_side));
+                return true;
+            }
+            self.scores = (0, 0);
+            self.events.push(Event::SetWon(point_side));
+        }
+        if self.is_time_up() {
+            self.finish_timed_match();
+            if self.state != MatchState::Playing {
+                return true;
+            }
+        }
+        if self.is_match_point(point_side) {
+            self.events.push(Event::MatchPoint(point_side));
+        }
+        self.serve_after_point(point_side, ball_idx);
+        true
+    }
+
+    /// Returns whether `side` wins the match with the next point.
+    fn is_match_point(&self, side: Side) -> bool {
+        let (score, other, sets) = match side {
+            Side::Left => (self.scores.0, self.scores.1, self.sets.0),
+            Side::Right => (self.scores.1, self.scores.0, self.sets.1),
+        };
+        sets + 1 >= self.rules.sets_to_win() && self.rules.wins_set(score + 1, other)
+    }
+
+    /// Serves the ball at `ball_idx` again after the point won by
+    /// `point_side`, as the service of the match goes.
+    fn serve_after_point(&mut self, point_side: Side, ball_idx: usize) {
+        let dir_x = match (self.rules.service, self.lineup) {
+            (Service::TableTennis, Lineup::Single | Lineup::Coop) => {
+                serve_dir_x(self.table_tennis_server())
+            }
+            _ => match point_side.toggle() {
+                Side::Left => -1.,
+                Side::Right => 1.,
+            },
+        };
+        let Entity::Ball(ball) = &mut self.entities[ball_idx] else {
+            unreachable!("entity is not a ball");
+        };
+        *ball = Ball {
+            owner: ball.owner,
+            ..Ball::new(&self.court, &self.physics, (dir_x, self.rng.sign()))
+        };
+        self.waiting_serve = self.manual_serve && self.lineup == Lineup::Single;
+    }
+}
+
+/// Returns the side serving the next point of a set at `scores` after
+/// `sets` were won, as in table tennis. The left side serves first in odd
+/// sets and the right one in even sets, and the service changes every
+/// [`SERVES_PER_TURN`] points, or every point in deuce.
+pub fn table_tennis_server(rules: &Rules, scores: (i32, i32), sets: (u32, u32)) -> Side {
+    let first = if (sets.0 + sets.1).is_multiple_of(2) {
+        Side::Left
+    } else {
+        Side::Right
+    };
+    let points = scores.0 + scores.1;
+    let turns = if rules.is_deuce(scores) {
+        let before_deuce = (rules.win_score - 1) * 2;
+        before_deuce / SERVES_PER_TURN + points - before_deuce
+    } else {
+        points / SERVES_PER_TURN
+    };
+    if turns % 2 == 0 {
+        first
+    } else {
+        first.toggle()
+    }
+}
+
+/// Returns the horizontal direction of a ball served by `server`.
+fn serve_dir_x(server: Side) -> f32 {
+    match server {
+        Side::Left => 1.,
+        Side::Right => -1.,
+    }
+}
+
+/// Returns mutable references to two different elements of `items`.
+fn pair_mut<T>(items: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    assert_ne!(a, b, "indices must be different");
+    if a < b {
+        let (head, tail) = items.split_at_mut(b);
+        (&mut head[a], &mut tail[0])
+    } else {
+        let (head, tail) = items.split_at_mut(a);
+        (&mut tail[0], &mut head[b])
+    }
+}
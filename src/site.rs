@@ -0,0 +1,126 @@
+//! Files that make the web version installable and playable offline.
+//!
+//! `pong --write-site DIR` writes the web app manifest, the service worker
+//! and the icons they need to the directory of the site, next to the files
+//! in `assets/ghpages` and `pong.wasm`. Every asset is embedded in
+//! `pong.wasm`, so the service worker only caches the files of the site.
+//! Its cache is named after the commit of the build, so every deployment
+//! replaces it, and the game tells the players to reload the page once the
+//! new version is ready.
+use std::{fs, io, path::Path};
+
+use macroquad::texture::Image;
+
+use crate::assets;
+
+/// Files of the site cached for offline play.
+const FILES: &[&str] = &[
+    "./",
+    "index.html",
+    "mq_js_bundle.js",
+    "pong.js",
+    "pong.wasm",
+    "favicon.png",
+    "manifest.webmanifest",
+    "icon-192.png",
+    "icon-512.png",
+];
+
+/// Sizes of the icons of the installed game, which are the largest window
+/// icon scaled up.
+const ICON_SIZES: [u16; 2] = [192, 512];
+
+/// Writes the manifest, the service worker and the icons to `dir`.
+pub fn write(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join("manifest.webmanifest"), manifest())?;
+    fs::write(dir.join("sw.js"), service_worker())?;
+    let icon = assets::big_icon();
+    for size in ICON_SIZES {
+        let path = dir.join(format!("icon-{size}.png"));
+        let path = path
+            .to_str()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path is not UTF-8"))?;
+        scaled(&icon, size).export_png(path);
+    }
+    Ok(())
+}
+
+fn manifest() -> String {
+    let icons: Vec<_> = ICON_SIZES
+        .iter()
+        .map(|size| {
+            format!(
+                r#"{{"src": "icon-{size}.png", "sizes": "{size}x{size}", "type": "image/png"}}"#
+            )
+        })
+        .collect();
+    format!(
+        r##"{{
+  "name": "PONG",
+  "short_name": "PONG",
+  "start_url": "./",
+  "display": "fullscreen",
+  "orientation": "landscape",
+  "background_color": "#000000",
+  "theme_color": "#000000",
+  "icons": [{}]
+}}
+"##,
+        icons.join(", ")
+    )
+}
+
+fn service_worker() -> String {
+    let files: Vec<_> = FILES.iter().map(|file| format!(r#""{file}""#)).collect();
+    format!(
+        r#"// Generated by `pong --write-site`. See src/site.rs.
+const CACHE = "pong-{}";
+const FILES = [{}];
+
+self.addEventListener("install", (event) => {{
+  event.waitUntil(caches.open(CACHE).then((cache) => cache.addAll(FILES)));
+  // The page running keeps the game it loaded, so the new files can be
+  // served right away.
+  self.skipWaiting();
+}});
+
+self.addEventListener("activate", (event) => {{
+  event.waitUntil(
+    caches
+      .keys()
+      .then((keys) => Promise.all(keys.filter((key) => key !== CACHE).map((key) => caches.delete(key))))
+      .then(() => self.clients.claim()),
+  );
+}});
+
+self.addEventListener("fetch", (event) => {{
+  event.respondWith(caches.match(event.request).then((cached) => cached || fetch(event.request)));
+}});
+"#,
+        env!("PONG_GIT_HASH"),
+        files.join(", ")
+    )
+}
+
+/// Returns `icon` scaled up to `size` pixels wide, without smoothing so the
+/// pixels stay sharp.
+fn scaled(icon: &Image, size: u16) -> Image {
+    let mut image = Image::gen_image_color(size, size, Default::default());
+    for y in 0..size {
+        for x in 0..size {
+            let (src_x, src_y) = (
+                x as u32 * icon.width as u32 / size as u32,
+                y as u32 * icon.height as u32 / size as u32,
+            );
+            // `export_png` flips the images, which are read upside down from
+            // render targets.
+            image.set_pixel(
+                x as u32,
+                (size - 1 - y) as u32,
+                icon.get_pixel(src_x, src_y),
+            );
+        }
+    }
+    image
+}
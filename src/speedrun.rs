@@ -0,0 +1,319 @@
+//! Timer for speedruns.
+//!
+//! The timer counts game time, so pauses and slow motion don't affect it. It
+//! starts with the first serve, takes a split on every point and stops when
+//! the match is won. Wins of a player against a bot are compared with the
+//! personal best for the same court, physics and bot.
+use std::fmt::Write;
+
+use macroquad::prelude::*;
+use pong::{
+    court::Court,
+    physics::Physics,
+    sim::{Lineup, Side, TICK_RATE},
+};
+
+use crate::{
+    draw::FOREGROUND_COLOR,
+    label::Label,
+    storage::{self, Dir, Format},
+};
+
+/// Entry where the settings and personal bests are kept.
+const SPEEDRUN: Format = Format {
+    dir: Dir::Saves,
+    name: "speedrun.txt",
+    migrations: &[storage::unversioned],
+};
+
+/// Number of splits shown under the timer.
+const SHOWN_SPLITS: usize = 5;
+
+/// Colors of the splits ahead of and behind the personal best.
+pub const AHEAD_COLOR: Color = GREEN;
+pub const BEHIND_COLOR: Color = RED;
+
+/// Time at which a point was won.
+#[derive(Clone, Copy)]
+struct Split {
+    side: Side,
+    tick: u64,
+}
+
+/// Fastest win in a category.
+struct PersonalBest {
+    category: String,
+    /// Time of every point of the run, in seconds.
+    splits: Vec<f32>,
+}
+
+pub struct Speedrun {
+    /// Whether the timer is shown.
+    pub enabled: bool,
+    bests: Vec<PersonalBest>,
+    /// Category of the current match, if it can set a personal best.
+    category: Option<String>,
+    /// Side played by the runner against a bot.
+    runner_side: Option<Side>,
+    splits: Vec<Split>,
+    /// Last tick of the match seen so far.
+    tick: u64,
+    finished: bool,
+    /// Splits of the personal best when the match started, to compare with.
+    best_splits: Option<Vec<f32>>,
+    /// Whether the match set a new personal best.
+    new_best: bool,
+    /// Hundredths of a second shown by the timer.
+    shown: Option<u32>,
+    timer_label: Label,
+    /// Last splits, colored by how they compare with the personal best.
+    split_labels: Vec<Label>,
+    best_label: Option<Label>,
+    new_best_label: Label,
+}
+
+impl Speedrun {
+    /// Loads the settings and personal bests. Lines that cannot be parsed
+    /// are skipped.
+    pub fn load() -> Self {
+        let mut speedrun = Self {
+            enabled: false,
+            bests: Vec::new(),
+            category: None,
+            runner_side: None,
+            splits: Vec::new(),
+            tick: 0,
+            finished: false,
+            best_splits: None,
+            new_best: false,
+            shown: None,
+            timer_label: Label::new("", 32),
+            split_labels: Vec::new(),
+            best_label: None,
+            new_best_label: Label::new("NEW PB!", 20),
+        };
+        speedrun.new_best_label.set_color(AHEAD_COLOR);
+        speedrun.update_timer();
+        for line in SPEEDRUN.load().unwrap_or_default().lines() {
+            match line.split_once(' ') {
+                Some(("timer", value)) => speedrun.enabled = value == "on",
+                Some(("best", value)) => {
+                    let Some((category, splits)) = value.split_once('\t') else {
+                        continue;
+                    };
+                    let Ok(splits) = splits.split(' ').map(str::parse).collect() else {
+                        continue;
+                    };
+                    speedrun.bests.push(PersonalBest {
+                        category: category.to_owned(),
+                        splits,
+                    });
+                }
+                _ => {}
+            }
+        }
+        speedrun
+    }
+
+    fn save(&self) {
+        let mut contents = format!("timer {}\n", if self.enabled { "on" } else { "off" });
+        for best in &self.bests {
+            let splits: Vec<_> = best
+                .splits
+                .iter()
+                .map(|secs| format!("{secs:.3}"))
+                .collect();
+            writeln!(contents, "best {}\t{}", best.category, splits.join(" "))
+                .expect("format personal best");
+        }
+        if let Err(err) = SPEEDRUN.save(&contents) {
+            eprintln!("error: save speedrun: {err}");
+        }
+    }
+
+    /// Shows or hides the timer.
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.save();
+    }
+
+    /// Resets the timer for a new match. `bots` are the names of the bots
+    /// playing on the left and right sides. Only matches of a single player
+    /// against a bot can set personal bests.
+    pub fn start(
+        &mut self,
+        court: &Court,
+        physics: &Physics,
+        lineup: Lineup,
+        bots: &(Option<String>, Option<String>),
+    ) {
+        let (runner_side, bot) = match (lineup, bots) {
+            (Lineup::Single, (None, Some(bot))) => (Some(Side::Left), Some(bot)),
+            (Lineup::Single, (Some(bot), None)) => (Some(Side::Right), Some(bot)),
+            _ => (None, None),
+        };
+        self.runner_side = runner_side;
+        self.category = bot.map(|bot| {
+            format!(
+                "{} {} {} {bot}",
+                court.spec(),
+                physics.preset_name().unwrap_or("custom"),
+                physics.ball_shape
+            )
+        });
+        self.best_splits = self.category.as_ref().and_then(|category| {
+            self.bests
+                .iter()
+                .find(|best| &best.category == category)
+                .map(|best| best.splits.clone())
+        });
+        self.splits.clear();
+        self.tick = 0;
+        self.finished = false;
+        self.new_best = false;
+        self.update_timer();
+        self.update_splits();
+        self.best_label = self
+            .best_splits
+            .as_ref()
+            .and_then(|best| best.last())
+            .map(|&best| Label::new(&format!("PB {}", format_secs(best)), 20));
+    }
+
+    /// Returns the category of the current match, if it can set a personal
+    /// best.
+    pub fn category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+
+    /// Returns the side played by the runner against a bot.
+    pub fn runner_side(&self) -> Option<Side> {
+        self.runner_side
+    }
+
+    /// Returns whether the finished match set a new personal best.
+    pub fn is_new_best(&self) -> bool {
+        self.new_best
+    }
+
+    /// Advances the timer to `tick` of the match.
+    pub fn update(&mut self, tick: u64) {
+        if !self.finished {
+            self.tick = tick;
+            self.update_timer();
+        }
+    }
+
+    /// Takes a split for a point won by `side` at `tick`.
+    pub fn split(&mut self, side: Side, tick: u64) {
+        self.update(tick);
+        if !self.finished {
+            self.splits.push(Split { side, tick });
+            self.update_splits();
+        }
+    }
+
+    /// Stops the timer after `side` won the match and records a new
+    /// personal best if the runner beat it.
+    pub fn finish(&mut self, side: Side) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+
+        if self.runner_side != Some(side) {
+            return;
+        }
+        let Some(category) = self.category.clone() else {
+            return;
+        };
+        let total = secs(self.tick);
+        let best = self.best_splits.as_ref().and_then(|best| best.last());
+        if best.is_some_and(|&best| best <= total) {
+            return;
+        }
+        self.new_best = true;
+        let splits = self.splits.iter().map(|split| secs(split.tick)).collect();
+        match self.bests.iter_mut().find(|best| best.category == category) {
+            Some(best) => best.splits = splits,
+            None => self.bests.push(PersonalBest { category, splits }),
+        }
+        self.save();
+    }
+
+    /// Writes the time of the timer again if the hundredths shown changed.
+    fn update_timer(&mut self) {
+        let hundredths = (secs(self.tick) * 100.) as u32;
+        if self.shown != Some(hundredths) {
+            self.shown = Some(hundredths);
+            self.timer_label.set_text(&format_secs(secs(self.tick)));
+        }
+    }
+
+    /// Writes the last splits again, after one was taken.
+    fn update_splits(&mut self) {
+        let best = self.best_splits.as_ref();
+        let first = self.splits.len().saturating_sub(SHOWN_SPLITS);
+        self.split_labels
+            .resize_with(self.splits.len() - first, || Label::new("", 20));
+        let shown = self.splits.iter().enumerate().skip(first);
+        for (label, (i, split)) in self.split_labels.iter_mut().zip(shown) {
+            let secs = secs(split.tick);
+            let mut text = format!(
+                "{:>2} {:<5} {}",
+                i + 1,
+                split.side.to_string(),
+                format_secs(secs)
+            );
+            let mut color = FOREGROUND_COLOR;
+            if let Some(&best) = best.and_then(|best| best.get(i)) {
+                write!(text, " {:+.2}", secs - best).expect("format delta");
+                color = if secs <= best {
+                    AHEAD_COLOR
+                } else {
+                    BEHIND_COLOR
+                };
+            }
+            label.set_text(&text);
+            label.set_color(color);
+        }
+    }
+
+    /// Draws the timer, the last splits and how the run compares with the
+    /// personal best in the top left corner of the court.
+    pub fn draw(&self) {
+        if !self.enabled {
+            return;
+        }
+
+        let (x, mut y) = (10., 20.);
+        self.timer_label.draw_left(x, y);
+        for label in &self.split_labels {
+            y += 20.;
+            label.draw_left(x, y);
+        }
+        if let Some(label) = &self.best_label {
+            y += 24.;
+            label.draw_left(x, y);
+        }
+        if self.new_best {
+            y += 24.;
+            self.new_best_label.draw_left(x, y);
+        }
+    }
+}
+
+fn secs(tick: u64) -> f32 {
+    tick as f32 / TICK_RATE as f32
+}
+
+/// Formats `secs` as minutes, seconds and hundredths.
+fn format_secs(secs: f32) -> String {
+    let hundredths = (secs * 100.) as u32;
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
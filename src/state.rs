@@ -0,0 +1,268 @@
+//! Game states.
+//!
+//! Every screen of the game is a [`GameState`]. States are kept in a
+//! [`StateStack`] and the topmost one is the only one updated. Overlays, like
+//! the pause screen, are drawn over the states below them, which stay frozen.
+mod browser;
+mod calibration;
+mod countdown;
+mod crashed;
+mod diagnostics;
+mod gameplay;
+mod join;
+mod main_menu;
+mod mode_select;
+mod next_up;
+mod pause;
+mod photo;
+mod playback;
+mod rules;
+mod setup;
+mod summary;
+mod tutorial;
+mod warmup;
+mod winner;
+
+pub use browser::ReplayBrowser;
+pub use calibration::Calibration;
+pub use crashed::Crashed;
+pub use diagnostics::Diagnostics;
+pub use gameplay::Gameplay;
+pub use join::Join;
+pub use main_menu::MainMenu;
+pub use next_up::NextUp;
+pub use playback::Playback;
+pub use rules::RulesEditor;
+pub use setup::Setup;
+pub use summary::Summary;
+pub use tutorial::Tutorial;
+pub use warmup::Warmup;
+
+use std::path::PathBuf;
+
+use pong::{
+    code::MatchCode, court::Court, physics::Physics, rules::Rules, runner::Runner,
+    script::InputScript, sim::Lineup,
+};
+
+use crate::{
+    assets::Assets,
+    draw::{Canvas, Crt},
+    input::{Devices, Input, Scheme},
+    party::Party,
+    pulses::Pulses,
+    settings::{Autosave, Settings},
+    speedrun::Speedrun,
+    stats::History,
+};
+
+/// Resources shared by all the states.
+pub struct Context {
+    pub runner: Runner,
+    pub assets: Assets,
+    /// Where the court is drawn.
+    pub canvas: Canvas,
+    /// Strength of the CRT effect, when enabled in the settings.
+    pub crt: Crt,
+    pub pulses: Pulses,
+    pub inputs: Vec<Input>,
+    /// Way the players last controlled the game.
+    pub scheme: Scheme,
+    /// Keys moving the racket of every side.
+    pub devices: Devices,
+    /// Court on which new matches are played.
+    pub court: Court,
+    pub physics: Physics,
+    pub lineup: Lineup,
+    /// Rules of new matches.
+    pub rules: Rules,
+    /// Names of the bots playing on the left and right sides.
+    pub bots: (Option<String>, Option<String>),
+    /// Where to save the trace of every finished match.
+    pub record: Option<PathBuf>,
+    /// Inputs replacing the ones of the players in every match.
+    pub script: Option<InputScript>,
+    /// Where to save the inputs of the players in every finished match.
+    pub record_inputs: Option<PathBuf>,
+    /// Matches finished in this and previous runs.
+    pub history: History,
+    pub speedrun: Speedrun,
+    pub settings: Settings,
+    /// Saves the settings when they change.
+    pub autosave: Autosave,
+    /// Seed of the next match, when replicating a shared code.
+    pub seed: Option<u64>,
+    /// Code of the match being played, to share it.
+    pub code: Option<MatchCode>,
+    /// Score and sets the next match starts from, when resuming a crashed
+    /// match.
+    pub resume: Option<((i32, i32), (u32, u32))>,
+    /// Players taking turns in party mode, if playing it.
+    pub party: Option<Party>,
+    /// Whether the right racket mirrors the left one in single matches.
+    pub mirror: bool,
+    /// Whether the first match set up by the options or in the main menu
+    /// starts with a warmup.
+    pub warmup: bool,
+    /// Whether the score, the timers and the other overlays of the matches
+    /// are shown. Hiding them leaves clean footage for streams and
+    /// recordings.
+    pub hud: bool,
+    /// Time elapsed since the previous frame.
+    pub dt: f32,
+}
+
+/// What to do with a state after it has been updated.
+pub enum Transition {
+    None,
+    Push(Box<dyn GameState>),
+    Pop,
+    Replace(Box<dyn GameState>),
+    /// Removes every state and adds another one, like going back to the
+    /// main menu.
+    Reset(Box<dyn GameState>),
+    /// Removes every state, which ends the game.
+    Quit,
+}
+
+pub trait GameState {
+    /// Called when the state is added to the stack.
+    fn on_enter(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the state is removed from the stack.
+    fn on_exit(&mut self, _ctx: &mut Context) {}
+
+    /// Called when another state is pushed on top of this one.
+    fn on_cover(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the state above this one is popped.
+    fn on_uncover(&mut self, _ctx: &mut Context) {}
+
+    fn update(&mut self, ctx: &mut Context) -> Transition;
+
+    fn draw(&self, ctx: &Context);
+
+    /// Returns whether the state is waiting for the players, so it can be
+    /// updated at a lower rate.
+    fn is_idle(&self) -> bool {
+        false
+    }
+
+    /// Returns whether the states below this one must still be drawn.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+/// Returns the state the next match set up in `ctx` starts with, or the
+/// screen before it in party mode, after a warmup if set in `ctx` and the
+/// players need to join.
+pub fn first_match(ctx: &Context) -> Box<dyn GameState> {
+    let mut first: Box<dyn GameState> = if ctx.party.is_some() {
+        Box::new(NextUp::new())
+    } else {
+        Box::new(Gameplay::new())
+    };
+    // Matches between bots or played by scripts need no warmup.
+    let humans = ctx.script.is_none() && (ctx.bots.0.is_none() || ctx.bots.1.is_none());
+    if ctx.warmup && humans && Warmup::is_supported(ctx.lineup) {
+        first = Box::new(Warmup::new(first));
+    }
+    if Join::is_needed(ctx) {
+        first = Box::new(Join::new(first));
+    }
+    first
+}
+
+pub struct StateStack {
+    states: Vec<Box<dyn GameState>>,
+    /// Whether a state was replaced by another since the last call to
+    /// [`StateStack::take_replaced`].
+    replaced: bool,
+}
+
+impl StateStack {
+    pub fn new(mut initial: Box<dyn GameState>, ctx: &mut Context) -> Self {
+        initial.on_enter(ctx);
+        Self {
+            states: vec![initial],
+            replaced: false,
+        }
+    }
+
+    /// Pushes `state` on top of the stack, as if the topmost state had
+    /// requested it.
+    pub fn push(&mut self, mut state: Box<dyn GameState>, ctx: &mut Context) {
+        if let Some(top) = self.states.last_mut() {
+            top.on_cover(ctx);
+        }
+        state.on_enter(ctx);
+        self.states.push(state);
+    }
+
+    pub fn update(&mut self, ctx: &mut Context) {
+        let Some(top) = self.states.last_mut() else {
+            return;
+        };
+
+        match top.update(ctx) {
+            Transition::None => {}
+            Transition::Push(state) => self.push(state, ctx),
+            Transition::Pop => {
+                if let Some(mut old) = self.states.pop() {
+                    old.on_exit(ctx);
+                }
+                if let Some(top) = self.states.last_mut() {
+                    top.on_uncover(ctx);
+                }
+            }
+            Transition::Replace(mut state) => {
+                if let Some(mut old) = self.states.pop() {
+                    old.on_exit(ctx);
+                }
+                state.on_enter(ctx);
+                self.states.push(state);
+                self.replaced = true;
+            }
+            Transition::Reset(mut state) => {
+                while let Some(mut old) = self.states.pop() {
+                    old.on_exit(ctx);
+                }
+                state.on_enter(ctx);
+                self.states.push(state);
+                self.replaced = true;
+            }
+            Transition::Quit => {
+                while let Some(mut old) = self.states.pop() {
+                    old.on_exit(ctx);
+                }
+            }
+        }
+    }
+
+    pub fn draw(&self, ctx: &Context) {
+        let first = self
+            .states
+            .iter()
+            .rposition(|state| !state.is_overlay())
+            .unwrap_or(0);
+        for state in &self.states[first..] {
+            state.draw(ctx);
+        }
+    }
+
+    /// Returns whether a state was replaced by another since the last call,
+    /// switching between screens like the setup, a match and the winner
+    /// screen.
+    pub fn take_replaced(&mut self) -> bool {
+        std::mem::take(&mut self.replaced)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.states.last().is_some_and(|top| top.is_idle())
+    }
+}
@@ -0,0 +1,321 @@
+use macroquad::prelude::*;
+use pong::sim::Side;
+
+use crate::{
+    draw::{self, FOREGROUND_COLOR},
+    input::Input,
+    label::Label,
+    prompt::{Action, Prompts},
+    replays::{self, Replay},
+    state::{playback::Playback, Context, GameState, Transition},
+};
+
+/// Rows of replays shown at once.
+const ROWS_SHOWN: usize = 8;
+const ROW_HEIGHT: f32 = 40.;
+/// Height of the court where the first row is drawn.
+const FIRST_ROW_Y: f32 = 150.;
+
+/// Order in which the replays are listed.
+#[derive(Clone, Copy, PartialEq)]
+enum Sort {
+    /// Newest first.
+    Date,
+    /// Highest scores first.
+    Score,
+    /// Longest first.
+    Duration,
+    /// By game mode, and newest first within every mode.
+    Mode,
+}
+
+impl Sort {
+    const ALL: [Sort; 4] = [Sort::Date, Sort::Score, Sort::Duration, Sort::Mode];
+
+    fn name(self) -> &'static str {
+        match self {
+            Sort::Date => "DATE",
+            Sort::Score => "SCORE",
+            Sort::Duration => "DURATION",
+            Sort::Mode => "MODE",
+        }
+    }
+
+    fn sort(self, replays: &mut [Replay]) {
+        let date = |replay: &Replay| replay.trace.meta.date.unwrap_or_default();
+        match self {
+            Sort::Date => replays.sort_by_key(|replay| std::cmp::Reverse(date(replay))),
+            Sort::Score => replays.sort_by_key(|replay| {
+                let (left, right) = replay.trace.meta.scores.unwrap_or_default();
+                std::cmp::Reverse((left.max(right), left.min(right)))
+            }),
+            Sort::Duration => {
+                replays.sort_by(|a, b| b.trace.duration_secs().total_cmp(&a.trace.duration_secs()))
+            }
+            Sort::Mode => replays.sort_by_key(|replay| {
+                (
+                    replay.trace.lineup.to_string(),
+                    std::cmp::Reverse(date(replay)),
+                )
+            }),
+        }
+    }
+}
+
+/// Lists the replays of the finished matches, so they can be watched or
+/// deleted.
+pub struct ReplayBrowser {
+    replays: Vec<Replay>,
+    sort: Sort,
+    /// Index of the chosen replay in `replays`.
+    selected: usize,
+    /// Whether the chosen replay is deleted if the delete key is pressed
+    /// again.
+    confirm_delete: bool,
+    /// Inputs of the previous frame, so held keys only count once.
+    held: Vec<Input>,
+    title_label: Label,
+    sort_label: Label,
+    row_labels: Vec<Label>,
+    /// Shown above the prompts instead of the ones for the replays when
+    /// there are none, or when deleting one is being confirmed.
+    notice_label: Option<Label>,
+    prompts: Prompts,
+}
+
+impl ReplayBrowser {
+    pub fn new() -> Self {
+        Self {
+            replays: Vec::new(),
+            sort: Sort::Date,
+            selected: 0,
+            confirm_delete: false,
+            held: Vec::new(),
+            title_label: Label::new("REPLAYS", 75),
+            sort_label: Label::new("", 24),
+            row_labels: Vec::new(),
+            notice_label: None,
+            prompts: Prompts::new(&[], 20),
+        }
+    }
+
+    /// Returns the index of the first row shown, so the chosen one is always
+    /// visible.
+    fn first_shown(&self) -> usize {
+        let last = self.replays.len().saturating_sub(ROWS_SHOWN);
+        self.selected.saturating_sub(ROWS_SHOWN / 2).min(last)
+    }
+
+    /// Returns the index of the replay on the row tapped during the current
+    /// frame, if any.
+    fn tapped_row(&self, ctx: &Context) -> Option<usize> {
+        let court = &ctx.runner.sim().court;
+        let viewport = draw::viewport(court);
+        let touch = touches()
+            .into_iter()
+            .find(|touch| touch.phase == TouchPhase::Started)?;
+        let y = (touch.position.y - viewport.y) / viewport.h * court.height;
+        let row = ((y - FIRST_ROW_Y) / ROW_HEIGHT + 0.5).floor();
+        if row < 0. || row as usize >= ROWS_SHOWN {
+            return None;
+        }
+        let index = self.first_shown() + row as usize;
+        (index < self.replays.len()).then_some(index)
+    }
+
+    fn update_labels(&mut self, ctx: &Context) {
+        self.sort_label
+            .set_text(&format!("SORTED BY {}", self.sort.name()));
+
+        let rows: Vec<_> = self
+            .replays
+            .iter()
+            .skip(self.first_shown())
+            .take(ROWS_SHOWN)
+            .map(row_text)
+            .collect();
+        self.row_labels
+            .resize_with(rows.len(), || Label::new("", 24));
+        for (label, row) in self.row_labels.iter_mut().zip(&rows) {
+            label.set_text(row);
+        }
+
+        let notice = if self.replays.is_empty() {
+            Some("NO REPLAYS YET")
+        } else if self.confirm_delete {
+            Some("PRESS DELETE AGAIN TO DELETE THE REPLAY")
+        } else {
+            None
+        };
+        match (notice, &mut self.notice_label) {
+            (Some(text), Some(label)) => label.set_text(text),
+            (Some(text), label) => *label = Some(Label::new(text, 20)),
+            (None, label) => *label = None,
+        }
+        if notice.is_some() {
+            self.prompts.set_actions(&[Action::Leave]);
+        } else {
+            self.prompts.set_actions(&[
+                Action::Select,
+                Action::Watch,
+                Action::Sort,
+                Action::Delete,
+                Action::Leave,
+            ]);
+        }
+        self.prompts.update(ctx.scheme);
+    }
+}
+
+impl GameState for ReplayBrowser {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        self.replays = replays::load_all();
+        self.sort.sort(&mut self.replays);
+        self.selected = 0;
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if ctx.inputs.contains(&Input::Pause) {
+            return Transition::Pop;
+        }
+        let pressed = |input: Input| ctx.inputs.contains(&input) && !self.held.contains(&input);
+        let (mut up, mut down, mut watch) = (
+            pressed(Input::Up(Side::Left)) || pressed(Input::Up(Side::Right)),
+            pressed(Input::Down(Side::Left)) || pressed(Input::Down(Side::Right)),
+            pressed(Input::Serve),
+        );
+        self.held.clone_from(&ctx.inputs);
+
+        let selected = self.selected;
+        // Tapping a replay chooses it, and tapping it again watches it.
+        if !touches().is_empty() {
+            (up, down, watch) = (false, false, false);
+            if let Some(index) = self.tapped_row(ctx) {
+                watch = index == self.selected;
+                self.selected = index;
+            }
+        }
+
+        if !self.replays.is_empty() {
+            let count = self.replays.len();
+            if up {
+                self.selected = (self.selected + count - 1) % count;
+            }
+            if down {
+                self.selected = (self.selected + 1) % count;
+            }
+        }
+        if self.selected != selected {
+            self.confirm_delete = false;
+        }
+
+        if ctx.inputs.contains(&Input::Cycle) && !self.replays.is_empty() {
+            let name = self.replays[self.selected].name.clone();
+            let pos = Sort::ALL.iter().position(|&sort| sort == self.sort);
+            self.sort = Sort::ALL[pos.map_or(0, |pos| (pos + 1) % Sort::ALL.len())];
+            self.sort.sort(&mut self.replays);
+            // The chosen replay stays chosen.
+            self.selected = self
+                .replays
+                .iter()
+                .position(|replay| replay.name == name)
+                .unwrap_or(0);
+        }
+
+        if ctx.inputs.contains(&Input::Delete) && !self.replays.is_empty() {
+            if self.confirm_delete {
+                let replay = self.replays.remove(self.selected);
+                replays::remove(&replay.name);
+                self.selected = self.selected.min(self.replays.len().saturating_sub(1));
+            }
+            self.confirm_delete = !self.confirm_delete;
+        }
+
+        self.update_labels(ctx);
+
+        if watch {
+            if let Some(replay) = self.replays.get(self.selected) {
+                self.confirm_delete = false;
+                return Transition::Push(Box::new(Playback::new(replay.trace.clone())));
+            }
+        }
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let court = &ctx.runner.sim().court;
+        let x = court.width * 0.5;
+        self.title_label.draw_center(x, 50.);
+        self.sort_label.draw_center(x, 100.);
+        for (i, label) in self.row_labels.iter().enumerate() {
+            let y = FIRST_ROW_Y + i as f32 * ROW_HEIGHT;
+            label.draw_center(x, y);
+            if self.first_shown() + i == self.selected {
+                let half = ROW_HEIGHT * 0.5;
+                draw_rectangle_lines(
+                    20.,
+                    y - half,
+                    court.width - 40.,
+                    ROW_HEIGHT,
+                    2.,
+                    FOREGROUND_COLOR,
+                );
+            }
+        }
+        let hints = self.notice_label.iter().chain(self.prompts.labels());
+        for (i, label) in hints.rev().enumerate() {
+            label.draw_center(x, court.height - 20. - i as f32 * 24.);
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+}
+
+/// Returns the description of `replay` shown on its row.
+fn row_text(replay: &Replay) -> String {
+    let meta = &replay.trace.meta;
+    let date = meta
+        .date
+        .map_or_else(|| "UNKNOWN DATE".to_owned(), date_text);
+    let players = match &meta.players {
+        Some((left, right)) => format!("{left} vs {right}"),
+        None => "unknown players".to_owned(),
+    };
+    let score = match meta.scores {
+        Some((left, right)) => format!("{left} - {right}"),
+        None => "?".to_owned(),
+    };
+    let secs = replay.trace.duration_secs() as u32;
+    format!(
+        "{date}  {}  {score}  {}:{:02}  {}",
+        players.to_uppercase(),
+        secs / 60,
+        secs % 60,
+        replay.trace.lineup.to_string().to_uppercase(),
+    )
+}
+
+/// Returns the UTC date and time of the Unix timestamp `secs`.
+fn date_text(secs: u64) -> String {
+    let (days, secs) = (secs / 86400, secs % 86400);
+    // Civil date from the days since 1970-01-01, counting eras of 400 years
+    // from 0000-03-01.
+    let days = days as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60
+    )
+}
@@ -0,0 +1,180 @@
+use macroquad::prelude::*;
+use pong::sim::Side;
+
+use crate::{
+    audio::Bus,
+    input::{self, Input},
+    label::Label,
+    latency::MAX_LATENCY_SECS,
+    prompt::{Action, HintLabel},
+    state::{Context, GameState, Transition},
+};
+
+/// Seconds between beats of the metronome. Taps are matched to the nearest
+/// beat, so half of it must be longer than the highest latency.
+const BEAT_SECS: f64 = 0.9;
+/// Number of beats played, and how many of the first ones are not measured
+/// so the players can get into the rhythm.
+const BEATS: u32 = 10;
+const WARMUP_BEATS: u32 = 2;
+/// Fewest taps in time needed to measure the latency.
+const MIN_TAPS: usize = 4;
+
+/// Measures how long the sounds take to be heard by having the players tap
+/// in time with a metronome, then sets the audio latency to it.
+pub struct Calibration {
+    /// State started once the calibration is done.
+    next: Option<Box<dyn GameState>>,
+    /// Time the first beat is played at.
+    start: f64,
+    /// Beats played so far.
+    played: u32,
+    /// How late every tap in time was after its beat, in seconds.
+    offsets: Vec<f64>,
+    /// Measured latency, once the metronome is over. It is `None` if there
+    /// were too few taps in time, and the latency is kept as it was.
+    result: Option<Option<f32>>,
+    /// Inputs of the previous frame, so held keys only count once.
+    held: Vec<Input>,
+    title_label: Label,
+    status_label: Label,
+    retry_label: HintLabel,
+    confirm_label: HintLabel,
+}
+
+impl Calibration {
+    pub fn new(next: Box<dyn GameState>) -> Self {
+        Self {
+            next: Some(next),
+            start: 0.,
+            played: 0,
+            offsets: Vec::new(),
+            result: None,
+            held: Vec::new(),
+            title_label: Label::new("AUDIO LATENCY", 100),
+            status_label: Label::new("", 40),
+            retry_label: HintLabel::new(Action::Retry, 30),
+            confirm_label: HintLabel::new(Action::Confirm, 30),
+        }
+    }
+
+    /// Starts the metronome over.
+    fn restart(&mut self) {
+        self.start = get_time() + BEAT_SECS;
+        self.played = 0;
+        self.offsets.clear();
+        self.result = None;
+        self.update_beats_left();
+    }
+
+    fn update_beats_left(&mut self) {
+        self.status_label.set_text(&format!(
+            "TAP IN TIME WITH THE BEAT ({} LEFT)",
+            BEATS - self.played
+        ));
+    }
+
+    /// Plays the beats that are due and records the tap, if `tapped`.
+    fn update_metronome(&mut self, ctx: &Context, tapped: bool) {
+        let now = get_time();
+        while self.played < BEATS && now >= self.start + self.played as f64 * BEAT_SECS {
+            let sounds = &ctx.assets.sounds;
+            sounds.play_on(Bus::Ui, sounds.racket.first());
+            self.played += 1;
+            self.update_beats_left();
+        }
+
+        if tapped {
+            let beat = ((now - self.start) / BEAT_SECS).round();
+            let offset = now - self.start - beat * BEAT_SECS;
+            // Taps before the metronome starts for real are not measured.
+            if (WARMUP_BEATS as f64..BEATS as f64).contains(&beat) {
+                self.offsets.push(offset);
+            }
+        }
+
+        // The taps of the last beat can be late.
+        let end = self.start + BEATS as f64 * BEAT_SECS;
+        if now >= end {
+            self.result = Some(self.measure());
+            self.status_label.set_text(&match self.result {
+                Some(Some(latency)) => format!("LATENCY {:.0} MS", latency * 1000.),
+                _ => "NOT ENOUGH TAPS IN TIME".to_owned(),
+            });
+        }
+    }
+
+    /// Returns the median of the offsets of the taps, or `None` if there are
+    /// too few.
+    fn measure(&self) -> Option<f32> {
+        if self.offsets.len() < MIN_TAPS {
+            return None;
+        }
+        let mut offsets = self.offsets.clone();
+        offsets.sort_by(f64::total_cmp);
+        let median = offsets[offsets.len() / 2] as f32;
+        Some(median.clamp(0., MAX_LATENCY_SECS))
+    }
+}
+
+impl GameState for Calibration {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        self.restart();
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let pressed = |input: Input| ctx.inputs.contains(&input) && !self.held.contains(&input);
+        let (mut retry, mut confirm) = (
+            pressed(Input::Up(Side::Left))
+                || pressed(Input::Up(Side::Right))
+                || pressed(Input::Down(Side::Left))
+                || pressed(Input::Down(Side::Right)),
+            pressed(Input::Serve),
+        );
+        self.held.clone_from(&ctx.inputs);
+
+        let Some(result) = self.result else {
+            self.update_metronome(ctx, confirm);
+            return Transition::None;
+        };
+
+        // Taps on the top of the screen measure again and the ones on the
+        // bottom confirm the result.
+        if let Some(pos) = input::tapped() {
+            (retry, confirm) = (pos.y < 0.5, pos.y >= 0.5);
+        }
+        if retry {
+            self.restart();
+            return Transition::None;
+        }
+        if confirm {
+            if let Some(latency) = result {
+                ctx.settings.audio_latency = latency;
+            }
+            return match self.next.take() {
+                Some(next) => Transition::Replace(next),
+                None => Transition::Pop,
+            };
+        }
+
+        self.retry_label.update(ctx.scheme);
+        self.confirm_label.update(ctx.scheme);
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let center = ctx.runner.sim().court.rect().center();
+        self.title_label.draw_center(center.x, center.y - 150.);
+        self.status_label.draw_center(center.x, center.y);
+        if self.result.is_some() {
+            self.retry_label.draw_center(center.x, center.y + 120.);
+            self.confirm_label.draw_center(center.x, center.y + 160.);
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        // The beats must be played on time.
+        false
+    }
+}
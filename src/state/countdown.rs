@@ -0,0 +1,73 @@
+use macroquad::prelude::*;
+
+use crate::{
+    audio::Bus,
+    draw::FOREGROUND_COLOR,
+    label::Label,
+    settings::Effect,
+    state::{Context, GameState, Transition},
+};
+
+/// Seconds between the beats of the countdown.
+const BEAT_SECS: f32 = 0.5;
+/// Number of ticks before the tone serving the ball.
+const TICKS: u32 = 3;
+/// Radius the ring pulsing on every beat starts and ends at.
+const RING_RADIUS: (f32, f32) = (90., 20.);
+const RING_THICKNESS: f32 = 4.;
+
+/// Holds the serve with a steady count of three ticks followed by a tone, so
+/// the players can time it by ear as well as by sight. It is drawn over the
+/// match, which stays frozen until the tone.
+pub struct Countdown {
+    /// Seconds since the countdown started.
+    elapsed: f32,
+    /// Ticks played so far.
+    played: u32,
+    count_label: Label,
+}
+
+impl Countdown {
+    pub fn new() -> Self {
+        Self {
+            elapsed: 0.,
+            played: 0,
+            count_label: Label::new("", 150),
+        }
+    }
+}
+
+impl GameState for Countdown {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let sounds = &ctx.assets.sounds;
+        while self.played <= TICKS && self.elapsed >= self.played as f32 * BEAT_SECS {
+            if self.played == TICKS {
+                sounds.play_on(Bus::Ui, sounds.racket.first());
+                return Transition::Pop;
+            }
+            sounds.play_on(Bus::Ui, sounds.wall.first());
+            self.count_label
+                .set_text(&(TICKS - self.played).to_string());
+            self.played += 1;
+        }
+        self.elapsed += ctx.dt;
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let center = ctx.runner.sim().court.rect().center();
+        self.count_label.draw_center(center.x, center.y - 150.);
+        // The ring closes on the center of the court by the next beat.
+        let beat = (self.elapsed / BEAT_SECS).fract();
+        let radius = if ctx.settings.allows(Effect::Pulse) {
+            RING_RADIUS.0 + (RING_RADIUS.1 - RING_RADIUS.0) * beat
+        } else {
+            RING_RADIUS.1
+        };
+        draw_circle_lines(center.x, center.y, radius, RING_THICKNESS, FOREGROUND_COLOR);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
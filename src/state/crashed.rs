@@ -0,0 +1,96 @@
+use macroquad::prelude::*;
+
+use crate::{
+    crash::{Report, LAST_CRASH_NAME},
+    input::{self, Input},
+    label::Label,
+    prompt::{Action, HintLabel},
+    state::{Context, GameState, Gameplay, Transition},
+};
+
+/// Longest part of the panic message shown, in characters.
+const MAX_PANIC_LEN: usize = 60;
+
+/// Tells the players the previous run crashed and offers to resume the match
+/// that was being played, or to move on to the first state of the game.
+pub struct Crashed {
+    report: Report,
+    /// State started if the crashed match is not resumed.
+    next: Option<Box<dyn GameState>>,
+    title_label: Label,
+    panic_label: Label,
+    saved_label: Label,
+    recover_label: HintLabel,
+    dismiss_label: HintLabel,
+}
+
+impl Crashed {
+    pub fn new(report: Report, next: Box<dyn GameState>) -> Self {
+        let mut panic = report.panic.clone();
+        if let Some((end, _)) = panic.char_indices().nth(MAX_PANIC_LEN) {
+            panic.truncate(end);
+            panic.push_str("...");
+        }
+        // Crashes outside of matches leave nothing to resume, so they are
+        // only dismissed to start a new one.
+        let dismiss = if report.resume.is_some() {
+            Action::Dismiss
+        } else {
+            Action::Start
+        };
+        Self {
+            report,
+            next: Some(next),
+            title_label: Label::new("THE GAME CRASHED", 100),
+            panic_label: Label::new(&panic, 24),
+            saved_label: Label::new(&format!("The report was saved as {LAST_CRASH_NAME}"), 24),
+            recover_label: HintLabel::new(Action::Recover, 30),
+            dismiss_label: HintLabel::new(dismiss, 30),
+        }
+    }
+}
+
+impl GameState for Crashed {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let recover = ctx.inputs.contains(&Input::Serve) || input::tapped().is_some();
+        if let (true, Some(save)) = (recover, self.report.resume.take()) {
+            let code = save.code;
+            ctx.court = code.court;
+            ctx.physics = code.physics;
+            ctx.lineup = code.lineup;
+            ctx.rules = code.rules;
+            ctx.seed = Some(code.seed);
+            ctx.resume = Some((save.scores, save.sets));
+            return Transition::Replace(Box::new(Gameplay::new()));
+        }
+        if recover || get_last_key_pressed().is_some() {
+            return self
+                .next
+                .take()
+                .map_or(Transition::Pop, Transition::Replace);
+        }
+
+        self.recover_label.update(ctx.scheme);
+        self.dismiss_label.update(ctx.scheme);
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let center = ctx.runner.sim().court.rect().center();
+        self.title_label.draw_center(center.x, center.y - 150.);
+        self.panic_label.draw_center(center.x, center.y - 60.);
+        self.saved_label.draw_center(center.x, center.y - 20.);
+        if self.report.resume.is_some() {
+            self.recover_label.draw_center(center.x, center.y + 80.);
+        }
+        self.dismiss_label.draw_center(center.x, center.y + 120.);
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,204 @@
+use macroquad::{
+    miniquad::{self, Backend},
+    prelude::*,
+};
+use pong::archive;
+
+use crate::{
+    audio::{self, Bus},
+    crash,
+    input::{self, Input, Scheme},
+    label::Label,
+    prompt::{self, Action, HintLabel},
+    settings::Settings,
+    speedrun::Speedrun,
+    state::{Context, GameState, Transition},
+    stats::History,
+    storage::{self, Dir},
+};
+
+const SHADE_COLOR: Color = Color::new(0., 0., 0., 0.85);
+
+/// Shows the build of the game and what it runs on, to attach it to bug
+/// reports. It is hidden behind a key combination and drawn over any other
+/// screen.
+pub struct Diagnostics {
+    title_label: Label,
+    lines: Vec<Label>,
+    open_label: HintLabel,
+    data_label: Label,
+    /// Scheme the export and import prompts were written for.
+    data_scheme: Option<Scheme>,
+    /// Result of opening the data folder, or of exporting or importing the
+    /// data.
+    status_label: Option<Label>,
+    close_label: Label,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self {
+            title_label: Label::new("DIAGNOSTICS", 75),
+            lines: Vec::new(),
+            open_label: HintLabel::new(Action::OpenFolder, 30),
+            data_label: Label::new("", 30),
+            data_scheme: None,
+            status_label: None,
+            close_label: Label::new("(Press ESC to close)", 30),
+        }
+    }
+}
+
+impl GameState for Diagnostics {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        // SAFETY: The rendering context is only queried, and nothing else
+        // is drawing meanwhile.
+        let info = unsafe { get_internal_gl() }.quad_context.info();
+        let renderer = match info.backend {
+            Backend::OpenGl => format!("OpenGL {}", info.gl_version_string),
+            Backend::Metal => "Metal".to_owned(),
+        };
+        let sounds = &ctx.assets.sounds;
+        let buses: Vec<_> = Bus::ALL
+            .iter()
+            .zip(sounds.mixer.buses)
+            .map(|(bus, volume)| format!("{} {:.0}%", bus.name(), volume * 100.))
+            .collect();
+        let assets = ctx
+            .assets
+            .overrides
+            .as_ref()
+            .map_or_else(|| "embedded".to_owned(), |dir| dir.display().to_string());
+        let mut texts = vec![
+            format!("Build: {}", crash::build_info()),
+            format!("Renderer: {renderer}"),
+            format!(
+                "Screen: {}x{} at {:.1}x",
+                screen_width(),
+                screen_height(),
+                miniquad::window::dpi_scale()
+            ),
+            format!(
+                "Audio: {} (volume {:.0}%, {})",
+                audio::BACKEND,
+                sounds.mixer.master * 100.,
+                buses.join(", ")
+            ),
+            format!("Assets: {assets}"),
+        ];
+        for dir in Dir::ALL {
+            texts.push(format!(
+                "Folder for {}: {}",
+                dir.name(),
+                storage::location(dir)
+            ));
+        }
+        self.lines = texts.iter().map(|text| Label::new(text, 24)).collect();
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if ctx.inputs.contains(&Input::Pause) || input::tapped().is_some() {
+            return Transition::Pop;
+        }
+        if ctx.inputs.contains(&Input::OpenFolder) {
+            let text = match storage::open(Dir::Saves) {
+                Ok(()) => "Data folder opened".to_owned(),
+                Err(err) => {
+                    eprintln!("error: open data folder: {err}");
+                    "Could not open the data folder".to_owned()
+                }
+            };
+            self.status_label = Some(Label::new(&text, 24));
+        }
+        if ctx.inputs.contains(&Input::ExportData) {
+            let exported = storage::pack().and_then(|archive| {
+                storage::export(archive::NAME, &archive).map_err(|err| err.to_string())
+            });
+            let text = match exported {
+                Ok(location) => format!("Data exported to {location}"),
+                Err(err) => {
+                    eprintln!("error: export data: {err}");
+                    "Could not export the data".to_owned()
+                }
+            };
+            self.status_label = Some(Label::new(&text, 24));
+        }
+        let imported = if ctx.inputs.contains(&Input::ImportData) {
+            storage::pick_import(archive::NAME)
+        } else {
+            Ok(storage::take_import())
+        };
+        let text = match imported
+            .map_err(|err| err.to_string())
+            .and_then(|archive| archive.map(|archive| storage::unpack(&archive)).transpose())
+        {
+            Ok(Some(entries)) => {
+                reload(ctx);
+                Some(format!("{entries} entries imported"))
+            }
+            Ok(None) => None,
+            Err(err) => {
+                eprintln!("error: import data: {err}");
+                Some("Could not import the data".to_owned())
+            }
+        };
+        if let Some(text) = text {
+            self.status_label = Some(Label::new(&text, 24));
+        }
+        // The browser keeps the data out of reach.
+        if cfg!(not(target_family = "wasm")) {
+            self.open_label.update(ctx.scheme);
+        }
+        if self.data_scheme != Some(ctx.scheme) {
+            self.data_scheme = Some(ctx.scheme);
+            let hints: Vec<_> = [Action::ExportData, Action::ImportData]
+                .into_iter()
+                .filter_map(|action| prompt::prompt(action, ctx.scheme))
+                .collect();
+            let text = if hints.is_empty() {
+                String::new()
+            } else {
+                format!("({})", hints.join(", "))
+            };
+            self.data_label.set_text(&text);
+        }
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let court = &ctx.runner.sim().court;
+        let center = court.rect().center();
+        draw_rectangle(0., 0., court.width, court.height, SHADE_COLOR);
+        let top = center.y - self.lines.len() as f32 * 18. - 30.;
+        self.title_label.draw_center(center.x, top - 80.);
+        for (i, label) in self.lines.iter().enumerate() {
+            label.draw_center(center.x, top + i as f32 * 36.);
+        }
+        let bottom = top + self.lines.len() as f32 * 36.;
+        self.open_label.draw_center(center.x, bottom + 30.);
+        self.data_label.draw_center(center.x, bottom + 65.);
+        self.close_label.draw_center(center.x, bottom + 100.);
+        if let Some(label) = &self.status_label {
+            label.draw_center(center.x, court.height - 30.);
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+/// Loads the data kept in memory again after importing it, so it is not
+/// overwritten with the old data.
+fn reload(ctx: &mut Context) {
+    if let Some(settings) = Settings::load() {
+        ctx.settings = settings;
+        ctx.assets.sounds.mixer = settings.mixer();
+    }
+    ctx.history = History::load();
+    ctx.speedrun = Speedrun::load();
+}
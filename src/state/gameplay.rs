@@ -0,0 +1,829 @@
+use macroquad::{miniquad, prelude::*};
+use pong::{
+    code::MatchCode,
+    entity::Object,
+    sim::{self, Controls, Event, Lineup, Motion, Side, TICK_RATE},
+    trace::{Meta, Trace},
+};
+
+use crate::{
+    audio::Bus,
+    camera::{DynamicCamera, Minimap},
+    crash,
+    decals::Decals,
+    draw::{self, BACKGROUND_COLOR, FOREGROUND_COLOR},
+    ghost::Ghost,
+    haptics, input,
+    input::{Input, Scheme},
+    label::Label,
+    latency::Lookahead,
+    momentum::Momentum,
+    prompt::{self, Action},
+    replay::ReplayBuffer,
+    replays,
+    settings::{CameraMode, Effect, HudLayout},
+    state::{countdown::Countdown, pause::Pause, winner::Winner, Context, GameState, Transition},
+    stats::MatchStats,
+    trail::Trail,
+};
+
+/// How long the game slows down when a rally for the match starts, in real
+/// seconds.
+const SLOW_MOTION_SECS: f32 = 1.5;
+const SLOW_MOTION_SCALE: f32 = 0.35;
+const SLOW_MOTION_TINT: Color = Color::new(0.8, 0.1, 0.1, 0.25);
+
+/// Number of frames kept for the winner sequence.
+const REPLAY_FRAMES: usize = 256;
+
+/// How far back the practice modes can be rewound, and the steps between
+/// the snapshots kept for it.
+const REWIND_SECS: u64 = 10;
+const REWIND_INTERVAL: u64 = 2;
+const REWIND_TINT: Color = Color::new(0.1, 0.2, 0.8, 0.2);
+
+/// How long the score is called after every point with the table tennis
+/// score.
+const CALL_SECS: f32 = 1.5;
+/// Radius of the marker next to the racket serving with the table tennis
+/// score, and its distance to the racket.
+const SERVER_MARKER: (f32, f32) = (6., 16.);
+
+/// Width and height of the bars of the pause button on touch screens, and
+/// the gap between them.
+const PAUSE_BARS: (f32, f32, f32) = (6., 24., 8.);
+
+/// Size of the score in the classic and the stream layouts of the HUD.
+const SCORE_FONT_SIZE: u16 = 75;
+const STREAM_SCORE_FONT_SIZE: u16 = 120;
+/// Height of the momentum meter in the classic and stream layouts, below
+/// the sets and at the top of the court.
+const MOMENTUM_Y: f32 = 85.;
+const STREAM_MOMENTUM_Y: f32 = 20.;
+/// Top and size of the panel of the broadcast scoreboard, and the sizes of
+/// the score and the names in it.
+const BROADCAST_Y: f32 = 10.;
+const BROADCAST_PANEL: (f32, f32) = (600., 90.);
+const BROADCAST_PANEL_COLOR: Color = Color::new(0., 0., 0., 0.6);
+const BROADCAST_SCORE_FONT_SIZE: u16 = 80;
+const BROADCAST_NAME_FONT_SIZE: u16 = 32;
+/// Space between the center of the score and the names.
+const BROADCAST_NAME_GAP: f32 = 100.;
+/// Radius of the dots of the sets and the server, and space between them.
+const BROADCAST_DOT: (f32, f32) = (5., 16.);
+/// Height of the momentum meter below the broadcast scoreboard and its
+/// rally counter.
+const BROADCAST_MOMENTUM_Y: f32 = 150.;
+
+/// Seconds without input after which a player is warned, and after which
+/// they forfeit the match.
+const IDLE_WARN_SECS: f32 = 30.;
+const IDLE_FORFEIT_SECS: f32 = 40.;
+
+/// A match being played.
+pub struct Gameplay {
+    events: Vec<Event>,
+    score_label: Label,
+    /// Remaining time of the slow motion.
+    slow_motion: f32,
+    replay: ReplayBuffer,
+    stats: MatchStats,
+    /// Racket of the best run against the same bot.
+    ghost: Ghost,
+    trail: Trail,
+    /// Marks of the bounces off the walls in the current rally.
+    decals: Decals,
+    momentum: Momentum,
+    camera: DynamicCamera,
+    /// Whole court shown while the dynamic camera zooms in, created once
+    /// the court is known.
+    minimap: Option<Minimap>,
+    /// Whether the left and right sides are played by people.
+    humans: (bool, bool),
+    /// Seconds since the players on the left and right sides last moved.
+    idle: (f32, f32),
+    /// Side warned and seconds it has left to move, as shown by the idle
+    /// label.
+    idle_warning: Option<(Side, f32)>,
+    idle_label: Label,
+    /// Sounds played ahead of time to make up for the audio latency.
+    lookahead: Lookahead,
+    /// Whether the first serve of the match is still to be counted down.
+    first_serve: bool,
+    /// Snapshots of the last seconds of practice matches, to rewind them.
+    rewind: ReplayBuffer,
+    /// Step of the match shown while rewinding. It goes back by fractions
+    /// of a step on fast displays.
+    rewind_tick: Option<f32>,
+    /// Scores and sets of the step shown while rewinding, as written in the
+    /// score labels.
+    rewind_scores: Option<((i32, i32), (u32, u32))>,
+    /// Scheme the rewind prompt was written for.
+    rewind_scheme: Option<Scheme>,
+    rewind_label: Label,
+    /// Score called after the last point with the table tennis score, and
+    /// for how long it is still shown.
+    call_label: Label,
+    call_secs: f32,
+    /// Side that served the last point with the table tennis score, to
+    /// call when the service changes.
+    server: Option<Side>,
+    /// Sets won by every side, in matches of more than one set.
+    sets_label: Label,
+    /// Score, names of the players on the left and right sides and hits of
+    /// the rally shown by the broadcast scoreboard.
+    broadcast_score_label: Label,
+    broadcast_name_labels: (Label, Label),
+    rally_label: Label,
+    /// Whole seconds left in timed matches, as shown by the timer.
+    timer_secs: Option<u32>,
+    timer_label: Label,
+}
+
+impl Gameplay {
+    pub fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            score_label: Label::new("0 - 0", SCORE_FONT_SIZE),
+            slow_motion: 0.,
+            replay: ReplayBuffer::new(REPLAY_FRAMES),
+            stats: MatchStats::default(),
+            ghost: Ghost::new(),
+            trail: Trail::default(),
+            decals: Decals::default(),
+            momentum: Momentum::default(),
+            camera: DynamicCamera::default(),
+            minimap: None,
+            humans: (false, false),
+            idle: (0., 0.),
+            idle_warning: None,
+            idle_label: Label::new("", 40),
+            lookahead: Lookahead::default(),
+            first_serve: false,
+            rewind: ReplayBuffer::new((REWIND_SECS * TICK_RATE as u64 / REWIND_INTERVAL) as usize),
+            rewind_tick: None,
+            rewind_scores: None,
+            rewind_scheme: None,
+            rewind_label: Label::new("", 20),
+            call_label: Label::new("", 60),
+            call_secs: 0.,
+            server: None,
+            sets_label: Label::new("", 24),
+            broadcast_score_label: Label::new("0 - 0", BROADCAST_SCORE_FONT_SIZE),
+            broadcast_name_labels: (
+                Label::new("", BROADCAST_NAME_FONT_SIZE),
+                Label::new("", BROADCAST_NAME_FONT_SIZE),
+            ),
+            rally_label: Label::new("", 24),
+            timer_secs: None,
+            timer_label: Label::new("", 40),
+        }
+    }
+}
+
+impl Gameplay {
+    /// Keeps track of how long the players have been idle. Players are
+    /// warned after [`IDLE_WARN_SECS`] and forfeit the match after
+    /// [`IDLE_FORFEIT_SECS`], so a match left alone doesn't go on forever.
+    fn update_idle(&mut self, ctx: &mut Context) {
+        let mut warning = None;
+        for (side, human, idle) in [
+            (Side::Left, self.humans.0, &mut self.idle.0),
+            (Side::Right, self.humans.1, &mut self.idle.1),
+        ] {
+            if !human || input::motion(&ctx.inputs, side) != Motion::Stop {
+                *idle = 0.;
+                continue;
+            }
+            *idle += ctx.dt;
+            if *idle >= IDLE_FORFEIT_SECS {
+                *idle = 0.;
+                ctx.runner.apply(move |sim| sim.forfeit(side));
+            } else if *idle >= IDLE_WARN_SECS {
+                warning = Some((side, (IDLE_FORFEIT_SECS - *idle).ceil()));
+            }
+        }
+        if warning != self.idle_warning {
+            self.idle_warning = warning;
+            let text = warning
+                .map(|(side, secs)| format!("{side} PLAYER, MOVE OR FORFEIT IN {secs}"))
+                .unwrap_or_default();
+            self.idle_label.set_text(&text);
+        }
+    }
+}
+
+impl Gameplay {
+    /// Takes the match back in time, as fast as it was played, while the
+    /// rewind key is held. It resumes from there once the key is released.
+    fn update_rewind(&mut self, ctx: &mut Context) {
+        let tick = match self.rewind_tick {
+            Some(tick) => tick,
+            None => {
+                ctx.runner.set_paused(true);
+                // The points played since the last rewind changed the labels.
+                self.rewind_scores = None;
+                // Rewound matches cannot be replayed from their controls.
+                if !self.stats.rewound {
+                    self.stats.rewound = true;
+                    ctx.runner.take_trace();
+                    ctx.runner.take_inputs();
+                }
+                ctx.runner.sim().tick as f32
+            }
+        };
+        let mut tick = (tick - ctx.dt * TICK_RATE as f32).max(0.);
+        self.rewind_tick = Some(tick);
+        let Some(frame) = self.rewind.rewind_to(tick as u64) else {
+            return;
+        };
+        // The oldest snapshot is as far as it goes.
+        tick = tick.max(frame.sim.tick as f32);
+        self.rewind_tick = Some(tick);
+        let mut sim = frame.sim.clone();
+        sim.clear_events();
+        if self.rewind_scores != Some((sim.scores, sim.sets)) {
+            self.rewind_scores = Some((sim.scores, sim.sets));
+            self.score_label
+                .set_text(&score_text(ctx, sim.scores, sim.sets));
+            self.sets_label.set_text(&sets_text(ctx, sim.sets));
+            self.broadcast_score_label
+                .set_text(&format!("{} - {}", sim.scores.0, sim.scores.1));
+            self.server = server(ctx, sim.scores, sim.sets);
+        }
+        ctx.runner.apply(move |latest| *latest = sim);
+        self.events.clear();
+        ctx.runner.update(ctx.dt, &mut self.events);
+    }
+}
+
+impl GameState for Gameplay {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        let seed = ctx
+            .seed
+            .take()
+            .unwrap_or_else(|| (miniquad::date::now() * 1e6) as u64);
+        let court = ctx.court.clone();
+        let physics = ctx.physics;
+        let lineup = ctx.lineup;
+        let rules = ctx.rules;
+        ctx.code = Some(MatchCode {
+            court: court.clone(),
+            physics,
+            lineup,
+            rules,
+            seed,
+        });
+        ctx.speedrun
+            .start(&ctx.court, &ctx.physics, lineup, &ctx.bots);
+        self.camera.reset(&ctx.court);
+        self.minimap = Some(Minimap::new(&ctx.court));
+        self.ghost
+            .start(ctx.speedrun.category(), ctx.speedrun.runner_side());
+        crash::start(ctx.code.as_ref());
+        let resume = ctx.resume.take();
+        // Every match is recorded to keep its replay. Resumed matches cannot
+        // be replayed from their start.
+        if resume.is_none() {
+            ctx.runner
+                .record(court.clone(), physics, lineup, rules, seed);
+        }
+        // Input scripts start with the match, so they are neither recorded
+        // nor played in resumed matches.
+        let script = ctx.script.clone().filter(|_| resume.is_none());
+        let scripted = script.is_some();
+        if resume.is_none() && ctx.record_inputs.is_some() {
+            ctx.runner.record_inputs();
+        }
+        ctx.runner.play_script(script);
+        let (scores, sets) = resume.unwrap_or_default();
+        ctx.runner.apply(move |sim| {
+            sim.court = court;
+            sim.physics = physics;
+            sim.lineup = lineup;
+            sim.rules = rules;
+            sim.reset(seed);
+            sim.scores = scores;
+            sim.sets = sets;
+        });
+        ctx.runner.reset_bots(seed);
+        ctx.runner.set_time_scale(1.);
+        ctx.runner.set_paused(false);
+        self.score_label.set_text(&score_text(ctx, scores, sets));
+        self.sets_label.set_text(&sets_text(ctx, sets));
+        self.broadcast_score_label
+            .set_text(&format!("{} - {}", scores.0, scores.1));
+        let (left, right) = players(ctx);
+        for (side, name, label) in [
+            (Side::Left, left, &mut self.broadcast_name_labels.0),
+            (Side::Right, right, &mut self.broadcast_name_labels.1),
+        ] {
+            label.set_text(&match name.as_str() {
+                "player" => side.to_string(),
+                _ => name.replace('-', " ").to_uppercase(),
+            });
+        }
+        self.rally_label.set_text("");
+        self.server = server(ctx, scores, sets);
+        // The right keys move the partner in co-op matches, and nobody
+        // plays on the right in the modes without a right racket or when it
+        // mirrors the left one. Nobody plays at all while a script does.
+        self.humans = (
+            !scripted && ctx.bots.0.is_none(),
+            !scripted
+                && !ctx.mirror
+                && match lineup {
+                    Lineup::Single | Lineup::Juggler => ctx.bots.1.is_none(),
+                    Lineup::Coop => true,
+                    Lineup::Goalkeeper | Lineup::Targets => false,
+                },
+        );
+        self.idle = (0., 0.);
+        self.first_serve = true;
+    }
+
+    fn on_exit(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        ctx.runner.set_time_scale(1.);
+    }
+
+    fn on_cover(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+    }
+
+    fn on_uncover(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(false);
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if ctx.inputs.contains(&Input::Pause) || ctx.inputs.contains(&Input::Suspend) {
+            return Transition::Push(Box::new(Pause::new()));
+        }
+        if std::mem::take(&mut self.first_serve) && has_countdown(ctx) {
+            return Transition::Push(Box::new(Countdown::new()));
+        }
+
+        let controls = controls(ctx);
+        ctx.runner.set_controls(controls);
+
+        self.update_idle(ctx);
+
+        let timer_secs = ctx
+            .runner
+            .sim()
+            .time_left()
+            .map(|secs| secs.max(0.).ceil() as u32);
+        if timer_secs != self.timer_secs {
+            self.timer_secs = timer_secs;
+            self.timer_label
+                .set_text(&timer_secs.map(|secs| secs.to_string()).unwrap_or_default());
+        }
+
+        self.score_label
+            .set_font_size(match ctx.settings.hud_layout {
+                HudLayout::Classic => SCORE_FONT_SIZE,
+                HudLayout::Stream => STREAM_SCORE_FONT_SIZE,
+            });
+
+        if can_rewind(ctx.lineup) && self.rewind_scheme != Some(ctx.scheme) {
+            self.rewind_scheme = Some(ctx.scheme);
+            let hint = prompt::prompt(Action::Rewind, ctx.scheme);
+            self.rewind_label.set_text(&hint.unwrap_or_default());
+        }
+        if can_rewind(ctx.lineup) && ctx.inputs.contains(&Input::Rewind) {
+            self.trail.clear();
+            self.decals.clear();
+            self.camera.reset(&ctx.runner.sim().court);
+            self.update_rewind(ctx);
+            return Transition::None;
+        }
+        if self.rewind_tick.take().is_some() {
+            ctx.runner.set_paused(false);
+        }
+
+        self.call_secs = (self.call_secs - ctx.dt).max(0.);
+        if self.slow_motion > 0. {
+            self.slow_motion -= ctx.dt;
+            if self.slow_motion <= 0. {
+                ctx.runner.set_time_scale(1.);
+            }
+        }
+
+        self.events.clear();
+        ctx.runner.update(ctx.dt, &mut self.events);
+        self.replay.push(ctx.runner.sim(), &self.events);
+        // The rackets are placed back for the serve after a point, and a
+        // new rally starts.
+        let point = self
+            .events
+            .iter()
+            .any(|event| matches!(event, Event::Point(_)));
+        if point {
+            self.trail.clear();
+            self.decals.clear();
+        }
+        self.camera.update(ctx.runner.sim(), point, ctx.dt);
+        self.trail.update(&ctx.runner.sim().entities, ctx.dt);
+        self.decals.update(ctx.dt);
+        self.momentum.update(ctx.dt);
+        if self.events.contains(&Event::WallBounce) {
+            self.decals.hit(ctx.runner.sim());
+        }
+        let tick = ctx.runner.sim().tick;
+        if can_rewind(ctx.lineup)
+            && self
+                .rewind
+                .latest()
+                .is_none_or(|frame| frame.sim.tick + REWIND_INTERVAL <= tick)
+        {
+            self.rewind.push(ctx.runner.sim(), &[]);
+        }
+        crash::record(ctx.runner.sim(), &self.events);
+
+        // The game runs slower in slow motion, and so does the lookahead.
+        let mut latency = ctx.settings.audio_latency;
+        if self.slow_motion > 0. {
+            latency *= SLOW_MOTION_SCALE;
+        }
+        self.lookahead
+            .predict(ctx.runner.sim(), controls, latency, &ctx.assets.sounds);
+
+        let mut transition = Transition::None;
+        for &event in &self.events {
+            if self.lookahead.is_pending(event, ctx.runner.sim().tick) {
+                ctx.assets.sounds.play(event, ctx.runner.sim());
+            }
+            if let Some(entry) = self.stats.record(event, ctx.runner.sim()) {
+                self.momentum.record(&entry, ctx.runner.sim());
+                self.rally_label.set_text(&match self.stats.rally() {
+                    0 => String::new(),
+                    rally => format!("RALLY {rally}"),
+                });
+            }
+            if ctx.settings.haptics {
+                haptics::play(event);
+            }
+            if ctx.settings.allows(Effect::ShaderPulse) {
+                ctx.pulses.trigger(event, ctx.runner.sim());
+            }
+            match event {
+                Event::Point(side) => {
+                    ctx.speedrun.split(side, ctx.runner.sim().tick);
+                    let sim = ctx.runner.sim();
+                    let (scores, sets) = (sim.scores, sim.sets);
+                    self.score_label.set_text(&score_text(ctx, scores, sets));
+                    self.sets_label.set_text(&sets_text(ctx, sets));
+                    self.broadcast_score_label
+                        .set_text(&format!("{} - {}", scores.0, scores.1));
+                    let server = server(ctx, scores, sets);
+                    let changed = server != self.server;
+                    if let Some(text) = call_score(ctx, scores, sets, changed) {
+                        self.call_label.set_text(&text);
+                        self.call_secs = CALL_SECS;
+                        if changed {
+                            let sounds = &ctx.assets.sounds;
+                            sounds.play_on(Bus::Announcer, &sounds.chime);
+                        }
+                    }
+                    self.server = server;
+                }
+                Event::MatchPoint(_) => {
+                    self.slow_motion = SLOW_MOTION_SECS;
+                    ctx.runner.set_time_scale(SLOW_MOTION_SCALE);
+                }
+                Event::Winner(side) => {
+                    ctx.speedrun.finish(side);
+                    // The replay is saved with the names of who played it.
+                    let players = players(ctx);
+                    if let Some(party) = &mut ctx.party {
+                        party.finish(side);
+                    }
+                    if let Some(mut trace) = ctx.runner.take_trace() {
+                        trace.meta = Meta {
+                            players: Some(players),
+                            date: Some(miniquad::date::now() as u64),
+                            scores: Some(ctx.runner.sim().scores),
+                        };
+                        replays::save(&trace);
+                        save_trace(ctx, &trace);
+                        if let Some(category) = ctx.speedrun.category() {
+                            if ctx.speedrun.is_new_best() {
+                                Ghost::save(category, &trace);
+                            }
+                        }
+                    }
+                    save_inputs(ctx);
+                    let replay = std::mem::replace(&mut self.replay, ReplayBuffer::new(0));
+                    let stats = std::mem::take(&mut self.stats);
+                    transition = Transition::Replace(Box::new(Winner::new(side, replay, stats)));
+                }
+                Event::TargetHit => {
+                    let sim = ctx.runner.sim();
+                    self.score_label
+                        .set_text(&score_text(ctx, sim.scores, sim.sets));
+                }
+                Event::WallBounce | Event::RacketBounce | Event::SetWon(_) => {}
+            }
+        }
+        if point && matches!(transition, Transition::None) && has_countdown(ctx) {
+            transition = Transition::Push(Box::new(Countdown::new()));
+        }
+        self.stats.observe(ctx.runner.sim());
+        ctx.speedrun.update(ctx.runner.sim().tick);
+        self.ghost.update(ctx.runner.sim().tick);
+        transition
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let sim = ctx.runner.sim();
+        // The court is drawn through the dynamic camera, if enabled, but the
+        // HUD stays in place.
+        let view = (ctx.settings.camera == CameraMode::Dynamic
+            && ctx.settings.allows(Effect::Camera))
+        .then(|| self.camera.view(&sim.court));
+        let court_camera = view.map(|view| ctx.canvas.zoomed(view));
+        if let Some(camera) = &court_camera {
+            set_camera(camera);
+        }
+        draw::draw_walls(&sim.court, sim.tick);
+        self.decals.draw();
+        set_camera(&ctx.canvas.camera);
+        if ctx.hud {
+            self.draw_hud(ctx);
+        }
+        self.idle_label
+            .draw_center(sim.court.width * 0.5, sim.court.height * 0.5);
+        if let Some(camera) = &court_camera {
+            set_camera(camera);
+        }
+        self.ghost.draw();
+        if ctx.settings.allows(Effect::Trail) {
+            self.trail.draw(ctx.settings.racket_trail);
+        }
+        draw::draw_entities(&sim.entities, ctx.settings.outline);
+        if ctx.hud {
+            self.draw_server_marker(ctx);
+        }
+        set_camera(&ctx.canvas.camera);
+        if let (Some(view), Some(minimap), true) = (view, &self.minimap, ctx.hud) {
+            set_camera(&minimap.camera);
+            clear_background(BACKGROUND_COLOR);
+            draw::draw_walls(&sim.court, sim.tick);
+            draw::draw_entities(&sim.entities, false);
+            set_camera(&ctx.canvas.camera);
+            minimap.draw(&sim.court, view);
+        }
+        if self.call_secs > 0. && ctx.hud {
+            self.call_label
+                .draw_center(sim.court.width * 0.5, sim.court.height * 0.3);
+        }
+
+        if self.slow_motion > 0. && ctx.settings.allows(Effect::Tint) {
+            let mut tint = SLOW_MOTION_TINT;
+            tint.a *= self.slow_motion / SLOW_MOTION_SECS;
+            draw_rectangle(0., 0., sim.court.width, sim.court.height, tint);
+        }
+        if self.rewind_tick.is_some() && ctx.settings.allows(Effect::Tint) {
+            draw_rectangle(0., 0., sim.court.width, sim.court.height, REWIND_TINT);
+        }
+    }
+}
+
+impl Gameplay {
+    /// Draws the score, the time left, the rewind hint and, on touch
+    /// screens, the pause button.
+    fn draw_hud(&self, ctx: &Context) {
+        let sim = ctx.runner.sim();
+        if ctx.scheme == Scheme::Touch {
+            draw_pause_button();
+        }
+        // Only matches between two sides have a broadcast scoreboard and
+        // momentum.
+        let two_sided = matches!(ctx.lineup, Lineup::Single | Lineup::Coop | Lineup::Juggler);
+        let broadcast = ctx.settings.broadcast_hud && two_sided;
+        if broadcast {
+            self.draw_broadcast_scoreboard(ctx);
+        } else {
+            match ctx.settings.hud_layout {
+                HudLayout::Classic => {
+                    self.score_label.draw_center(sim.court.width * 0.5, 30.);
+                    self.sets_label.draw_center(sim.court.width * 0.5, 65.);
+                }
+                HudLayout::Stream => {
+                    self.score_label.draw_left(20., sim.court.height - 70.);
+                    self.sets_label.draw_left(24., sim.court.height - 20.);
+                }
+            }
+        }
+        if ctx.settings.momentum_meter && two_sided {
+            let y = match ctx.settings.hud_layout {
+                _ if broadcast => BROADCAST_MOMENTUM_Y,
+                HudLayout::Classic => MOMENTUM_Y,
+                HudLayout::Stream => STREAM_MOMENTUM_Y,
+            };
+            self.momentum.draw(&sim.court, y);
+        }
+        self.timer_label
+            .draw_left(sim.court.width - self.timer_label.width() - 20., 26.);
+        self.rewind_label
+            .draw_center(sim.court.width * 0.5, sim.court.height - 20.);
+    }
+
+    /// Draws the scoreboard of the broadcast HUD: the names of the players
+    /// around the score, the sets they have won below their names, a dot
+    /// next to the name of the server and the hits of the current rally.
+    fn draw_broadcast_scoreboard(&self, ctx: &Context) {
+        let sim = ctx.runner.sim();
+        let center = sim.court.width * 0.5;
+        let (width, height) = BROADCAST_PANEL;
+        draw_rectangle(
+            center - width * 0.5,
+            BROADCAST_Y,
+            width,
+            height,
+            BROADCAST_PANEL_COLOR,
+        );
+        let middle = BROADCAST_Y + height * 0.5;
+        self.broadcast_score_label.draw_center(center, middle - 10.);
+
+        // Sets needed to win the match, shown as dots filled as they are won.
+        let sets = (ctx.rules.best_of > 1).then_some(ctx.rules.best_of / 2 + 1);
+        let (radius, gap) = BROADCAST_DOT;
+        for (side, label, won) in [
+            (Side::Left, &self.broadcast_name_labels.0, sim.sets.0),
+            (Side::Right, &self.broadcast_name_labels.1, sim.sets.1),
+        ] {
+            // Names end next to the score, with the dot of the server
+            // between them and the dots of the sets from their outer end.
+            let (x, dir) = match side {
+                Side::Left => (center - BROADCAST_NAME_GAP - label.width(), -1.),
+                Side::Right => (center + BROADCAST_NAME_GAP, 1.),
+            };
+            label.draw_left(x, middle - 12.);
+            let inner = center + dir * BROADCAST_NAME_GAP;
+            if self.server == Some(side) {
+                draw_circle(inner - dir * gap, middle - 12., radius, FOREGROUND_COLOR);
+            }
+            for i in 0..sets.unwrap_or(0) {
+                let x = inner + dir * (label.width() - radius - i as f32 * gap);
+                if i < won {
+                    draw_circle(x, middle + 16., radius, FOREGROUND_COLOR);
+                } else {
+                    draw_circle_lines(x, middle + 16., radius, 1., FOREGROUND_COLOR);
+                }
+            }
+        }
+
+        self.rally_label
+            .draw_center(center, BROADCAST_Y + height + 22.);
+    }
+
+    /// Draws a dot next to the racket of the server, if the service
+    /// alternates.
+    fn draw_server_marker(&self, ctx: &Context) {
+        let sim = ctx.runner.sim();
+        if let Some(racket) = self.server.and_then(|side| sim.racket(side)) {
+            let rect = racket.rect();
+            let (radius, gap) = SERVER_MARKER;
+            let x = match racket.side {
+                Side::Left => rect.right() + gap,
+                Side::Right => rect.left() - gap,
+            };
+            draw_circle(x, rect.center().y, radius, FOREGROUND_COLOR);
+        }
+    }
+}
+
+/// Draws the two bars of the pause button in the middle of its area.
+fn draw_pause_button() {
+    let center = input::pause_button().center();
+    let (width, height, gap) = PAUSE_BARS;
+    for x in [center.x - gap * 0.5 - width, center.x + gap * 0.5] {
+        draw_rectangle(x, center.y - height * 0.5, width, height, FOREGROUND_COLOR);
+    }
+}
+
+/// Returns the controls of the players in the inputs of the frame.
+pub fn controls(ctx: &Context) -> Controls {
+    // In co-op matches the keys of the right player move the partner, and
+    // in mirror practice the right racket follows the left one.
+    let left = input::motion(&ctx.inputs, Side::Left);
+    let (right, partner) = match ctx.lineup {
+        Lineup::Single if ctx.mirror => (left, Motion::Stop),
+        Lineup::Single | Lineup::Goalkeeper | Lineup::Juggler | Lineup::Targets => {
+            (input::motion(&ctx.inputs, Side::Right), Motion::Stop)
+        }
+        Lineup::Coop => (Motion::Stop, input::motion(&ctx.inputs, Side::Right)),
+    };
+    Controls {
+        left,
+        right,
+        partner,
+    }
+}
+
+/// Returns whether matches of `lineup` can be rewound. Only the practice
+/// modes, played alone against a machine, can.
+fn can_rewind(lineup: Lineup) -> bool {
+    matches!(lineup, Lineup::Goalkeeper | Lineup::Targets)
+}
+
+/// Returns whether the serves of the match are counted down. Only the
+/// lineups where the ball is served after every point have them.
+fn has_countdown(ctx: &Context) -> bool {
+    ctx.settings.serve_countdown
+        && matches!(ctx.lineup, Lineup::Single | Lineup::Coop | Lineup::Juggler)
+}
+
+/// Returns the side serving the next point of a set at `scores` after
+/// `sets` were won if the score is called as in table tennis, which only
+/// matches played point by point against someone are.
+fn server(ctx: &Context, scores: (i32, i32), sets: (u32, u32)) -> Option<Side> {
+    (ctx.settings.table_tennis_score && matches!(ctx.lineup, Lineup::Single | Lineup::Coop))
+        .then(|| sim::table_tennis_server(&ctx.rules, scores, sets))
+}
+
+/// Returns the score called after a point that left the set at `scores`
+/// after `sets` were won, if it is called as in table tennis. The service
+/// changing is called too.
+fn call_score(
+    ctx: &Context,
+    scores: (i32, i32),
+    sets: (u32, u32),
+    changed: bool,
+) -> Option<String> {
+    let server = server(ctx, scores, sets)?;
+    let text = score_text(ctx, scores, sets);
+    if !changed {
+        return Some(text);
+    }
+    Some(format!("{text}  {server} TO SERVE"))
+}
+
+/// Returns the text of the sets won by every side, shown under the score in
+/// matches of more than one set.
+fn sets_text(ctx: &Context, sets: (u32, u32)) -> String {
+    if ctx.rules.best_of == 1
+        || !matches!(ctx.lineup, Lineup::Single | Lineup::Coop | Lineup::Juggler)
+    {
+        return String::new();
+    }
+    format!("SETS {} - {}", sets.0, sets.1)
+}
+
+/// Returns the text of the score shown on top of the court. With the table
+/// tennis score, the score of the server comes first.
+fn score_text(ctx: &Context, scores: (i32, i32), sets: (u32, u32)) -> String {
+    let scores = match server(ctx, scores, sets) {
+        Some(Side::Right) => (scores.1, scores.0),
+        Some(Side::Left) | None => scores,
+    };
+    match ctx.lineup {
+        Lineup::Goalkeeper => format!("{} SAVES - {} GOALS", scores.0, scores.1),
+        Lineup::Targets => format!("{} TARGETS - {} MISSED", scores.0, scores.1),
+        Lineup::Single | Lineup::Coop | Lineup::Juggler => {
+            format!("{} - {}", scores.0, scores.1)
+        }
+    }
+}
+
+/// Returns the names of who plays on the left and right sides, for the
+/// replays.
+fn players(ctx: &Context) -> (String, String) {
+    if let Some(party) = &ctx.party {
+        let (left, right) = &party.players;
+        return (left.to_lowercase(), right.to_lowercase());
+    }
+    let name = |bot: &Option<String>| bot.clone().unwrap_or_else(|| "player".to_owned());
+    let right = match ctx.lineup {
+        Lineup::Single if ctx.mirror => "mirror".to_owned(),
+        Lineup::Goalkeeper => "ball-machine".to_owned(),
+        Lineup::Targets => "targets".to_owned(),
+        Lineup::Single | Lineup::Coop | Lineup::Juggler => name(&ctx.bots.1),
+    };
+    (name(&ctx.bots.0), right)
+}
+
+/// Saves the inputs of the players in the finished match if recording them
+/// was requested.
+fn save_inputs(ctx: &mut Context) {
+    let Some(script) = ctx.runner.take_inputs() else {
+        return;
+    };
+    let Some(path) = &ctx.record_inputs else {
+        return;
+    };
+    if let Err(err) = std::fs::write(path, script.to_string()) {
+        eprintln!("error: write inputs {}: {err}", path.display());
+    }
+}
+
+/// Saves the trace of the finished match if recording was requested.
+fn save_trace(ctx: &Context, trace: &Trace) {
+    let Some(path) = &ctx.record else {
+        return;
+    };
+    if let Err(err) = std::fs::write(path, trace.to_string()) {
+        eprintln!("error: write trace {}: {err}", path.display());
+    }
+}
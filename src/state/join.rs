@@ -0,0 +1,109 @@
+use macroquad::prelude::*;
+use pong::sim::Lineup;
+
+use crate::{
+    input::{self, Device, Devices},
+    label::Label,
+    state::{Context, GameState, Transition},
+};
+
+/// Lets the players of a match between two people pick their side with the
+/// keys they want to play with. The first device pressed takes the left
+/// racket and the second one the right racket. Tapping the screen skips the
+/// screen, as touches move the racket on the half of the screen touched.
+pub struct Join {
+    /// State started once both players have joined.
+    next: Option<Box<dyn GameState>>,
+    /// Devices that have joined, in order.
+    joined: Vec<Device>,
+    title_label: Label,
+    slot_labels: [Label; 2],
+    slot_names: [&'static str; 2],
+}
+
+impl Join {
+    pub fn new(next: Box<dyn GameState>) -> Self {
+        Self {
+            next: Some(next),
+            joined: Vec::new(),
+            title_label: Label::new("PRESS A KEY TO JOIN", 75),
+            slot_labels: [Label::new("", 40), Label::new("", 40)],
+            slot_names: ["LEFT", "RIGHT"],
+        }
+    }
+
+    /// Returns whether the match in `ctx` is played by two people, who
+    /// must pick their sides first.
+    pub fn is_needed(ctx: &Context) -> bool {
+        match ctx.lineup {
+            Lineup::Coop => true,
+            Lineup::Single | Lineup::Juggler => {
+                !ctx.mirror && ctx.bots.0.is_none() && ctx.bots.1.is_none()
+            }
+            Lineup::Goalkeeper | Lineup::Targets => false,
+        }
+    }
+
+    /// Writes the slots again, after a device joined.
+    fn update_slots(&mut self) {
+        let free: Vec<_> = Device::ALL
+            .into_iter()
+            .filter(|device| !self.joined.contains(device))
+            .map(Device::name)
+            .collect();
+        for (i, label) in self.slot_labels.iter_mut().enumerate() {
+            let text = match self.joined.get(i) {
+                Some(device) => format!("{}: {}", self.slot_names[i], device.name()),
+                None => format!("{}: press {}", self.slot_names[i], free.join(" or ")),
+            };
+            label.set_text(&text);
+        }
+    }
+}
+
+impl GameState for Join {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        // Both players share the left side in co-op matches.
+        if ctx.lineup == Lineup::Coop {
+            self.slot_names = ["TOP LEFT", "BOTTOM LEFT"];
+        }
+        self.update_slots();
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if input::tapped().is_some() {
+            ctx.devices = Devices::default();
+            return self
+                .next
+                .take()
+                .map_or(Transition::Pop, Transition::Replace);
+        }
+        if let Some(device) = input::device_pressed() {
+            if !self.joined.contains(&device) {
+                self.joined.push(device);
+                self.update_slots();
+            }
+        }
+        if let [left, right] = self.joined[..] {
+            ctx.devices = Devices { left, right };
+            return self
+                .next
+                .take()
+                .map_or(Transition::Pop, Transition::Replace);
+        }
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let center = ctx.runner.sim().court.rect().center();
+        self.title_label.draw_center(center.x, center.y - 100.);
+        for (i, label) in self.slot_labels.iter().enumerate() {
+            label.draw_center(center.x, center.y + i as f32 * 60.);
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,60 @@
+use macroquad::prelude::*;
+
+use crate::{
+    label::Label,
+    menu::Menu,
+    state::{mode_select::ModeSelect, Context, GameState, Transition},
+};
+
+/// Where the first entry of the menus is drawn.
+pub const MENU_TOP: f32 = 220.;
+
+/// Entries of the menu. The web version cannot quit, as the page stays open.
+#[cfg(not(target_family = "wasm"))]
+const ENTRIES: &[&str] = &["1 PLAYER", "2 PLAYERS", "QUIT"];
+#[cfg(target_family = "wasm")]
+const ENTRIES: &[&str] = &["1 PLAYER", "2 PLAYERS"];
+
+/// First screen of the game, where the number of players is chosen.
+pub struct MainMenu {
+    menu: Menu,
+    title_label: Label,
+}
+
+impl MainMenu {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new(ENTRIES),
+            title_label: Label::new("PONG", 80),
+        }
+    }
+}
+
+impl GameState for MainMenu {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        self.menu.hold(&ctx.inputs);
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let court = &ctx.runner.sim().court;
+        match self.menu.update(&ctx.inputs, court, MENU_TOP) {
+            Some(0) => return Transition::Replace(Box::new(ModeSelect::new(1))),
+            Some(1) => return Transition::Replace(Box::new(ModeSelect::new(2))),
+            Some(_) => return Transition::Quit,
+            None => {}
+        }
+        self.menu.update_hints(ctx.scheme);
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let court = &ctx.runner.sim().court;
+        self.title_label.draw_center(court.rect().center().x, 100.);
+        self.menu.draw(court, MENU_TOP);
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+}
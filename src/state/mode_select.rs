@@ -0,0 +1,138 @@
+use macroquad::prelude::*;
+use pong::{
+    bot,
+    sim::{Lineup, Side},
+};
+
+use crate::{
+    input::Input,
+    label::Label,
+    menu::Menu,
+    state::{
+        self,
+        main_menu::{MainMenu, MENU_TOP},
+        Context, GameState, Transition,
+    },
+    COOP_BOT,
+};
+
+/// Bot played against by a single player.
+const OPPONENT_BOT: &str = "tracker";
+
+/// Way of playing that can be chosen.
+#[derive(Clone, Copy)]
+enum Mode {
+    /// One player against [`OPPONENT_BOT`].
+    VersusBot,
+    Goalkeeper,
+    Targets,
+    Mirror,
+    /// Two players against each other.
+    Versus,
+    Coop,
+    Juggler,
+    /// Goes back to the main menu.
+    Back,
+}
+
+impl Mode {
+    const ONE_PLAYER: &'static [Mode] = &[
+        Mode::VersusBot,
+        Mode::Goalkeeper,
+        Mode::Targets,
+        Mode::Mirror,
+        Mode::Back,
+    ];
+    const TWO_PLAYERS: &'static [Mode] = &[Mode::Versus, Mode::Coop, Mode::Juggler, Mode::Back];
+
+    fn title(self) -> &'static str {
+        match self {
+            Mode::VersusBot => "VERSUS BOT",
+            Mode::Goalkeeper => "GOALKEEPER",
+            Mode::Targets => "TARGET PRACTICE",
+            Mode::Mirror => "MIRROR",
+            Mode::Versus => "VERSUS",
+            Mode::Coop => "CO-OP",
+            Mode::Juggler => "JUGGLER",
+            Mode::Back => "BACK",
+        }
+    }
+}
+
+/// Lets the players choose what to play, once they have chosen how many
+/// they are in the main menu.
+pub struct ModeSelect {
+    modes: &'static [Mode],
+    menu: Menu,
+    title_label: Label,
+}
+
+impl ModeSelect {
+    /// Offers the modes for `players` players.
+    pub fn new(players: u32) -> Self {
+        let (modes, title) = match players {
+            1 => (Mode::ONE_PLAYER, "1 PLAYER"),
+            _ => (Mode::TWO_PLAYERS, "2 PLAYERS"),
+        };
+        let titles: Vec<_> = modes.iter().map(|mode| mode.title()).collect();
+        Self {
+            modes,
+            menu: Menu::new(&titles),
+            title_label: Label::new(title, 60),
+        }
+    }
+}
+
+/// Sets up the matches of `mode` and returns the state they start with.
+fn start(ctx: &mut Context, mode: Mode) -> Box<dyn GameState> {
+    let (lineup, bots) = match mode {
+        Mode::VersusBot => (Lineup::Single, (None, Some(OPPONENT_BOT))),
+        Mode::Goalkeeper => (Lineup::Goalkeeper, (None, None)),
+        Mode::Targets => (Lineup::Targets, (None, None)),
+        Mode::Coop => (Lineup::Coop, (None, Some(COOP_BOT))),
+        Mode::Juggler => (Lineup::Juggler, (None, None)),
+        Mode::Mirror | Mode::Versus | Mode::Back => (Lineup::Single, (None, None)),
+    };
+    ctx.lineup = lineup;
+    ctx.mirror = matches!(mode, Mode::Mirror);
+    ctx.bots = (bots.0.map(str::to_owned), bots.1.map(str::to_owned));
+    for (side, name) in [(Side::Left, bots.0), (Side::Right, bots.1)] {
+        ctx.runner.set_bot(side, name.and_then(bot::by_name));
+    }
+    state::first_match(ctx)
+}
+
+impl GameState for ModeSelect {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        self.menu.hold(&ctx.inputs);
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let court = &ctx.runner.sim().court;
+        match self.menu.update(&ctx.inputs, court, MENU_TOP) {
+            Some(i) => {
+                return match self.modes[i] {
+                    Mode::Back => Transition::Replace(Box::new(MainMenu::new())),
+                    mode => Transition::Replace(start(ctx, mode)),
+                };
+            }
+            None if ctx.inputs.contains(&Input::Pause) => {
+                return Transition::Replace(Box::new(MainMenu::new()));
+            }
+            None => {}
+        }
+        self.menu.update_hints(ctx.scheme);
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let court = &ctx.runner.sim().court;
+        self.title_label.draw_center(court.rect().center().x, 100.);
+        self.menu.draw(court, MENU_TOP);
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,86 @@
+use macroquad::prelude::*;
+use pong::sim::Side;
+
+use crate::{
+    input::Input,
+    label::Label,
+    prompt::{Action, HintLabel},
+    state::{gameplay::Gameplay, Context, GameState, Transition},
+};
+
+/// Shown before every match of a party: who won the last one and how many
+/// in a row, who plays the next one and who waits in the queue.
+pub struct NextUp {
+    /// Inputs of the previous frame, so the key that left the winner screen
+    /// doesn't start the match too.
+    held: Vec<Input>,
+    title_label: Label,
+    streak_label: Label,
+    match_label: Label,
+    queue_label: Label,
+    start_label: HintLabel,
+}
+
+impl NextUp {
+    pub fn new() -> Self {
+        Self {
+            held: Vec::new(),
+            title_label: Label::new("WINNER STAYS ON", 60),
+            streak_label: Label::new("", 30),
+            match_label: Label::new("", 40),
+            queue_label: Label::new("", 24),
+            start_label: HintLabel::new(Action::Start, 20),
+        }
+    }
+}
+
+impl GameState for NextUp {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        self.held.clone_from(&ctx.inputs);
+        let Some(party) = &ctx.party else {
+            return;
+        };
+        let streak = match party.streak {
+            Some((side, 1)) => format!("{} WINS", party.player(side)),
+            Some((side, streak)) => format!("{} WINS {streak} IN A ROW", party.player(side)),
+            None => String::new(),
+        };
+        self.streak_label.set_text(&streak);
+        self.match_label.set_text(&format!(
+            "{} - {}",
+            party.player(Side::Left),
+            party.player(Side::Right)
+        ));
+        let queue: Vec<_> = party.queue.iter().map(String::as_str).collect();
+        self.queue_label
+            .set_text(&format!("NEXT IN LINE: {}", queue.join(", ")));
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let start = ctx.inputs.contains(&Input::Serve) && !self.held.contains(&Input::Serve);
+        self.held.clone_from(&ctx.inputs);
+        if start {
+            return Transition::Replace(Box::new(Gameplay::new()));
+        }
+        if ctx.party.is_none() {
+            return Transition::Replace(Box::new(Gameplay::new()));
+        }
+        self.start_label.update(ctx.scheme);
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let court = &ctx.runner.sim().court;
+        let center = court.rect().center();
+        self.title_label.draw_center(center.x, 80.);
+        self.streak_label.draw_center(center.x, center.y - 80.);
+        self.match_label.draw_center(center.x, center.y);
+        self.queue_label.draw_center(center.x, center.y + 70.);
+        self.start_label.draw_center(center.x, court.height - 40.);
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+}
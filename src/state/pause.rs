@@ -0,0 +1,141 @@
+use macroquad::prelude::*;
+
+use crate::{
+    draw::FOREGROUND_COLOR,
+    input::{self, Input, Scheme},
+    label::Label,
+    page,
+    prompt::{Action, HintLabel},
+    state::{main_menu::MainMenu, photo::Photo, summary::Summary, Context, GameState, Transition},
+};
+
+const SHADE_COLOR: Color = Color::new(0., 0., 0., 0.6);
+
+/// Time the screen must be held to leave the match on touch screens.
+const LEAVE_HOLD_SECS: f32 = 1.;
+
+/// Size of the bar filling up while the screen is held.
+const HOLD_BAR_SIZE: (f32, f32) = (300., 8.);
+
+/// Freezes the state below until the game is resumed.
+pub struct Pause {
+    paused_label: Label,
+    resume_label: HintLabel,
+    photo_label: HintLabel,
+    /// Code of the match, to share it.
+    code_label: Label,
+    leave_label: HintLabel,
+    menu_label: HintLabel,
+    /// Tells that reloading the page gets a new version of the game.
+    update_label: Label,
+    /// Time the screen has been held, if it was touched while paused. Short
+    /// taps resume the match and long presses leave it.
+    held_secs: Option<f32>,
+}
+
+impl Pause {
+    pub fn new() -> Self {
+        Self {
+            paused_label: Label::new("PAUSED", 150),
+            resume_label: HintLabel::new(Action::Resume, 40),
+            photo_label: HintLabel::new(Action::Photo, 24),
+            code_label: Label::new("", 24),
+            leave_label: HintLabel::new(Action::LeaveMatch, 24),
+            menu_label: HintLabel::new(Action::MainMenu, 24),
+            update_label: Label::new("", 24),
+            held_secs: None,
+        }
+    }
+}
+
+impl GameState for Pause {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        if let Some(code) = &ctx.code {
+            self.code_label.set_text(&format!("CODE {code}"));
+        }
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if ctx.inputs.contains(&Input::Pause) {
+            return Transition::Pop;
+        }
+        // The touches don't count either while the game is suspended.
+        if ctx.inputs.contains(&Input::Suspend) {
+            self.held_secs = None;
+            return Transition::None;
+        }
+        // Touch screens have no pause key: the screen is tapped to resume
+        // and held to leave. Only touches started while paused count, so
+        // releasing the one that paused doesn't resume.
+        if input::tapped().is_some() {
+            self.held_secs = Some(0.);
+        }
+        if let Some(secs) = &mut self.held_secs {
+            if touches().is_empty() {
+                return Transition::Pop;
+            }
+            *secs += ctx.dt;
+            if *secs >= LEAVE_HOLD_SECS {
+                return leave(ctx);
+            }
+        }
+        if ctx.inputs.contains(&Input::MainMenu) {
+            return Transition::Reset(Box::new(MainMenu::new()));
+        }
+        if ctx.inputs.contains(&Input::Photo) {
+            let sim = ctx.runner.sim().clone();
+            return Transition::Push(Box::new(Photo::new(sim)));
+        }
+        self.resume_label.update(ctx.scheme);
+        self.photo_label.update(ctx.scheme);
+        self.leave_label.update(ctx.scheme);
+        self.menu_label.update(ctx.scheme);
+        if page::update_available() {
+            self.update_label
+                .set_text("UPDATE AVAILABLE: RELOAD THE PAGE TO PLAY IT");
+        }
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let court = &ctx.runner.sim().court;
+        let center = court.rect().center();
+        draw_rectangle(0., 0., court.width, court.height, SHADE_COLOR);
+        self.update_label.draw_center(center.x, 30.);
+        self.paused_label.draw_center(center.x, center.y);
+        self.resume_label.draw_center(center.x, center.y + 100.);
+        self.photo_label.draw_center(center.x, center.y + 150.);
+        // Holding the screen to leave is only needed on touch screens.
+        if ctx.scheme == Scheme::Touch {
+            self.leave_label.draw_center(center.x, center.y + 190.);
+        } else {
+            self.menu_label.draw_center(center.x, center.y + 190.);
+        }
+        if let Some(secs) = self.held_secs {
+            let (width, height) = HOLD_BAR_SIZE;
+            let filled = width * (secs / LEAVE_HOLD_SECS).min(1.);
+            let x = center.x - width * 0.5;
+            draw_rectangle(x, center.y + 70., filled, height, FOREGROUND_COLOR);
+        }
+        self.code_label.draw_center(center.x, court.height - 30.);
+    }
+
+    fn is_idle(&self) -> bool {
+        // The bar fills up smoothly while the screen is held.
+        self.held_secs.is_none()
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+/// Leaves the match, summing up the session first like quitting does.
+fn leave(ctx: &Context) -> Transition {
+    let session = ctx.history.session();
+    if session.is_empty() {
+        Transition::Quit
+    } else {
+        Transition::Replace(Box::new(Summary::new(session)))
+    }
+}
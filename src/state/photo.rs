@@ -0,0 +1,204 @@
+use macroquad::{miniquad, prelude::*};
+use pong::sim::{Side, Simulation};
+
+use crate::{
+    camera::FreeCamera,
+    draw::{self, Canvas, Crt, BACKGROUND_COLOR},
+    input::Input,
+    label::Label,
+    prompt::{Action, Prompts},
+    render,
+    state::{Context, GameState, Transition},
+    storage::{self, Dir},
+};
+
+/// How much every press changes a part of the CRT effect.
+const KNOB_STEP: f32 = 0.1;
+
+/// Part of the CRT effect adjusted in the photo mode.
+#[derive(Clone, Copy, PartialEq)]
+enum Knob {
+    Curvature,
+    Scanlines,
+    Vignette,
+}
+
+impl Knob {
+    const ALL: [Knob; 3] = [Knob::Curvature, Knob::Scanlines, Knob::Vignette];
+
+    fn name(self) -> &'static str {
+        match self {
+            Knob::Curvature => "CURVATURE",
+            Knob::Scanlines => "SCANLINES",
+            Knob::Vignette => "VIGNETTE",
+        }
+    }
+
+    fn value(self, crt: &mut Crt) -> &mut f32 {
+        match self {
+            Knob::Curvature => &mut crt.curvature,
+            Knob::Scanlines => &mut crt.scanlines,
+            Knob::Vignette => &mut crt.vignette,
+        }
+    }
+}
+
+/// Freezes the match to take photos of it, without the score and with a
+/// camera that can be zoomed and panned with the mouse. The CRT effect can
+/// be adjusted for the photos, which are saved to the screenshots
+/// directory as seen on screen.
+pub struct Photo {
+    sim: Simulation,
+    camera: FreeCamera,
+    /// Index of the part of the CRT effect being adjusted in [`Knob::ALL`].
+    knob: usize,
+    /// Whether the controls are shown over the photo.
+    controls: bool,
+    /// CRT effect before the photo mode, given back when leaving it.
+    crt: Crt,
+    /// Inputs of the previous frame, so held keys only count once.
+    held: Vec<Input>,
+    /// Knob, percentage and whether the CRT effect was enabled when the
+    /// knob label was written.
+    knob_shown: Option<(usize, i32, bool)>,
+    knob_label: Label,
+    saved_label: Label,
+    prompts: Prompts,
+}
+
+impl Photo {
+    pub fn new(sim: Simulation) -> Self {
+        Self {
+            camera: FreeCamera::new(&sim.court),
+            sim,
+            knob: 0,
+            controls: true,
+            crt: Crt::default(),
+            held: Vec::new(),
+            knob_shown: None,
+            knob_label: Label::new("", 40),
+            saved_label: Label::new("", 24),
+            prompts: Prompts::new(&[], 20),
+        }
+    }
+
+    /// Draws the frozen match on `canvas` as seen by the camera.
+    fn draw_frame(&self, canvas: &Canvas, outline: bool) {
+        set_camera(&canvas.zoomed(self.camera.view(&self.sim.court, None)));
+        clear_background(BACKGROUND_COLOR);
+        draw::draw_walls(&self.sim.court, self.sim.tick);
+        draw::draw_entities(&self.sim.entities, outline);
+    }
+
+    /// Saves the frozen match through the CRT effect as a PNG image, and
+    /// returns the text telling where.
+    fn save(&self, ctx: &Context) -> String {
+        let canvas = Canvas::new(&self.sim.court);
+        let output = Canvas::new(&self.sim.court);
+        self.draw_frame(&canvas, ctx.settings.outline);
+        let crt = ctx.settings.crt.then_some(ctx.crt);
+        render::draw_through_crt(&canvas, &output, &ctx.assets, crt, ctx.settings.palette);
+        set_camera(&ctx.canvas.camera);
+
+        let name = format!("photo-{}.png", (miniquad::date::now() * 1e3) as u64);
+        let saved = storage::path(Dir::Screenshots, &name).and_then(|path| {
+            render::save_png(&output, &path)?;
+            Ok(path)
+        });
+        match saved {
+            Ok(path) => format!("SAVED TO {}", path.display()),
+            Err(err) => {
+                eprintln!("error: save photo {name}: {err}");
+                "COULD NOT SAVE THE PHOTO".to_owned()
+            }
+        }
+    }
+}
+
+impl GameState for Photo {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        self.crt = ctx.crt;
+    }
+
+    fn on_exit(&mut self, ctx: &mut Context) {
+        ctx.crt = self.crt;
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if ctx.inputs.contains(&Input::Pause) {
+            return Transition::Pop;
+        }
+        let pressed = |input: Input| ctx.inputs.contains(&input) && !self.held.contains(&input);
+        let up = pressed(Input::Up(Side::Left)) || pressed(Input::Up(Side::Right));
+        let down = pressed(Input::Down(Side::Left)) || pressed(Input::Down(Side::Right));
+        let take = pressed(Input::Serve);
+        self.held.clone_from(&ctx.inputs);
+
+        if ctx.inputs.contains(&Input::Heatmap) {
+            self.controls = !self.controls;
+        }
+        if ctx.inputs.contains(&Input::Cycle) {
+            self.knob = (self.knob + 1) % Knob::ALL.len();
+        }
+        let knob = Knob::ALL[self.knob];
+        if up || down {
+            let step = if up { KNOB_STEP } else { -KNOB_STEP };
+            let value = knob.value(&mut ctx.crt);
+            *value = (*value + step).clamp(0., 1.);
+        }
+        self.camera.update_mouse(&self.sim.court, None);
+        if take {
+            let text = self.save(ctx);
+            self.saved_label.set_text(&text);
+        }
+
+        let percent = (*knob.value(&mut ctx.crt) * 100.).round() as i32;
+        let knob_shown = (self.knob, percent, ctx.settings.crt);
+        if self.knob_shown != Some(knob_shown) {
+            self.knob_shown = Some(knob_shown);
+            let knob_text = if ctx.settings.crt {
+                format!("< {} {percent}% >", knob.name())
+            } else {
+                "CRT EFFECT OFF".to_owned()
+            };
+            self.knob_label.set_text(&knob_text);
+        }
+        let actions = if ctx.settings.crt {
+            [
+                Action::TakePhoto,
+                Action::Tune,
+                Action::NextKnob,
+                Action::HideControls,
+                Action::Leave,
+            ]
+            .as_slice()
+        } else {
+            [Action::TakePhoto, Action::HideControls, Action::Leave].as_slice()
+        };
+        self.prompts.set_actions(actions);
+        self.prompts.update(ctx.scheme);
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        self.draw_frame(&ctx.canvas, ctx.settings.outline);
+        set_camera(&ctx.canvas.camera);
+        if !self.controls {
+            return;
+        }
+        let court = &self.sim.court;
+        self.knob_label.draw_center(court.width * 0.5, 30.);
+        self.saved_label.draw_center(court.width * 0.5, 70.);
+        for (i, label) in self.prompts.labels().iter().rev().enumerate() {
+            label.draw_center(court.width * 0.5, court.height - 20. - i as f32 * 24.);
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,224 @@
+use macroquad::prelude::*;
+use pong::{
+    entity::Entity,
+    sim::{MatchState, Simulation, TICK_SECS},
+    trace::Trace,
+};
+
+use crate::{
+    camera::FreeCamera,
+    draw,
+    input::Input,
+    label::Label,
+    prompt::{Action, Prompts},
+    state::{Context, GameState, Transition},
+};
+
+/// Speed of the playback in slow motion.
+const SLOW_MOTION_SCALE: f32 = 0.25;
+/// Zoom of the camera when it starts following the ball.
+const FOLLOW_ZOOM: f32 = 2.5;
+
+/// What the playback is doing, as told under the score.
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Playing,
+    SlowMotion,
+    /// Paused at the step.
+    Paused(u64),
+    End,
+}
+
+impl Status {
+    fn text(self) -> String {
+        match self {
+            Status::Playing => String::new(),
+            Status::SlowMotion => "SLOW MOTION".to_owned(),
+            Status::Paused(tick) => format!("PAUSED AT STEP {tick}"),
+            Status::End => "END".to_owned(),
+        }
+    }
+}
+
+/// Plays a recorded match back with a camera that can zoom on the ball, pan
+/// around the court, slow down and step one frame at a time.
+pub struct Playback {
+    trace: Trace,
+    sim: Simulation,
+    /// Time of the playback not simulated yet.
+    lag: f32,
+    paused: bool,
+    slow_motion: bool,
+    /// Whether the camera stays centered on the ball.
+    follow: bool,
+    camera: FreeCamera,
+    /// Scores and status the labels were written for.
+    shown: Option<((i32, i32), Status)>,
+    score_label: Label,
+    status_label: Label,
+    prompts: Prompts,
+}
+
+impl Playback {
+    pub fn new(trace: Trace) -> Self {
+        let sim = replay_sim(&trace);
+        Self {
+            trace,
+            camera: FreeCamera::new(&sim.court),
+            sim,
+            lag: 0.,
+            paused: false,
+            slow_motion: false,
+            follow: false,
+            shown: None,
+            score_label: Label::new("0 - 0", 75),
+            status_label: Label::new("", 40),
+            prompts: Prompts::new(&[], 20),
+        }
+    }
+
+    /// Returns whether the whole trace has been played.
+    fn is_over(&self) -> bool {
+        self.sim.state != MatchState::Playing
+            || self.sim.tick as usize >= self.trace.controls().len()
+    }
+
+    /// Advances the playback by one step of the simulation.
+    fn step(&mut self, ctx: &Context) {
+        let Some(&controls) = self.trace.controls().get(self.sim.tick as usize) else {
+            return;
+        };
+        self.sim.step(controls);
+        for &event in self.sim.events() {
+            ctx.assets.sounds.play(event, &self.sim);
+        }
+        self.sim.clear_events();
+    }
+
+    /// Returns where the camera is centered if it follows the ball.
+    fn target(&self) -> Option<Vec2> {
+        ball_center(&self.sim).filter(|_| self.follow)
+    }
+}
+
+impl GameState for Playback {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        // The court is drawn at the size of the one the match was played on.
+        let court = self.trace.court.clone();
+        ctx.runner.apply(move |sim| sim.court = court);
+    }
+
+    fn on_exit(&mut self, ctx: &mut Context) {
+        let court = ctx.court.clone();
+        ctx.runner.apply(move |sim| sim.court = court);
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if ctx.inputs.contains(&Input::Pause) {
+            return Transition::Pop;
+        }
+        if ctx.inputs.contains(&Input::Serve) {
+            if self.is_over() {
+                self.sim = replay_sim(&self.trace);
+                self.lag = 0.;
+                self.paused = false;
+            } else {
+                self.paused = !self.paused;
+            }
+        }
+        if ctx.inputs.contains(&Input::SlowMotion) {
+            self.slow_motion = !self.slow_motion;
+        }
+        if ctx.inputs.contains(&Input::Follow) {
+            self.follow = !self.follow;
+            self.camera = FreeCamera::new(&self.sim.court);
+            if self.follow {
+                self.camera.zoom = FOLLOW_ZOOM;
+            }
+        }
+        if self.camera.update_mouse(&self.sim.court, self.target()) {
+            self.follow = false;
+        }
+
+        if self.paused {
+            if ctx.inputs.contains(&Input::StepFrame) {
+                self.step(ctx);
+            }
+        } else {
+            let scale = if self.slow_motion {
+                SLOW_MOTION_SCALE
+            } else {
+                1.
+            };
+            self.lag += ctx.dt * scale;
+            while self.lag >= TICK_SECS && !self.is_over() {
+                self.lag -= TICK_SECS;
+                self.step(ctx);
+            }
+        }
+
+        let status = if self.is_over() {
+            Status::End
+        } else if self.paused {
+            Status::Paused(self.sim.tick)
+        } else if self.slow_motion {
+            Status::SlowMotion
+        } else {
+            Status::Playing
+        };
+        let shown = (self.sim.scores, status);
+        if self.shown != Some(shown) {
+            self.shown = Some(shown);
+            self.score_label
+                .set_text(&format!("{} - {}", self.sim.scores.0, self.sim.scores.1));
+            self.status_label.set_text(&status.text());
+        }
+        let actions = if self.is_over() {
+            [Action::WatchAgain, Action::Leave].as_slice()
+        } else {
+            [
+                Action::TogglePause,
+                Action::StepFrame,
+                Action::SlowMotion,
+                Action::Follow,
+                Action::Leave,
+            ]
+            .as_slice()
+        };
+        self.prompts.set_actions(actions);
+        self.prompts.update(ctx.scheme);
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let view = self.camera.view(&self.sim.court, self.target());
+        set_camera(&ctx.canvas.zoomed(view));
+        draw::draw_walls(&self.sim.court, self.sim.tick);
+        draw::draw_entities(&self.sim.entities, ctx.settings.outline);
+        set_camera(&ctx.canvas.camera);
+
+        let court = &self.sim.court;
+        self.score_label.draw_center(court.width * 0.5, 30.);
+        self.status_label.draw_center(court.width * 0.5, 80.);
+        for (i, label) in self.prompts.labels().iter().rev().enumerate() {
+            label.draw_center(court.width * 0.5, court.height - 20. - i as f32 * 24.);
+        }
+    }
+}
+
+/// Returns the simulation a trace is replayed on, ready for its first step.
+fn replay_sim(trace: &Trace) -> Simulation {
+    let mut sim = Simulation::with_rules(trace.court.clone(), trace.physics, trace.seed);
+    sim.lineup = trace.lineup;
+    sim.rules = trace.rules;
+    sim.reset(trace.seed);
+    sim
+}
+
+fn ball_center(sim: &Simulation) -> Option<Vec2> {
+    sim.entities.iter().find_map(|entity| match entity {
+        Entity::Ball(ball) => Some(ball.center()),
+        _ => None,
+    })
+}
@@ -0,0 +1,305 @@
+use macroquad::prelude::*;
+use pong::{
+    rules::Rules,
+    sim::{Service, Side},
+};
+
+use crate::{
+    input::Input,
+    label::Label,
+    prompt::{Action, Prompts},
+    rulesets::{self, Ruleset, NAME_LEN},
+    state::{setup::cycle, Context, GameState, Transition},
+};
+
+/// Highest win score and number of sets that can be chosen.
+const MAX_WIN_SCORE: i32 = 21;
+const MAX_BEST_OF: u32 = 7;
+/// Step between the time limits that can be chosen, in seconds, and the
+/// longest one.
+const TIME_LIMIT_STEP: u32 = 60;
+const MAX_TIME_LIMIT: u32 = 600;
+
+/// Letters the names of the rulesets are made of.
+const NAME_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Part of the rules being edited.
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    /// Ruleset the rules start from.
+    Ruleset,
+    WinScore,
+    Sets,
+    Deuce,
+    Service,
+    Timer,
+    /// Name the rules are saved with, edited one letter at a time.
+    Name,
+}
+
+impl Field {
+    const ALL: [Field; 7] = [
+        Field::Ruleset,
+        Field::WinScore,
+        Field::Sets,
+        Field::Deuce,
+        Field::Service,
+        Field::Timer,
+        Field::Name,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Field::Ruleset => "RULESET",
+            Field::WinScore => "POINTS PER SET",
+            Field::Sets => "BEST OF",
+            Field::Deuce => "WIN BY TWO",
+            Field::Service => "SERVICE",
+            Field::Timer => "TIME LIMIT",
+            Field::Name => "SAVE AS",
+        }
+    }
+}
+
+/// Lets the players choose the rules of the next matches, starting from a
+/// preset or a saved ruleset. Rules that match none of the presets are
+/// saved with their name, replacing the ruleset saved with the same one.
+pub struct RulesEditor {
+    /// State started once the rules are chosen.
+    next: Option<Box<dyn GameState>>,
+    rulesets: Vec<Ruleset>,
+    /// Index of the ruleset the rules started from in `rulesets`.
+    ruleset: usize,
+    rules: Rules,
+    name: [u8; NAME_LEN],
+    /// Index of the field being edited in [`Field::ALL`], and of the letter
+    /// being edited in the name.
+    field: usize,
+    letter: usize,
+    /// Inputs of the previous frame, so held keys only count once.
+    held: Vec<Input>,
+    title_label: Label,
+    field_labels: Vec<Label>,
+    prompts: Prompts,
+}
+
+impl RulesEditor {
+    pub fn new(next: Box<dyn GameState>) -> Self {
+        Self {
+            next: Some(next),
+            rulesets: Vec::new(),
+            ruleset: 0,
+            rules: Rules::CLASSIC,
+            name: [NAME_CHARS[0]; NAME_LEN],
+            field: 0,
+            letter: 0,
+            held: Vec::new(),
+            title_label: Label::new("RULES", 60),
+            field_labels: Field::ALL.iter().map(|_| Label::new("", 24)).collect(),
+            prompts: Prompts::new(
+                &[
+                    Action::Change,
+                    Action::NextField,
+                    Action::Start,
+                    Action::Leave,
+                ],
+                20,
+            ),
+        }
+    }
+
+    /// Starts from the ruleset at `index` in `rulesets`.
+    fn select(&mut self, index: usize) {
+        self.ruleset = index;
+        let ruleset = &self.rulesets[index];
+        self.rules = ruleset.rules;
+        if ruleset.name.len() == NAME_LEN {
+            self.name.copy_from_slice(ruleset.name.as_bytes());
+        }
+    }
+
+    /// Changes the field being edited to the next or previous value.
+    fn change(&mut self, up: bool) {
+        let rules = &mut self.rules;
+        match Field::ALL[self.field] {
+            Field::Ruleset => self.select(cycle(self.ruleset, self.rulesets.len(), up)),
+            Field::WinScore => {
+                let step = if up { 1 } else { -1 };
+                rules.win_score = (rules.win_score + step).clamp(1, MAX_WIN_SCORE);
+            }
+            Field::Sets => {
+                rules.best_of = if up {
+                    (rules.best_of + 2).min(MAX_BEST_OF)
+                } else {
+                    rules.best_of.saturating_sub(2).max(1)
+                };
+            }
+            Field::Deuce => rules.deuce = !rules.deuce,
+            Field::Service => {
+                rules.service = match rules.service {
+                    Service::Classic => Service::TableTennis,
+                    Service::TableTennis => Service::Classic,
+                };
+            }
+            Field::Timer => {
+                let secs = rules.time_limit.unwrap_or(0);
+                let secs = if up {
+                    (secs + TIME_LIMIT_STEP).min(MAX_TIME_LIMIT)
+                } else {
+                    secs.saturating_sub(TIME_LIMIT_STEP)
+                };
+                rules.time_limit = (secs > 0).then_some(secs);
+            }
+            Field::Name => {
+                let letter = &mut self.name[self.letter];
+                let pos = NAME_CHARS.iter().position(|c| c == letter).unwrap_or(0);
+                *letter = NAME_CHARS[cycle(pos, NAME_CHARS.len(), up)];
+            }
+        }
+    }
+
+    /// Saves the rules if they match none of the presets.
+    fn save(&mut self) {
+        if self.rules.preset_name().is_some() {
+            return;
+        }
+        let ruleset = Ruleset {
+            name: String::from_utf8_lossy(&self.name).into_owned(),
+            rules: self.rules,
+        };
+        match self
+            .rulesets
+            .iter_mut()
+            .find(|saved| saved.name == ruleset.name)
+        {
+            Some(saved) => *saved = ruleset,
+            None => self.rulesets.push(ruleset),
+        }
+        if let Err(err) = rulesets::save(&self.rulesets) {
+            eprintln!("error: save rules: {err}");
+        }
+    }
+
+    /// Writes the fields again, after the rules or the field being edited
+    /// changed.
+    fn update_fields(&mut self) {
+        for (i, field) in Field::ALL.into_iter().enumerate() {
+            let value = self.value_text(field);
+            let text = if i == self.field {
+                format!("{}  < {value} >", field.title())
+            } else {
+                format!("{}  {value}", field.title())
+            };
+            self.field_labels[i].set_text(&text);
+        }
+    }
+
+    fn value_text(&self, field: Field) -> String {
+        let rules = &self.rules;
+        match field {
+            Field::Ruleset => {
+                let ruleset = &self.rulesets[self.ruleset];
+                if ruleset.rules == *rules {
+                    ruleset.name.clone()
+                } else {
+                    format!("{} (CHANGED)", ruleset.name)
+                }
+            }
+            Field::WinScore => rules.win_score.to_string(),
+            Field::Sets => match rules.best_of {
+                1 => "1 SET".to_owned(),
+                sets => format!("{sets} SETS"),
+            },
+            Field::Deuce => if rules.deuce { "ON" } else { "OFF" }.to_owned(),
+            Field::Service => match rules.service {
+                Service::Classic => "CLASSIC".to_owned(),
+                Service::TableTennis => "TABLE TENNIS".to_owned(),
+            },
+            Field::Timer => match rules.time_limit {
+                Some(secs) => format!("{}:{:02}", secs / 60, secs % 60),
+                None => "OFF".to_owned(),
+            },
+            Field::Name if rules.preset_name().is_some() => "(PRESET)".to_owned(),
+            Field::Name => self
+                .name
+                .iter()
+                .enumerate()
+                .map(|(i, &c)| match Field::ALL[self.field] {
+                    Field::Name if i == self.letter => format!("[{}]", c as char),
+                    _ => format!(" {} ", c as char),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl GameState for RulesEditor {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        self.rulesets = rulesets::load();
+        let index = self
+            .rulesets
+            .iter()
+            .position(|ruleset| ruleset.rules == ctx.rules)
+            .unwrap_or(0);
+        self.select(index);
+        self.rules = ctx.rules;
+        self.update_fields();
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let pressed = |input: Input| ctx.inputs.contains(&input) && !self.held.contains(&input);
+        let up = pressed(Input::Up(Side::Left)) || pressed(Input::Up(Side::Right));
+        let down = pressed(Input::Down(Side::Left)) || pressed(Input::Down(Side::Right));
+        let confirm = pressed(Input::Serve);
+        self.held.clone_from(&ctx.inputs);
+
+        if ctx.inputs.contains(&Input::Pause) {
+            return self
+                .next
+                .take()
+                .map_or(Transition::Pop, Transition::Replace);
+        }
+        if confirm {
+            self.save();
+            ctx.rules = self.rules;
+            return self
+                .next
+                .take()
+                .map_or(Transition::Pop, Transition::Replace);
+        }
+        if ctx.inputs.contains(&Input::Cycle) {
+            if Field::ALL[self.field] == Field::Name && self.letter + 1 < NAME_LEN {
+                self.letter += 1;
+            } else {
+                self.field = (self.field + 1) % Field::ALL.len();
+                self.letter = 0;
+            }
+            self.update_fields();
+        }
+        // Presets are never saved, so they need no name.
+        let unnamed = Field::ALL[self.field] == Field::Name && self.rules.preset_name().is_some();
+        if (up || down) && !unnamed {
+            self.change(up);
+            self.update_fields();
+        }
+        self.prompts.update(ctx.scheme);
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let court = &ctx.runner.sim().court;
+        let center = court.rect().center();
+        self.title_label.draw_center(center.x, 60.);
+        for (i, label) in self.field_labels.iter().enumerate() {
+            label.draw_center(center.x, 140. + i as f32 * 40.);
+        }
+        for (i, label) in self.prompts.labels().iter().rev().enumerate() {
+            label.draw_center(center.x, court.height - 20. - i as f32 * 24.);
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,268 @@
+use macroquad::prelude::*;
+use pong::sim::Side;
+
+use crate::{
+    audio::Bus,
+    input::{self, Input, Scheme},
+    label::Label,
+    prompt::{Action, HintLabel},
+    settings::{Language, Settings, FRAME_RATES, VOLUME_STEP},
+    state::{Context, GameState, Transition},
+    vision::{self, Vision, PALETTE},
+};
+
+/// Settings chosen during the setup, in order.
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    Language,
+    Controls,
+    Crt,
+    Volume,
+    Vsync,
+    FrameRate,
+    ReducedMotion,
+    Outline,
+    ServeCountdown,
+    TableTennisScore,
+    Colors,
+}
+
+impl Step {
+    const ALL: [Step; 11] = [
+        Step::Language,
+        Step::Controls,
+        Step::Crt,
+        Step::Volume,
+        Step::Vsync,
+        Step::FrameRate,
+        Step::ReducedMotion,
+        Step::Outline,
+        Step::ServeCountdown,
+        Step::TableTennisScore,
+        Step::Colors,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Step::Language => "LANGUAGE",
+            Step::Controls => "CONTROLS",
+            Step::Crt => "CRT EFFECT",
+            Step::Volume => "VOLUME",
+            Step::Vsync => "VSYNC",
+            Step::FrameRate => "FRAME RATE",
+            Step::ReducedMotion => "REDUCED MOTION",
+            Step::Outline => "BALL AND RACKET OUTLINE",
+            Step::ServeCountdown => "SERVE COUNTDOWN",
+            Step::TableTennisScore => "TABLE TENNIS SCORE",
+            Step::Colors => "COLOR VISION PREVIEW",
+        }
+    }
+}
+
+/// Walks the player through the settings the first time the game is
+/// started, then saves them and moves on to the first state of the game.
+pub struct Setup {
+    /// Index of the current step in [`Step::ALL`].
+    step: usize,
+    settings: Settings,
+    /// Color vision the colors are previewed with. It is not saved.
+    vision: Vision,
+    /// State started once the setup is done.
+    next: Option<Box<dyn GameState>>,
+    /// Inputs of the previous frame, so held keys only count once.
+    held: Vec<Input>,
+    title_label: Label,
+    step_label: Label,
+    value_label: Label,
+    change_label: HintLabel,
+    confirm_label: HintLabel,
+}
+
+impl Setup {
+    pub fn new(next: Box<dyn GameState>) -> Self {
+        Self {
+            step: 0,
+            settings: Settings::default(),
+            vision: Vision::Normal,
+            next: Some(next),
+            held: Vec::new(),
+            title_label: Label::new("SETUP", 150),
+            step_label: Label::new("", 40),
+            value_label: Label::new("", 60),
+            change_label: HintLabel::new(Action::Change, 30),
+            confirm_label: HintLabel::new(Action::Confirm, 30),
+        }
+    }
+
+    /// Changes the setting of the current step to the next or previous
+    /// value, and previews it.
+    fn change(&mut self, ctx: &mut Context, forward: bool) {
+        match Step::ALL[self.step] {
+            Step::Language => {
+                let pos = Language::ALL
+                    .iter()
+                    .position(|&language| language == self.settings.language)
+                    .unwrap_or(0);
+                let pos = cycle(pos, Language::ALL.len(), forward);
+                self.settings.language = Language::ALL[pos];
+            }
+            Step::Controls => {
+                self.settings.scheme = match self.settings.scheme {
+                    Scheme::Keyboard => Scheme::Touch,
+                    Scheme::Touch => Scheme::Keyboard,
+                };
+                ctx.scheme = self.settings.scheme;
+            }
+            Step::Crt => self.settings.crt = !self.settings.crt,
+            Step::Volume => {
+                let step = if forward { VOLUME_STEP } else { -VOLUME_STEP };
+                self.settings.volume = (self.settings.volume + step).clamp(0., 1.);
+                ctx.assets.sounds.mixer = self.settings.mixer();
+                let sounds = &ctx.assets.sounds;
+                sounds.play_on(Bus::Ui, sounds.racket.first());
+            }
+            Step::Vsync => self.settings.vsync = !self.settings.vsync,
+            Step::FrameRate => {
+                // No limit comes before the lowest rate.
+                let pos = self
+                    .settings
+                    .max_fps
+                    .and_then(|fps| FRAME_RATES.iter().position(|&rate| rate == fps))
+                    .map_or(0, |pos| pos + 1);
+                let pos = cycle(pos, FRAME_RATES.len() + 1, forward);
+                self.settings.max_fps = pos.checked_sub(1).map(|pos| FRAME_RATES[pos]);
+            }
+            Step::ReducedMotion => self.settings.reduced_motion = !self.settings.reduced_motion,
+            Step::Outline => self.settings.outline = !self.settings.outline,
+            Step::ServeCountdown => self.settings.serve_countdown = !self.settings.serve_countdown,
+            Step::TableTennisScore => {
+                self.settings.table_tennis_score = !self.settings.table_tennis_score;
+            }
+            Step::Colors => {
+                let pos = Vision::ALL
+                    .iter()
+                    .position(|&vision| vision == self.vision)
+                    .unwrap_or(0);
+                let pos = cycle(pos, Vision::ALL.len(), forward);
+                self.vision = Vision::ALL[pos];
+            }
+        }
+        ctx.settings = self.settings;
+    }
+
+    fn value_text(&self) -> String {
+        match Step::ALL[self.step] {
+            Step::Language => self.settings.language.to_string().to_uppercase(),
+            Step::Controls => match self.settings.scheme {
+                Scheme::Keyboard => "KEYBOARD".to_owned(),
+                Scheme::Touch => "TOUCH SCREEN".to_owned(),
+            },
+            Step::Crt => if self.settings.crt { "ON" } else { "OFF" }.to_owned(),
+            Step::Volume => format!("{:.0}%", self.settings.volume * 100.),
+            Step::Vsync => if self.settings.vsync { "ON" } else { "OFF" }.to_owned(),
+            Step::FrameRate => match self.settings.max_fps {
+                Some(fps) => format!("{fps} FPS"),
+                None => "UNLIMITED".to_owned(),
+            },
+            Step::ReducedMotion => if self.settings.reduced_motion {
+                "ON"
+            } else {
+                "OFF"
+            }
+            .to_owned(),
+            Step::Outline => if self.settings.outline { "ON" } else { "OFF" }.to_owned(),
+            Step::ServeCountdown => if self.settings.serve_countdown {
+                "ON"
+            } else {
+                "OFF"
+            }
+            .to_owned(),
+            Step::TableTennisScore => if self.settings.table_tennis_score {
+                "ON"
+            } else {
+                "OFF"
+            }
+            .to_owned(),
+            Step::Colors => self.vision.name().to_uppercase(),
+        }
+    }
+}
+
+impl GameState for Setup {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        self.settings = ctx.settings;
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let pressed = |input: Input| ctx.inputs.contains(&input) && !self.held.contains(&input);
+        let (mut up, mut down, mut confirm) = (
+            pressed(Input::Up(Side::Left)) || pressed(Input::Up(Side::Right)),
+            pressed(Input::Down(Side::Left)) || pressed(Input::Down(Side::Right)),
+            pressed(Input::Serve),
+        );
+        self.held.clone_from(&ctx.inputs);
+
+        // Taps on the top of the screen change the setting and the ones on
+        // the bottom confirm it.
+        if let Some(pos) = input::tapped() {
+            (up, down, confirm) = (false, pos.y < 0.5, pos.y >= 0.5);
+        }
+
+        if up || down {
+            self.change(ctx, down);
+        }
+        if confirm {
+            self.step += 1;
+            if self.step == Step::ALL.len() {
+                ctx.settings = self.settings;
+                ctx.autosave.save_now(self.settings);
+                return match self.next.take() {
+                    Some(next) => Transition::Replace(next),
+                    None => Transition::Pop,
+                };
+            }
+        }
+
+        let step = Step::ALL[self.step];
+        self.step_label.set_text(&format!(
+            "{} ({} OF {})",
+            step.title(),
+            self.step + 1,
+            Step::ALL.len()
+        ));
+        self.value_label
+            .set_text(&format!("< {} >", self.value_text()));
+        self.change_label.update(ctx.scheme);
+        self.confirm_label.update(ctx.scheme);
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let court = &ctx.runner.sim().court;
+        let center = court.rect().center();
+        self.title_label.draw_center(center.x, center.y - 150.);
+        self.step_label.draw_center(center.x, center.y - 40.);
+        self.value_label.draw_center(center.x, center.y + 30.);
+        if Step::ALL[self.step] == Step::Colors {
+            let spacing = (court.width / (PALETTE.len() + 1) as f32).min(90.);
+            vision::draw_palette(self.vision, center.x, center.y + 70., spacing);
+        }
+        self.change_label.draw_center(center.x, center.y + 120.);
+        self.confirm_label.draw_center(center.x, center.y + 160.);
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+}
+
+/// Returns the position after or before `pos` among `count` values, going
+/// around at the ends.
+pub fn cycle(pos: usize, count: usize, forward: bool) -> usize {
+    if forward {
+        (pos + 1) % count
+    } else {
+        (pos + count - 1) % count
+    }
+}
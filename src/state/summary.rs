@@ -0,0 +1,74 @@
+use macroquad::prelude::*;
+
+use crate::{
+    label::Label,
+    prompt::{Action, HintLabel},
+    state::{Context, GameState, Transition},
+    stats::{MatchRecord, Totals},
+};
+
+const SHADE_COLOR: Color = Color::new(0., 0., 0., 0.8);
+
+/// Sums up the matches of the session before the game quits.
+pub struct Summary {
+    title_label: Label,
+    labels: Vec<Label>,
+    quit_label: HintLabel,
+    /// Whether the key that opened the summary has been released.
+    released: bool,
+}
+
+impl Summary {
+    pub fn new(session: &[MatchRecord]) -> Self {
+        let totals = Totals::new(session);
+        let secs = totals.secs as u32;
+        let labels = [
+            format!("MATCHES {}", totals.matches),
+            format!("LEFT WON {} LOST {}", totals.left_wins, totals.right_wins),
+            format!("RIGHT WON {} LOST {}", totals.right_wins, totals.left_wins),
+            format!("BEST RALLY {}", totals.longest_rally),
+            format!("PLAY TIME {}:{:02}", secs / 60, secs % 60),
+        ]
+        .iter()
+        .map(|text| Label::new(text, 40))
+        .collect();
+
+        Self {
+            title_label: Label::new("SESSION", 150),
+            labels,
+            quit_label: HintLabel::new(Action::Quit, 40),
+            released: false,
+        }
+    }
+}
+
+impl GameState for Summary {
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        self.quit_label.update(ctx.scheme);
+        if ctx.inputs.is_empty() {
+            self.released = true;
+        } else if self.released {
+            return Transition::Quit;
+        }
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let court = &ctx.runner.sim().court;
+        let center = court.rect().center();
+        draw_rectangle(0., 0., court.width, court.height, SHADE_COLOR);
+        self.title_label.draw_center(center.x, center.y - 150.);
+        for (i, label) in self.labels.iter().enumerate() {
+            label.draw_center(center.x, center.y - 60. + i as f32 * 40.);
+        }
+        self.quit_label.draw_center(center.x, center.y + 180.);
+    }
+
+    fn is_idle(&self) -> bool {
+        true
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,237 @@
+use macroquad::{miniquad, prelude::*};
+use pong::{
+    entity::Entity,
+    sim::{Controls, Event, Lineup, Motion, Side},
+};
+
+use crate::{
+    draw,
+    input::{self, Input, Scheme},
+    label::Label,
+    prompt::{self, Action},
+    state::{gameplay::Gameplay, pause::Pause, Context, GameState, Transition},
+};
+
+/// Spin used during the tutorial when the physics of the game has none, so
+/// it can be taught.
+const TUTORIAL_SPIN: f32 = 0.4;
+
+/// Vertical component of the direction of the ball above which a return
+/// counts as angled.
+const ANGLED_DIR_Y: f32 = 0.6;
+
+/// Seconds the racket must be moved in each direction to learn moving.
+const MOVE_SECS: f32 = 0.5;
+
+/// What the player is being taught.
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    Move,
+    Serve,
+    Return,
+    Angle,
+    Spin,
+    Done,
+}
+
+impl Step {
+    const COUNT: usize = 5;
+
+    fn next(self) -> Self {
+        match self {
+            Step::Move => Step::Serve,
+            Step::Serve => Step::Return,
+            Step::Return => Step::Angle,
+            Step::Angle => Step::Spin,
+            Step::Spin | Step::Done => Step::Done,
+        }
+    }
+
+    fn number(self) -> usize {
+        self as usize + 1
+    }
+
+    /// Returns the instructions of the step for players using `scheme`.
+    fn prompt(self, scheme: Scheme) -> String {
+        match self {
+            Step::Move => prompt::prompt(Action::Move, scheme).unwrap_or_default(),
+            Step::Serve => prompt::prompt(Action::Serve, scheme).unwrap_or_default(),
+            Step::Return => "Return the ball to the other side".to_owned(),
+            Step::Angle => {
+                "Hit the ball with an edge of the racket to return it at an angle".to_owned()
+            }
+            Step::Spin => "Hit the ball while moving your racket to add spin".to_owned(),
+            Step::Done => format!(
+                "Well done! {}",
+                prompt::prompt(Action::Start, scheme).unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// Teaches the controls against a bot, moving on to the next lesson only once
+/// the player has done what was asked. Every ball waits for the player to
+/// serve it.
+pub struct Tutorial {
+    step: Step,
+    /// Seconds the racket was moved up and down during the first step.
+    moved: (f32, f32),
+    events: Vec<Event>,
+    /// Step, whether a serve was awaited and scheme the labels were written
+    /// for.
+    shown: Option<(Step, bool, Scheme)>,
+    step_label: Label,
+    prompt_label: Label,
+}
+
+impl Tutorial {
+    pub fn new() -> Self {
+        Self {
+            step: Step::Move,
+            moved: (0., 0.),
+            events: Vec::new(),
+            shown: None,
+            step_label: Label::new("", 30),
+            prompt_label: Label::new("", 30),
+        }
+    }
+
+    fn advance(&mut self) {
+        self.step = self.step.next();
+    }
+
+    /// Checks the return that has just been hit by the player against the
+    /// current lesson.
+    fn check_return(&mut self, ctx: &Context) {
+        let sim = ctx.runner.sim();
+        let Some(racket) = sim.racket(Side::Left) else {
+            return;
+        };
+        // The bounce may be a few steps old, so it is told apart from the
+        // ones of the bot by where the ball is heading.
+        let Some(ball) = sim.entities.iter().find_map(|entity| match entity {
+            Entity::Ball(ball) if ball.dir.0 > 0. && ball.pos.0 < sim.court.width * 0.5 => {
+                Some(ball)
+            }
+            _ => None,
+        }) else {
+            return;
+        };
+        let learned = match self.step {
+            Step::Return => true,
+            Step::Angle => ball.dir.1.abs() >= ANGLED_DIR_Y,
+            Step::Spin => racket.vel != 0.,
+            Step::Move | Step::Serve | Step::Done => false,
+        };
+        if learned {
+            self.advance();
+        }
+    }
+}
+
+impl GameState for Tutorial {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        let seed = (miniquad::date::now() * 1e6) as u64;
+        let court = ctx.court.clone();
+        let mut physics = ctx.physics;
+        if physics.spin == 0. {
+            physics.spin = TUTORIAL_SPIN;
+        }
+        ctx.runner.apply(move |sim| {
+            sim.court = court;
+            sim.physics = physics;
+            sim.lineup = Lineup::Single;
+            sim.manual_serve = true;
+            sim.reset(seed);
+        });
+        ctx.runner.reset_bots(seed);
+        ctx.runner.set_time_scale(1.);
+        ctx.runner.set_paused(false);
+        ctx.code = None;
+    }
+
+    fn on_exit(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        ctx.runner.apply(|sim| sim.manual_serve = false);
+    }
+
+    fn on_cover(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+    }
+
+    fn on_uncover(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(false);
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if ctx.inputs.contains(&Input::Pause) {
+            return Transition::Push(Box::new(Pause::new()));
+        }
+
+        if self.step == Step::Done {
+            if ctx.inputs.contains(&Input::Serve) {
+                return Transition::Replace(Box::new(Gameplay::new()));
+            }
+        } else if self.step == Step::Move {
+            match input::motion(&ctx.inputs, Side::Left) {
+                Motion::Up => self.moved.0 += ctx.dt,
+                Motion::Down => self.moved.1 += ctx.dt,
+                Motion::Stop => {}
+            }
+            if self.moved.0 >= MOVE_SECS && self.moved.1 >= MOVE_SECS {
+                self.advance();
+            }
+        } else if ctx.runner.sim().is_waiting_serve() && ctx.inputs.contains(&Input::Serve) {
+            ctx.runner.apply(|sim| sim.serve());
+            if self.step == Step::Serve {
+                self.advance();
+            }
+        }
+
+        ctx.runner.set_controls(Controls {
+            left: input::motion(&ctx.inputs, Side::Left),
+            ..Default::default()
+        });
+        ctx.runner.update(ctx.dt, &mut self.events);
+        for event in std::mem::take(&mut self.events) {
+            ctx.assets.sounds.play(event, ctx.runner.sim());
+            match event {
+                Event::RacketBounce => self.check_return(ctx),
+                // The tutorial never ends by points.
+                Event::Point(_) => ctx.runner.apply(|sim| sim.scores = (0, 0)),
+                Event::WallBounce
+                | Event::MatchPoint(_)
+                | Event::SetWon(_)
+                | Event::Winner(_)
+                | Event::TargetHit => {}
+            }
+        }
+
+        let shown = (self.step, ctx.runner.sim().is_waiting_serve(), ctx.scheme);
+        if self.shown != Some(shown) {
+            self.shown = Some(shown);
+            self.step_label.set_text(&match self.step {
+                Step::Done => "TUTORIAL COMPLETE".to_owned(),
+                step => format!("STEP {} OF {}", step.number(), Step::COUNT),
+            });
+            let prompt = match shown {
+                // Once learned, serving is still asked for after every point.
+                (Step::Return | Step::Angle | Step::Spin, true, _) => {
+                    Step::Serve.prompt(ctx.scheme)
+                }
+                (step, _, _) => step.prompt(ctx.scheme),
+            };
+            self.prompt_label.set_text(&prompt);
+        }
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let sim = ctx.runner.sim();
+        draw::draw_walls(&sim.court, sim.tick);
+        draw::draw_entities(&sim.entities, ctx.settings.outline);
+        let center_x = sim.court.width * 0.5;
+        self.step_label.draw_center(center_x, 30.);
+        self.prompt_label.draw_center(center_x, 70.);
+    }
+}
@@ -0,0 +1,164 @@
+use macroquad::{miniquad, prelude::*};
+use pong::sim::{Event, Lineup, Side};
+
+use crate::{
+    draw,
+    input::{Input, Scheme},
+    label::Label,
+    state::{gameplay, pause::Pause, Context, GameState, Transition},
+};
+
+/// How long the warmup lasts unless the players are ready earlier.
+const WARMUP_SECS: f32 = 30.;
+
+/// Lets the players rally without scoring before the match, as table tennis
+/// players do. It ends once every player is ready or after
+/// [`WARMUP_SECS`].
+pub struct Warmup {
+    /// State started once the warmup is over.
+    next: Option<Box<dyn GameState>>,
+    /// Seconds left.
+    left: f32,
+    /// Whether the left and right sides are played by people, who must
+    /// tell they are ready. Bots always are.
+    humans: (bool, bool),
+    ready: (bool, bool),
+    events: Vec<Event>,
+    /// Whole seconds left, as shown by the title.
+    secs: Option<u32>,
+    /// Readiness and scheme the ready labels were written for.
+    ready_shown: Option<((bool, bool), Scheme)>,
+    title_label: Label,
+    ready_labels: [Label; 2],
+}
+
+impl Warmup {
+    pub fn new(next: Box<dyn GameState>) -> Self {
+        Self {
+            next: Some(next),
+            left: WARMUP_SECS,
+            humans: (false, false),
+            ready: (false, false),
+            events: Vec::new(),
+            secs: None,
+            ready_shown: None,
+            title_label: Label::new("", 40),
+            ready_labels: [Label::new("", 24), Label::new("", 24)],
+        }
+    }
+
+    /// Returns whether matches of `lineup` are warmed up for, which are the
+    /// ones played point by point against someone.
+    pub fn is_supported(lineup: Lineup) -> bool {
+        matches!(lineup, Lineup::Single | Lineup::Coop)
+    }
+}
+
+impl GameState for Warmup {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        let seed = (miniquad::date::now() * 1e6) as u64;
+        let court = ctx.court.clone();
+        let physics = ctx.physics;
+        let lineup = ctx.lineup;
+        let rules = ctx.rules;
+        ctx.runner.apply(move |sim| {
+            sim.court = court;
+            sim.physics = physics;
+            sim.lineup = lineup;
+            sim.rules = rules;
+            sim.warmup = true;
+            sim.reset(seed);
+        });
+        ctx.runner.reset_bots(seed);
+        ctx.runner.set_time_scale(1.);
+        ctx.runner.set_paused(false);
+        // The right keys move the partner in co-op matches.
+        self.humans = (
+            ctx.bots.0.is_none(),
+            match lineup {
+                Lineup::Single => ctx.bots.1.is_none(),
+                Lineup::Coop => true,
+                Lineup::Goalkeeper | Lineup::Juggler | Lineup::Targets => false,
+            },
+        );
+        self.ready = (!self.humans.0, !self.humans.1);
+    }
+
+    fn on_exit(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+        ctx.runner.apply(|sim| sim.warmup = false);
+    }
+
+    fn on_cover(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(true);
+    }
+
+    fn on_uncover(&mut self, ctx: &mut Context) {
+        ctx.runner.set_paused(false);
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        if ctx.inputs.contains(&Input::Pause) {
+            return Transition::Push(Box::new(Pause::new()));
+        }
+        if ctx.inputs.contains(&Input::Ready(Side::Left)) {
+            self.ready.0 = true;
+        }
+        if ctx.inputs.contains(&Input::Ready(Side::Right)) {
+            self.ready.1 = true;
+        }
+        self.left -= ctx.dt;
+        if self.left <= 0. || self.ready == (true, true) {
+            return self
+                .next
+                .take()
+                .map_or(Transition::Pop, Transition::Replace);
+        }
+
+        ctx.runner.set_controls(gameplay::controls(ctx));
+        ctx.runner.update(ctx.dt, &mut self.events);
+        for event in self.events.drain(..) {
+            ctx.assets.sounds.play(event, ctx.runner.sim());
+        }
+
+        let secs = self.left.ceil() as u32;
+        if self.secs != Some(secs) {
+            self.secs = Some(secs);
+            self.title_label
+                .set_text(&format!("WARMUP {}:{:02}", secs / 60, secs % 60));
+        }
+        if self.ready_shown == Some((self.ready, ctx.scheme)) {
+            return Transition::None;
+        }
+        self.ready_shown = Some((self.ready, ctx.scheme));
+        let devices = [ctx.devices.left, ctx.devices.right];
+        let slots = [
+            (Side::Left, self.humans.0, self.ready.0),
+            (Side::Right, self.humans.1, self.ready.1),
+        ];
+        for (i, (side, human, ready)) in slots.into_iter().enumerate() {
+            let text = match (human, ready, ctx.scheme) {
+                (false, _, _) => String::new(),
+                (true, true, _) => format!("{side} READY"),
+                // Touches only move the rackets, so the warmup runs its
+                // course.
+                (true, false, Scheme::Touch) => String::new(),
+                (true, false, Scheme::Keyboard) => {
+                    format!("{side}: PRESS {} WHEN READY", devices[i].ready_name())
+                }
+            };
+            self.ready_labels[i].set_text(&text);
+        }
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let sim = ctx.runner.sim();
+        draw::draw_walls(&sim.court, sim.tick);
+        draw::draw_entities(&sim.entities, ctx.settings.outline);
+        self.title_label.draw_center(sim.court.width * 0.5, 30.);
+        for (label, x) in self.ready_labels.iter().zip([0.25, 0.75]) {
+            label.draw_center(sim.court.width * x, 70.);
+        }
+    }
+}
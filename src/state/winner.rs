@@ -0,0 +1,418 @@
+use macroquad::prelude::*;
+
+use pong::{
+    entity::Entity,
+    sim::{Lineup, Side, Simulation},
+};
+
+use crate::{
+    draw::{self, Draw, HEATMAP_BALL_COLOR, HEATMAP_POINT_COLOR, LEFT_COLOR, RIGHT_COLOR},
+    input::Input,
+    label::Label,
+    particles::Particles,
+    prompt::{Action, HintLabel},
+    replay::ReplayBuffer,
+    settings::Effect,
+    state::{
+        browser::ReplayBrowser, gameplay::Gameplay, main_menu::MainMenu, next_up::NextUp, Context,
+        GameState, Transition,
+    },
+    stats::{Heatmap, Leaderboard, MatchRecord, MatchStats, SUGGESTION_STREAK},
+    storage,
+};
+
+/// Durations of the steps of the winner sequence.
+const FREEZE_SECS: f32 = 0.8;
+const ZOOM_SECS: f32 = 1.;
+/// Minimum time the stats are shown before a new match can be started.
+const STATS_SECS: f32 = 1.;
+
+const ZOOM: f32 = 2.;
+
+const TIMELINE_WIDTH: f32 = 600.;
+const TIMELINE_BAR: (f32, f32) = (6., 36.);
+
+const CONFETTI_COUNT: usize = 150;
+
+/// Number of target practice runs shown after a run.
+const LEADERBOARD_SHOWN: usize = 5;
+
+/// Celebrates the winner of the match: the final hit is frozen, the camera
+/// zooms on the winning side while confetti flies and then the stats of the
+/// match are shown until a key is pressed. The stats can be exported from
+/// there.
+pub struct Winner {
+    side: Side,
+    elapsed: f32,
+    /// Frame shown frozen, usually the final hit.
+    frame: Option<Simulation>,
+    particles: Particles,
+    winner_label: Label,
+    stats_labels: Vec<Label>,
+    stats: MatchStats,
+    /// Speed of the ball at every point, written next to its bar of the
+    /// timeline.
+    speed_labels: Vec<Label>,
+    show_heatmap: bool,
+    heatmap_label: HintLabel,
+    export_label: HintLabel,
+    replays_label: HintLabel,
+    menu_label: HintLabel,
+    /// Bot suggested to play against next, when the player keeps beating
+    /// or losing against the current one.
+    suggestion: Option<(Side, &'static str)>,
+    suggestion_label: Label,
+    try_suggested_label: HintLabel,
+    /// Result of the last export.
+    exported_label: Option<Label>,
+    play_again_label: HintLabel,
+    /// Code of the match, to play it again or share it.
+    code_label: Label,
+}
+
+impl Winner {
+    pub fn new(side: Side, replay: ReplayBuffer, stats: MatchStats) -> Self {
+        let frame = replay
+            .last_hit()
+            .or_else(|| replay.latest())
+            .map(|frame| frame.sim.clone());
+
+        let secs = stats.secs as u32;
+        let stats_labels = [
+            format!("SCORE {} - {}", stats.scores.0, stats.scores.1),
+            format!("TIME {}:{:02}", secs / 60, secs % 60),
+            format!("HITS {}", stats.hits),
+            format!("LONGEST RALLY {}", stats.longest_rally),
+        ]
+        .iter()
+        .map(|text| Label::new(text, 40))
+        .collect();
+        let speed_labels = stats
+            .points
+            .iter()
+            .map(|point| Label::new(&format!("{:.0}", point.ball_speed), 16))
+            .collect();
+
+        Self {
+            side,
+            elapsed: 0.,
+            frame,
+            particles: Particles::default(),
+            winner_label: Label::new(&format!("{side} WON!"), 150),
+            stats_labels,
+            stats,
+            speed_labels,
+            show_heatmap: false,
+            heatmap_label: HintLabel::new(Action::Heatmap, 24),
+            export_label: HintLabel::new(Action::Export, 24),
+            replays_label: HintLabel::new(Action::Replays, 24),
+            menu_label: HintLabel::new(Action::MainMenu, 24),
+            suggestion: None,
+            suggestion_label: Label::new("", 24),
+            try_suggested_label: HintLabel::new(Action::TrySuggested, 24),
+            exported_label: None,
+            play_again_label: HintLabel::new(Action::PlayAgain, 40),
+            code_label: Label::new("", 24),
+        }
+    }
+
+    /// Draws a strip centered at `center` with one bar per point. Bars go up
+    /// for the left player and down for the right one, and are taller the
+    /// longer the rally. The speed of the ball is written next to them.
+    fn draw_timeline(&self, center: Vec2) {
+        let width = TIMELINE_WIDTH;
+        draw_line(
+            center.x - width * 0.5,
+            center.y,
+            center.x + width * 0.5,
+            center.y,
+            2.,
+            WHITE,
+        );
+
+        let count = self.stats.points.len().max(1) as f32;
+        let slot = (width / count).min(60.);
+        let start = center.x - slot * count * 0.5;
+        let max_rally = self
+            .stats
+            .points
+            .iter()
+            .map(|point| point.rally)
+            .max()
+            .unwrap_or(0);
+        for (i, (point, label)) in self.stats.points.iter().zip(&self.speed_labels).enumerate() {
+            let (min_bar, max_bar) = TIMELINE_BAR;
+            let bar = min_bar + (max_bar - min_bar) * point.rally as f32 / max_rally.max(1) as f32;
+            let x = start + slot * i as f32 + slot * 0.2;
+            let (y, color, text_y) = match point.side {
+                Side::Left => (center.y - bar, LEFT_COLOR, center.y - bar - 10.),
+                Side::Right => (center.y, RIGHT_COLOR, center.y + bar + 8.),
+            };
+            draw_rectangle(x, y, slot * 0.6, bar, color);
+            label.draw_center(x + slot * 0.3, text_y);
+        }
+    }
+
+    /// Writes `contents`, the stats of this and the previous matches or the
+    /// events of this one, to a file called `name`.
+    fn export(&mut self, name: &str, contents: &str) {
+        let text = match storage::export(name, contents) {
+            Ok(location) => format!("Stats exported to {location}"),
+            Err(err) => {
+                eprintln!("error: export stats: {err}");
+                "Could not export the stats".to_owned()
+            }
+        };
+        self.exported_label = Some(Label::new(&text, 24));
+    }
+
+    fn stats_shown(&self) -> bool {
+        self.elapsed >= FREEZE_SECS + ZOOM_SECS
+    }
+
+    /// Returns the part of the court shown while zooming on the winner.
+    fn view(&self, court: Rect) -> Rect {
+        let t = ((self.elapsed - FREEZE_SECS) / ZOOM_SECS).clamp(0., 1.);
+        let scale = 1. + (ZOOM - 1.) * t;
+        let size = court.size() / scale;
+        let focus_x = match self.side {
+            Side::Left => court.w * 0.25,
+            Side::Right => court.w * 0.75,
+        };
+        let focus = vec2(court.w * 0.5, court.h * 0.5).lerp(vec2(focus_x, court.h * 0.5), t);
+        let pos = (focus - size * 0.5).clamp(Vec2::ZERO, court.size() - size);
+        Rect::new(pos.x, pos.y, size.x, size.y)
+    }
+}
+
+impl GameState for Winner {
+    fn on_enter(&mut self, ctx: &mut Context) {
+        if let Some(code) = &ctx.code {
+            self.code_label.set_text(&format!("CODE {code}"));
+        }
+        // Goalkeeper runs always end with the ball machine winning, so only
+        // the saves are worth celebrating and they are not kept as matches.
+        if ctx.lineup == Lineup::Goalkeeper {
+            self.winner_label = Label::new(&format!("{} SAVES", self.stats.scores.0), 150);
+            return;
+        }
+        // Target practice runs are ranked against the best previous ones
+        // instead, unless they were rewound.
+        if ctx.lineup == Lineup::Targets {
+            self.winner_label = Label::new(&format!("{} TARGETS", self.stats.scores.0), 150);
+            let mut leaderboard = Leaderboard::load();
+            let rank = if self.stats.rewound {
+                None
+            } else {
+                leaderboard.push(self.stats.scores)
+            };
+            self.stats_labels = leaderboard
+                .scores
+                .iter()
+                .take(LEADERBOARD_SHOWN)
+                .enumerate()
+                .map(|(i, score)| {
+                    let marker = if rank == Some(i) { " <" } else { "" };
+                    let text = format!(
+                        "{}. {} TARGETS - {} MISSED{marker}",
+                        i + 1,
+                        score.hits,
+                        score.misses
+                    );
+                    Label::new(&text, 32)
+                })
+                .collect();
+            return;
+        }
+        // Only matches of the players against a single bot say how they
+        // fare against it.
+        let bot = match &ctx.bots {
+            (Some(name), None) => Some((Side::Left, name.as_str())),
+            (None, Some(name)) => Some((Side::Right, name.as_str())),
+            _ => None,
+        };
+        ctx.history
+            .push(MatchRecord::new(self.side, &self.stats, bot));
+        let Some((side, name)) = bot else {
+            return;
+        };
+        if let Some((next, won)) = ctx.history.suggestion(name) {
+            let text = if won {
+                format!("YOU'VE WON {SUGGESTION_STREAK} STRAIGHT AGAINST {name} - TRY {next}?")
+            } else {
+                format!("YOU'VE LOST {SUGGESTION_STREAK} STRAIGHT AGAINST {name} - TRY {next}?")
+            };
+            self.suggestion_label.set_text(&text.to_uppercase());
+            self.suggestion = Some((side, next));
+        }
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Transition {
+        let was_zooming = self.elapsed >= FREEZE_SECS;
+        self.elapsed += ctx.dt;
+        self.heatmap_label.update(ctx.scheme);
+        self.export_label.update(ctx.scheme);
+        self.replays_label.update(ctx.scheme);
+        self.menu_label.update(ctx.scheme);
+        self.try_suggested_label.update(ctx.scheme);
+        self.play_again_label.update(ctx.scheme);
+        if self.stats_shown() {
+            if ctx.inputs.contains(&Input::Heatmap) {
+                self.show_heatmap = !self.show_heatmap;
+            }
+            if ctx.inputs.contains(&Input::ExportJson) {
+                self.export("pong-stats.json", &ctx.history.to_json(&self.stats));
+            }
+            if ctx.inputs.contains(&Input::ExportCsv) {
+                self.export("pong-stats.csv", &ctx.history.to_csv());
+            }
+            if ctx.inputs.contains(&Input::ExportEvents) {
+                let events = self.stats.timeline.to_json_lines();
+                self.export("pong-events.jsonl", &events);
+            }
+            if ctx.inputs.contains(&Input::Replays) {
+                return Transition::Push(Box::new(ReplayBrowser::new()));
+            }
+            if ctx.inputs.contains(&Input::MainMenu) {
+                return Transition::Reset(Box::new(MainMenu::new()));
+            }
+            if let Some((side, name)) = self.suggestion {
+                if ctx.inputs.contains(&Input::Cycle) {
+                    match side {
+                        Side::Left => ctx.bots.0 = Some(name.to_owned()),
+                        Side::Right => ctx.bots.1 = Some(name.to_owned()),
+                    }
+                    return Transition::Replace(Box::new(Gameplay::new()));
+                }
+            }
+        }
+
+        // Keys skip the sequence and, once the stats have been read, start a
+        // new match. The keys held when the match ended are ignored.
+        let skip = ctx.inputs.iter().any(|input| {
+            !matches!(
+                input,
+                Input::Heatmap
+                    | Input::ExportJson
+                    | Input::ExportCsv
+                    | Input::ExportEvents
+                    | Input::Replays
+                    | Input::MainMenu
+                    | Input::Cycle
+            )
+        });
+        if was_zooming && skip {
+            if self.elapsed >= FREEZE_SECS + ZOOM_SECS + STATS_SECS {
+                // In party mode the next players are told first.
+                if ctx.party.is_some() {
+                    return Transition::Replace(Box::new(NextUp::new()));
+                }
+                return Transition::Replace(Box::new(Gameplay::new()));
+            }
+            self.elapsed = self.elapsed.max(FREEZE_SECS + ZOOM_SECS);
+        }
+
+        let court = &ctx.runner.sim().court;
+        if !was_zooming && self.elapsed >= FREEZE_SECS && ctx.settings.allows(Effect::Confetti) {
+            let (x, dir) = match self.side {
+                Side::Left => (0., vec2(1., -1.)),
+                Side::Right => (court.width, vec2(-1., -1.)),
+            };
+            self.particles
+                .confetti(vec2(x, court.height), dir, CONFETTI_COUNT);
+        }
+        self.particles.update(ctx.dt);
+
+        Transition::None
+    }
+
+    fn draw(&self, ctx: &Context) {
+        let court = ctx.runner.sim().court.rect();
+        let center = court.center();
+
+        if !self.stats_shown() {
+            if ctx.settings.allows(Effect::Zoom) {
+                set_camera(&ctx.canvas.zoomed(self.view(court)));
+            }
+            if let Some(sim) = &self.frame {
+                draw::draw_entities(&sim.entities, ctx.settings.outline);
+            }
+            set_camera(&ctx.canvas.camera);
+            self.particles.draw();
+            return;
+        }
+
+        // The rackets and the ball stay in the background, dimmed.
+        for entity in self.frame.iter().flat_map(|sim| &sim.entities) {
+            if !matches!(entity, Entity::Ball(_)) {
+                entity.draw();
+            }
+        }
+        draw_rectangle(
+            court.x,
+            court.y,
+            court.w,
+            court.h,
+            Color::new(0., 0., 0., 0.5),
+        );
+
+        if self.show_heatmap {
+            draw_heatmap(&self.stats.ball_heatmap, court, HEATMAP_BALL_COLOR);
+            draw_heatmap(&self.stats.point_heatmap, court, HEATMAP_POINT_COLOR);
+            self.heatmap_label.draw_center(center.x, court.h - 20.);
+            self.particles.draw();
+            return;
+        }
+
+        self.winner_label.draw_center(center.x, center.y - 150.);
+        self.code_label.draw_center(center.x, 30.);
+        for (i, label) in self.stats_labels.iter().enumerate() {
+            label.draw_center(center.x, center.y - 60. + i as f32 * 40.);
+        }
+        self.draw_timeline(vec2(center.x, center.y + 130.));
+        if self.elapsed >= FREEZE_SECS + ZOOM_SECS + STATS_SECS {
+            self.play_again_label.draw_center(center.x, center.y + 200.);
+        }
+        if self.suggestion.is_some() {
+            self.suggestion_label.draw_center(center.x, 60.);
+            self.try_suggested_label.draw_center(center.x, 85.);
+        }
+        self.menu_label.draw_center(center.x, court.h - 110.);
+        self.replays_label.draw_center(center.x, court.h - 80.);
+        self.heatmap_label.draw_center(center.x, court.h - 50.);
+        match &self.exported_label {
+            Some(label) => label.draw_center(center.x, court.h - 20.),
+            None => self.export_label.draw_center(center.x, court.h - 20.),
+        }
+        self.particles.draw();
+    }
+
+    fn is_idle(&self) -> bool {
+        self.elapsed >= FREEZE_SECS + ZOOM_SECS + STATS_SECS && self.particles.is_empty()
+    }
+}
+
+/// Draws `heatmap` over `court`, with more opaque cells where it counted
+/// more.
+fn draw_heatmap(heatmap: &Heatmap, court: Rect, color: Color) {
+    let max = heatmap.max();
+    if max == 0 {
+        return;
+    }
+    let cell = vec2(court.w / heatmap.cols as f32, court.h / heatmap.rows as f32);
+    for (i, &count) in heatmap.cells.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+        let (col, row) = (i % heatmap.cols, i / heatmap.cols);
+        let mut color = color;
+        color.a *= count as f32 / max as f32;
+        draw_rectangle(
+            court.x + col as f32 * cell.x,
+            court.y + row as f32 * cell.y,
+            cell.x,
+            cell.y,
+            color,
+        );
+    }
+}
@@ -0,0 +1,524 @@
+//! Statistics of a match and of every match played before.
+use std::fmt::Write;
+
+use macroquad::{math::Vec2, miniquad};
+use pong::{
+    bot,
+    entity::Entity,
+    sim::{Event, Side, Simulation, TICK_RATE},
+};
+
+use crate::{
+    storage::{self, Dir, Format},
+    timeline::{Entry, Kind, Timeline},
+};
+
+/// Coarse grid counting how often something happened in each part of the
+/// court.
+#[derive(Clone)]
+pub struct Heatmap {
+    pub cols: usize,
+    pub rows: usize,
+    pub cells: Vec<u32>,
+}
+
+impl Heatmap {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            cols,
+            rows,
+            cells: vec![0; cols * rows],
+        }
+    }
+
+    /// Counts `pos` on a court of size `court`.
+    pub fn add(&mut self, pos: Vec2, court: Vec2) {
+        let col = (pos.x / court.x * self.cols as f32) as usize;
+        let row = (pos.y / court.y * self.rows as f32) as usize;
+        let (col, row) = (col.min(self.cols - 1), row.min(self.rows - 1));
+        self.cells[row * self.cols + col] += 1;
+    }
+
+    pub fn max(&self) -> u32 {
+        self.cells.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// How a point was won.
+#[derive(Clone, Copy)]
+pub struct Point {
+    pub side: Side,
+    /// Hits during the point.
+    pub rally: u32,
+    /// Speed of the ball when it went out.
+    pub ball_speed: f32,
+}
+
+const HEATMAP_SIZE: (usize, usize) = (16, 12);
+
+#[derive(Clone)]
+pub struct MatchStats {
+    pub scores: (i32, i32),
+    /// Duration of the match in seconds of game time.
+    pub secs: f32,
+    /// Number of times the ball was hit by a racket.
+    pub hits: u32,
+    /// Most hits during a single point.
+    pub longest_rally: u32,
+    /// Every point in the order they were played.
+    pub points: Vec<Point>,
+    /// Where the ball travelled.
+    pub ball_heatmap: Heatmap,
+    /// Where the ball was when points were conceded.
+    pub point_heatmap: Heatmap,
+    /// Whether the match was rewound, which keeps it off the leaderboard.
+    pub rewound: bool,
+    /// Everything that happened during the match, which the stats are
+    /// worked out from.
+    pub timeline: Timeline,
+    rally: u32,
+}
+
+impl Default for MatchStats {
+    fn default() -> Self {
+        Self {
+            scores: (0, 0),
+            secs: 0.,
+            hits: 0,
+            longest_rally: 0,
+            points: Vec::new(),
+            ball_heatmap: Heatmap::new(HEATMAP_SIZE.0, HEATMAP_SIZE.1),
+            point_heatmap: Heatmap::new(HEATMAP_SIZE.0, HEATMAP_SIZE.1),
+            rewound: false,
+            timeline: Timeline::default(),
+            rally: 0,
+        }
+    }
+}
+
+impl MatchStats {
+    /// Accounts for `event`, which has just happened in `sim`, and returns
+    /// the entry logged for it in the timeline, if any.
+    pub fn record(&mut self, event: Event, sim: &Simulation) -> Option<Entry> {
+        let entry = self.timeline.record(event, sim);
+        if let Some(entry) = entry {
+            match entry.kind {
+                Kind::RacketBounce(_) => {
+                    self.hits += 1;
+                    self.rally += 1;
+                    self.longest_rally = self.longest_rally.max(self.rally);
+                }
+                Kind::Point { side, .. } => {
+                    self.points.push(Point {
+                        side,
+                        rally: self.rally,
+                        ball_speed: entry.speed,
+                    });
+                    let court = sim.court.rect().size();
+                    self.point_heatmap.add(entry.pos, court);
+                    self.rally = 0;
+                }
+                Kind::Serve { .. }
+                | Kind::WallBounce
+                | Kind::TargetHit
+                | Kind::SetWon(_)
+                | Kind::Winner(_) => {}
+            }
+        }
+        self.scores = sim.scores;
+        self.secs = sim.tick as f32 / TICK_RATE as f32;
+        entry
+    }
+
+    /// Returns the number of hits of the point being played.
+    pub fn rally(&self) -> u32 {
+        self.rally
+    }
+
+    /// Keeps track of the state of `sim` after the events of a frame have
+    /// been recorded.
+    pub fn observe(&mut self, sim: &Simulation) {
+        self.timeline.observe(sim);
+        for entity in &sim.entities {
+            if let Entity::Ball(ball) = entity {
+                self.ball_heatmap
+                    .add(ball.center(), sim.court.rect().size());
+            }
+        }
+    }
+}
+
+/// Entry where the finished matches are kept.
+const HISTORY: Format = Format {
+    dir: Dir::Saves,
+    name: "history.csv",
+    migrations: &[storage::unversioned, add_bot_columns],
+};
+
+const CSV_HEADER: &str =
+    "timestamp,secs,left_score,right_score,winner,hits,longest_rally,bot_side,bot";
+
+/// Migrates a history saved before the bots were recorded, leaving their
+/// columns empty.
+fn add_bot_columns(contents: &str) -> String {
+    let mut lines = contents.lines();
+    let mut migrated = format!("{CSV_HEADER}\n");
+    lines.next();
+    for line in lines {
+        writeln!(migrated, "{line},,").expect("format record");
+    }
+    migrated
+}
+
+/// Consecutive wins or losses against a bot after which a harder or easier
+/// one is suggested.
+pub const SUGGESTION_STREAK: usize = 5;
+
+/// Summary of a finished match.
+#[derive(Clone, Copy)]
+pub struct MatchRecord {
+    /// Seconds since the Unix epoch when the match finished.
+    pub timestamp: u64,
+    pub secs: f32,
+    pub scores: (i32, i32),
+    pub winner: Side,
+    pub hits: u32,
+    pub longest_rally: u32,
+    /// Side and name of the bot the player played against, if the match was
+    /// against one.
+    pub bot: Option<(Side, &'static str)>,
+}
+
+impl MatchRecord {
+    /// Summarizes a match won by `winner` that has just finished, played
+    /// against `bot` if it is set.
+    pub fn new(winner: Side, stats: &MatchStats, bot: Option<(Side, &str)>) -> Self {
+        Self {
+            timestamp: miniquad::date::now() as u64,
+            secs: stats.secs,
+            scores: stats.scores,
+            winner,
+            hits: stats.hits,
+            longest_rally: stats.longest_rally,
+            bot: bot.and_then(|(side, name)| Some((side, bot_name(name)?))),
+        }
+    }
+
+    /// Returns whether the player beat the bot of the match, if it was
+    /// played against one.
+    pub fn beat_bot(self) -> Option<bool> {
+        self.bot.map(|(side, _)| self.winner != side)
+    }
+
+    fn to_csv(self) -> String {
+        let (bot_side, bot) = match self.bot {
+            Some((side, name)) => (side_name(side), name),
+            None => ("", ""),
+        };
+        format!(
+            "{},{:.2},{},{},{},{},{},{bot_side},{bot}",
+            self.timestamp,
+            self.secs,
+            self.scores.0,
+            self.scores.1,
+            side_name(self.winner),
+            self.hits,
+            self.longest_rally
+        )
+    }
+
+    fn parse_csv(line: &str) -> Option<Self> {
+        let mut fields = line.split(',');
+        let mut next = || fields.next();
+        let record = Self {
+            timestamp: next()?.parse().ok()?,
+            secs: next()?.parse().ok()?,
+            scores: (next()?.parse().ok()?, next()?.parse().ok()?),
+            winner: parse_side(next()?)?,
+            hits: next()?.parse().ok()?,
+            longest_rally: next()?.parse().ok()?,
+            bot: match (next()?, next()?) {
+                ("", "") => None,
+                (side, name) => Some((parse_side(side)?, bot_name(name)?)),
+            },
+        };
+        next().is_none().then_some(record)
+    }
+
+    fn to_json(self) -> String {
+        let bot = match self.bot {
+            Some((side, name)) => format!(r#"{{"side": "{}", "name": "{name}"}}"#, side_name(side)),
+            None => "null".to_owned(),
+        };
+        format!(
+            r#"{{"timestamp": {}, "secs": {:.2}, "scores": [{}, {}], "winner": "{}", "hits": {}, "longest_rally": {}, "bot": {bot}}}"#,
+            self.timestamp,
+            self.secs,
+            self.scores.0,
+            self.scores.1,
+            side_name(self.winner),
+            self.hits,
+            self.longest_rally
+        )
+    }
+}
+
+/// Every match finished by the player, kept between runs.
+#[derive(Default)]
+pub struct History {
+    pub matches: Vec<MatchRecord>,
+    /// Number of matches finished before the game was started.
+    session_start: usize,
+}
+
+impl History {
+    /// Loads the saved matches. Lines that cannot be parsed are skipped.
+    pub fn load() -> Self {
+        let matches = HISTORY
+            .load()
+            .unwrap_or_default()
+            .lines()
+            .skip(1)
+            .filter_map(MatchRecord::parse_csv)
+            .collect::<Vec<_>>();
+        Self {
+            session_start: matches.len(),
+            matches,
+        }
+    }
+
+    /// Returns the matches finished since the game was started.
+    pub fn session(&self) -> &[MatchRecord] {
+        &self.matches[self.session_start..]
+    }
+
+    /// Appends `record` and saves the history.
+    pub fn push(&mut self, record: MatchRecord) {
+        self.matches.push(record);
+        let mut csv = format!("{CSV_HEADER}\n");
+        for record in &self.matches {
+            writeln!(csv, "{}", record.to_csv()).expect("format record");
+        }
+        if let Err(err) = HISTORY.save(&csv) {
+            eprintln!("error: save history: {err}");
+        }
+    }
+
+    /// Returns a harder bot to play against if the player has beaten `bot`
+    /// in the last [`SUGGESTION_STREAK`] matches against it, or an easier one
+    /// if they lost all of them, together with whether they won.
+    pub fn suggestion(&self, bot: &str) -> Option<(&'static str, bool)> {
+        let mut results = self
+            .matches
+            .iter()
+            .rev()
+            .filter(|record| record.bot.is_some_and(|(_, name)| name == bot))
+            .filter_map(|record| record.beat_bot());
+        let won = results.next()?;
+        let streak = 1 + results.take_while(|&result| result == won).count();
+        if streak < SUGGESTION_STREAK {
+            return None;
+        }
+        let pos = bot::DIFFICULTY.iter().position(|&name| name == bot)?;
+        let pos = if won { pos + 1 } else { pos.checked_sub(1)? };
+        bot::DIFFICULTY.get(pos).map(|&name| (name, won))
+    }
+
+    /// Returns one row per match followed by a row with the lifetime totals.
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{CSV_HEADER}\n");
+        for record in &self.matches {
+            writeln!(csv, "{}", record.to_csv()).expect("format record");
+        }
+        let totals = Totals::new(&self.matches);
+        writeln!(
+            csv,
+            "total,{:.2},{},{},,{},{},,",
+            totals.secs, totals.left_wins, totals.right_wins, totals.hits, totals.longest_rally
+        )
+        .expect("format totals");
+        csv
+    }
+
+    /// Returns the lifetime totals, every match and the details of
+    /// `current`, the match that has just finished.
+    pub fn to_json(&self, current: &MatchStats) -> String {
+        let totals = Totals::new(&self.matches);
+        let mut json = String::from("{\n");
+        writeln!(
+            json,
+            r#"  "lifetime": {{"matches": {}, "left_wins": {}, "right_wins": {}, "secs": {:.2}, "hits": {}, "longest_rally": {}}},"#,
+            totals.matches,
+            totals.left_wins,
+            totals.right_wins,
+            totals.secs,
+            totals.hits,
+            totals.longest_rally
+        )
+        .expect("format totals");
+
+        let points: Vec<_> = current
+            .points
+            .iter()
+            .map(|point| {
+                format!(
+                    r#"{{"side": "{}", "rally": {}, "ball_speed": {:.1}}}"#,
+                    side_name(point.side),
+                    point.rally,
+                    point.ball_speed
+                )
+            })
+            .collect();
+        writeln!(
+            json,
+            r#"  "match": {{"secs": {:.2}, "scores": [{}, {}], "hits": {}, "longest_rally": {}, "points": [{}]}},"#,
+            current.secs,
+            current.scores.0,
+            current.scores.1,
+            current.hits,
+            current.longest_rally,
+            points.join(", ")
+        )
+        .expect("format match");
+
+        let matches: Vec<_> = self
+            .matches
+            .iter()
+            .map(|record| format!("    {}", record.to_json()))
+            .collect();
+        writeln!(json, "  \"history\": [\n{}\n  ]", matches.join(",\n")).expect("format history");
+        json.push_str("}\n");
+        json
+    }
+}
+
+/// Statistics of a list of matches.
+#[derive(Default)]
+pub struct Totals {
+    pub matches: u32,
+    pub left_wins: u32,
+    pub right_wins: u32,
+    pub secs: f32,
+    pub hits: u32,
+    pub longest_rally: u32,
+}
+
+impl Totals {
+    pub fn new(records: &[MatchRecord]) -> Self {
+        let mut totals = Self::default();
+        for record in records {
+            totals.matches += 1;
+            match record.winner {
+                Side::Left => totals.left_wins += 1,
+                Side::Right => totals.right_wins += 1,
+            }
+            totals.secs += record.secs;
+            totals.hits += record.hits;
+            totals.longest_rally = totals.longest_rally.max(record.longest_rally);
+        }
+        totals
+    }
+}
+
+fn parse_side(name: &str) -> Option<Side> {
+    match name {
+        "left" => Some(Side::Left),
+        "right" => Some(Side::Right),
+        _ => None,
+    }
+}
+
+/// Returns the name of the built-in bot called `name`, which lives as long
+/// as the game.
+fn bot_name(name: &str) -> Option<&'static str> {
+    bot::BOT_NAMES.iter().copied().find(|&bot| bot == name)
+}
+
+pub fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Left => "left",
+        Side::Right => "right",
+    }
+}
+
+/// Entry where the best target practice runs are kept.
+const LEADERBOARD: Format = Format {
+    dir: Dir::Saves,
+    name: "targets.txt",
+    migrations: &[storage::unversioned],
+};
+
+/// Number of runs kept in the leaderboard.
+const LEADERBOARD_SIZE: usize = 10;
+
+/// Result of a target practice run.
+#[derive(Clone, Copy, PartialEq)]
+pub struct TargetsScore {
+    /// Seconds since the Unix epoch when the run finished.
+    pub timestamp: u64,
+    pub hits: i32,
+    pub misses: i32,
+}
+
+/// Best target practice runs, kept between runs of the game. More targets
+/// hit rank higher, and fewer missed balls break ties.
+#[derive(Default)]
+pub struct Leaderboard {
+    pub scores: Vec<TargetsScore>,
+}
+
+impl Leaderboard {
+    /// Loads the saved runs. Lines that cannot be parsed are skipped.
+    pub fn load() -> Self {
+        let scores = LEADERBOARD
+            .load()
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(' ');
+                let mut next = || fields.next();
+                let score = TargetsScore {
+                    timestamp: next()?.parse().ok()?,
+                    hits: next()?.parse().ok()?,
+                    misses: next()?.parse().ok()?,
+                };
+                next().is_none().then_some(score)
+            })
+            .collect();
+        Self { scores }
+    }
+
+    /// Ranks a run that has just finished with `scores` and saves the
+    /// leaderboard. Returns the position of the run, if it made it.
+    pub fn push(&mut self, scores: (i32, i32)) -> Option<usize> {
+        let score = TargetsScore {
+            timestamp: miniquad::date::now() as u64,
+            hits: scores.0,
+            misses: scores.1,
+        };
+        let pos = self
+            .scores
+            .iter()
+            .position(|other| (score.hits, -score.misses) > (other.hits, -other.misses))
+            .unwrap_or(self.scores.len());
+        if pos >= LEADERBOARD_SIZE {
+            return None;
+        }
+        self.scores.insert(pos, score);
+        self.scores.truncate(LEADERBOARD_SIZE);
+
+        let mut contents = String::new();
+        for score in &self.scores {
+            writeln!(
+                contents,
+                "{} {} {}",
+                score.timestamp, score.hits, score.misses
+            )
+            .expect("format score");
+        }
+        if let Err(err) = LEADERBOARD.save(&contents) {
+            eprintln!("error: save leaderboard: {err}");
+        }
+        Some(pos)
+    }
+}
@@ -0,0 +1,454 @@
+//! Data kept between runs of the game.
+//!
+//! On native platforms every entry is a file in one of the directories of
+//! the game, which follow the conventions of every platform. The wasm build
+//! keeps them in the local storage of the browser instead, where there are no
+//! directories, through the functions provided by `assets/ghpages/pong.js`,
+//! which is also used to hand exported files to the browser as downloads.
+use std::{io, path::PathBuf};
+
+use pong::archive::{self, Entry};
+
+use crate::{replays, settings};
+
+/// Kind of data, kept in its own directory.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Dir {
+    /// Preferences of the player.
+    Config,
+    /// Progress, stats and crash reports.
+    Saves,
+    /// Traces of matches, like the ghosts of the personal bests.
+    Replays,
+    Screenshots,
+}
+
+impl Dir {
+    pub const ALL: [Dir; 4] = [Dir::Config, Dir::Saves, Dir::Replays, Dir::Screenshots];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Dir::Config => "config",
+            Dir::Saves => "saves",
+            Dir::Replays => "replays",
+            Dir::Screenshots => "screenshots",
+        }
+    }
+}
+
+/// Returns the directory where the data of kind `dir` is stored.
+///
+/// The configuration goes to `$XDG_CONFIG_HOME/pong` on Linux and the rest
+/// to `$XDG_DATA_HOME/pong`. Other platforms have a single directory per
+/// application.
+#[cfg(not(target_family = "wasm"))]
+pub fn dir_path(dir: Dir) -> Option<PathBuf> {
+    use std::env::var_os;
+
+    let base = if cfg!(target_os = "windows") {
+        PathBuf::from(var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(var_os("HOME")?).join("Library/Application Support")
+    } else {
+        let (var, default) = match dir {
+            Dir::Config => ("XDG_CONFIG_HOME", ".config"),
+            Dir::Saves | Dir::Replays | Dir::Screenshots => ("XDG_DATA_HOME", ".local/share"),
+        };
+        match var_os(var) {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(var_os("HOME")?).join(default),
+        }
+    };
+    let base = base.join("pong");
+    Some(match dir {
+        Dir::Config | Dir::Saves => base,
+        Dir::Replays => base.join("replays"),
+        Dir::Screenshots => base.join("screenshots"),
+    })
+}
+
+/// Returns the contents of the entry called `name` in `dir`, or `None` if it
+/// has never been saved.
+#[cfg(not(target_family = "wasm"))]
+pub fn load(dir: Dir, name: &str) -> Option<String> {
+    let path = dir_path(dir)?.join(name);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => Some(contents),
+        // Every entry was kept in the saves directory before there were
+        // others, so that is where older entries are found.
+        Err(err) if err.kind() == io::ErrorKind::NotFound && dir != Dir::Saves => {
+            load(Dir::Saves, name)
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => {
+            eprintln!("error: read {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+/// Replaces the contents of the entry called `name` in `dir`.
+///
+/// The contents are written to a temporary file first, which then replaces
+/// the entry, so a crash while saving leaves the previous contents intact.
+#[cfg(not(target_family = "wasm"))]
+pub fn save(dir: Dir, name: &str, contents: &str) -> io::Result<()> {
+    let dir = dir_path(dir).ok_or(io::ErrorKind::NotFound)?;
+    std::fs::create_dir_all(&dir)?;
+    let tmp = dir.join(format!("{name}.tmp"));
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(tmp, dir.join(name))
+}
+
+/// Returns where the data of kind `dir` is stored, to show it to the player.
+#[cfg(not(target_family = "wasm"))]
+pub fn location(dir: Dir) -> String {
+    dir_path(dir).map_or_else(|| "unavailable".to_owned(), |dir| dir.display().to_string())
+}
+
+/// Opens `dir` in the file manager of the system.
+#[cfg(not(target_family = "wasm"))]
+pub fn open(dir: Dir) -> io::Result<()> {
+    let dir = dir_path(dir).ok_or(io::ErrorKind::NotFound)?;
+    std::fs::create_dir_all(&dir)?;
+    let opener = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(opener).arg(&dir).spawn()?;
+    Ok(())
+}
+
+/// Deletes the entry called `name` in `dir`, if it exists.
+#[cfg(not(target_family = "wasm"))]
+pub fn remove(dir: Dir, name: &str) -> io::Result<()> {
+    let path = dir_path(dir).ok_or(io::ErrorKind::NotFound)?.join(name);
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the names of the entries in `dir`.
+#[cfg(not(target_family = "wasm"))]
+pub fn list(dir: Dir) -> Vec<String> {
+    let Some(path) = dir_path(dir) else {
+        return Vec::new();
+    };
+    let entries = match std::fs::read_dir(&path) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Vec::new(),
+        Err(err) => {
+            eprintln!("error: list {}: {err}", path.display());
+            return Vec::new();
+        }
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            // Other kinds of data can be kept in subdirectories.
+            if !entry.file_type().ok()?.is_file() {
+                return None;
+            }
+            entry.file_name().into_string().ok()
+        })
+        // Leftovers of interrupted saves are not entries.
+        .filter(|name| !name.ends_with(".tmp"))
+        .collect()
+}
+
+/// Returns the path of the file `name` in `dir`, creating `dir` if needed,
+/// for files that are not text.
+#[cfg(not(target_family = "wasm"))]
+pub fn path(dir: Dir, name: &str) -> io::Result<PathBuf> {
+    let dir = dir_path(dir).ok_or(io::ErrorKind::NotFound)?;
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(name))
+}
+
+/// Makes `contents` available to the player as a file called `name` and
+/// returns where it can be found.
+#[cfg(not(target_family = "wasm"))]
+pub fn export(name: &str, contents: &str) -> io::Result<String> {
+    save(Dir::Saves, name, contents)?;
+    Ok(dir_path(Dir::Saves)
+        .ok_or(io::ErrorKind::NotFound)?
+        .join(name)
+        .display()
+        .to_string())
+}
+
+/// Returns the contents of the file called `name` exported with [`export`],
+/// to import it back.
+#[cfg(not(target_family = "wasm"))]
+pub fn pick_import(name: &str) -> io::Result<Option<String>> {
+    load(Dir::Saves, name)
+        .map(Some)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{name} not found")))
+}
+
+/// Files are only picked in the browser.
+#[cfg(not(target_family = "wasm"))]
+pub fn take_import() -> Option<String> {
+    None
+}
+
+#[cfg(target_family = "wasm")]
+mod js {
+    extern "C" {
+        pub fn pong_storage_len(key: *const u8, key_len: usize) -> i32;
+        pub fn pong_storage_read(key: *const u8, key_len: usize, buf: *mut u8, buf_len: usize);
+        pub fn pong_storage_write(key: *const u8, key_len: usize, data: *const u8, data_len: usize);
+        pub fn pong_storage_remove(key: *const u8, key_len: usize);
+        pub fn pong_storage_keys_len() -> usize;
+        pub fn pong_storage_keys_read(buf: *mut u8, buf_len: usize);
+        pub fn pong_download(name: *const u8, name_len: usize, data: *const u8, data_len: usize);
+        pub fn pong_upload();
+        pub fn pong_upload_len() -> i32;
+        pub fn pong_upload_read(buf: *mut u8, buf_len: usize);
+    }
+}
+
+#[cfg(target_family = "wasm")]
+pub fn load(_dir: Dir, name: &str) -> Option<String> {
+    // SAFETY: The buffers are valid for the given lengths and the functions
+    // don't keep them.
+    unsafe {
+        let len = usize::try_from(js::pong_storage_len(name.as_ptr(), name.len())).ok()?;
+        let mut buf = vec![0; len];
+        js::pong_storage_read(name.as_ptr(), name.len(), buf.as_mut_ptr(), buf.len());
+        String::from_utf8(buf).ok()
+    }
+}
+
+#[cfg(target_family = "wasm")]
+pub fn save(_dir: Dir, name: &str, contents: &str) -> io::Result<()> {
+    // SAFETY: See `load`.
+    unsafe {
+        js::pong_storage_write(name.as_ptr(), name.len(), contents.as_ptr(), contents.len());
+    }
+    Ok(())
+}
+
+#[cfg(target_family = "wasm")]
+pub fn location(_dir: Dir) -> String {
+    "local storage of the browser".to_owned()
+}
+
+#[cfg(target_family = "wasm")]
+pub fn open(_dir: Dir) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(target_family = "wasm")]
+pub fn remove(_dir: Dir, name: &str) -> io::Result<()> {
+    // SAFETY: See `load`.
+    unsafe {
+        js::pong_storage_remove(name.as_ptr(), name.len());
+    }
+    Ok(())
+}
+
+/// The local storage has no directories, so every entry is listed.
+#[cfg(target_family = "wasm")]
+pub fn list(_dir: Dir) -> Vec<String> {
+    // SAFETY: See `load`.
+    let keys = unsafe {
+        let mut buf = vec![0; js::pong_storage_keys_len()];
+        js::pong_storage_keys_read(buf.as_mut_ptr(), buf.len());
+        String::from_utf8(buf).unwrap_or_default()
+    };
+    keys.lines().map(str::to_owned).collect()
+}
+
+/// The local storage only holds text.
+#[cfg(target_family = "wasm")]
+pub fn path(_dir: Dir, _name: &str) -> io::Result<PathBuf> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(target_family = "wasm")]
+pub fn export(name: &str, contents: &str) -> io::Result<String> {
+    // SAFETY: See `load`.
+    unsafe {
+        js::pong_download(name.as_ptr(), name.len(), contents.as_ptr(), contents.len());
+    }
+    Ok("downloads".to_owned())
+}
+
+/// Asks the player for a file to import. The browser hands it over later,
+/// through [`take_import`].
+#[cfg(target_family = "wasm")]
+pub fn pick_import(_name: &str) -> io::Result<Option<String>> {
+    // SAFETY: The function takes no arguments.
+    unsafe { js::pong_upload() };
+    Ok(None)
+}
+
+/// Returns the contents of the file picked to import, once the browser has
+/// read it.
+#[cfg(target_family = "wasm")]
+pub fn take_import() -> Option<String> {
+    // SAFETY: See `load`.
+    unsafe {
+        let len = usize::try_from(js::pong_upload_len()).ok()?;
+        let mut buf = vec![0; len];
+        js::pong_upload_read(buf.as_mut_ptr(), buf.len());
+        String::from_utf8(buf).ok()
+    }
+}
+
+/// Migrates the contents of an entry to the next version of its format.
+pub type Migration = fn(&str) -> String;
+
+/// Entry whose format has a version, so entries saved by older versions of
+/// the game can be migrated when they are loaded.
+///
+/// Versioned entries start with a `version N` line. Entries without it were
+/// saved before entries had versions and are version 1.
+pub struct Format {
+    pub dir: Dir,
+    pub name: &'static str,
+    /// Migrations from every version to the next one, starting at version 1.
+    pub migrations: &'static [Migration],
+}
+
+impl Format {
+    /// Returns the version entries are saved with.
+    pub fn version(&self) -> usize {
+        self.migrations.len() + 1
+    }
+
+    /// Returns the contents of the entry in the current version of its
+    /// format, without the version line, or `None` if it has never been
+    /// saved.
+    ///
+    /// Entries in an older version are migrated and saved again, and the
+    /// contents they had are kept in a backup next to them.
+    pub fn load(&self) -> Option<String> {
+        let contents = load(self.dir, self.name)?;
+        let (version, mut body) = match contents.split_once('\n') {
+            Some((first, rest)) => match first.strip_prefix("version ").map(str::parse) {
+                Some(Ok(version)) => (version, rest.to_owned()),
+                _ => (1, contents.clone()),
+            },
+            None => (1, contents.clone()),
+        };
+        if version > self.version() {
+            eprintln!(
+                "warning: {} was saved by a newer version of the game ({version})",
+                self.name
+            );
+            return Some(body);
+        }
+        if version == self.version() {
+            return Some(body);
+        }
+
+        let backup = format!("{}.v{version}.bak", self.name);
+        if let Err(err) = save(self.dir, &backup, &contents) {
+            // Without a backup, the entry is left as it is.
+            eprintln!("error: back up {}: {err}", self.name);
+            return None;
+        }
+        for migrate in &self.migrations[version.max(1) - 1..] {
+            body = migrate(&body);
+        }
+        if let Err(err) = self.save(&body) {
+            eprintln!("error: save migrated {}: {err}", self.name);
+        }
+        Some(body)
+    }
+
+    /// Replaces the contents of the entry, which are in the current version
+    /// of its format.
+    pub fn save(&self, contents: &str) -> io::Result<()> {
+        save(
+            self.dir,
+            self.name,
+            &format!("version {}\n{contents}", self.version()),
+        )
+    }
+}
+
+/// Migrates an entry saved before entries had versions, which only lacked
+/// the version line.
+pub fn unversioned(contents: &str) -> String {
+    contents.to_owned()
+}
+
+/// Returns the archive of every entry saved.
+///
+/// Screenshots are images, which the web version cannot keep, so they are
+/// left out, and so are the files exported by the game, like the archive
+/// itself.
+pub fn pack() -> Result<String, String> {
+    let mut entries = Vec::new();
+    for dir in Dir::ALL {
+        if dir == Dir::Screenshots {
+            continue;
+        }
+        // Kinds of data can share a location, like every kind on the web,
+        // and then listing one lists them all.
+        let shared = Dir::ALL
+            .into_iter()
+            .any(|other| other != dir && location(other) == location(dir));
+        for name in list(dir) {
+            if is_export(&name) || (shared && kind(&name) != dir) {
+                continue;
+            }
+            if let Some(contents) = load(dir, &name) {
+                entries.push((dir, name, contents));
+            }
+        }
+    }
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|(dir, name, contents)| Entry {
+            dir: dir.name(),
+            name,
+            contents,
+        })
+        .collect();
+    archive::pack(&entries)
+}
+
+/// Saves the entries of `archive`, replacing the ones with the same names,
+/// and returns how many there were.
+pub fn unpack(archive: &str) -> Result<usize, String> {
+    // The entries are checked before saving any, so broken archives change
+    // nothing.
+    let mut entries = Vec::new();
+    for entry in archive::unpack(archive)? {
+        let dir = Dir::ALL
+            .into_iter()
+            .find(|dir| dir.name() == entry.dir)
+            .ok_or_else(|| format!("unknown folder: {}", entry.dir))?;
+        entries.push((dir, entry));
+    }
+    for (dir, entry) in &entries {
+        save(*dir, entry.name, entry.contents)
+            .map_err(|err| format!("save {}: {err}", entry.name))?;
+    }
+    Ok(entries.len())
+}
+
+/// Returns the kind of data of the entry called `name`, for the locations
+/// shared by several kinds.
+fn kind(name: &str) -> Dir {
+    if name == settings::SETTINGS.name {
+        Dir::Config
+    } else if name.ends_with(replays::EXTENSION) {
+        Dir::Replays
+    } else {
+        Dir::Saves
+    }
+}
+
+/// Returns whether the entry called `name` is a file exported by the game.
+fn is_export(name: &str) -> bool {
+    name.starts_with("pong-")
+}
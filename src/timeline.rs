@@ -0,0 +1,183 @@
+//! Log of what happened during a match.
+//!
+//! Every serve, bounce and point is logged with the step it happened on,
+//! where the ball was and how fast it went. The stats of the match are
+//! worked out from the log, which can also be exported as JSON Lines, one
+//! entry per line, for analysis with other tools:
+//!
+//! ```text
+//! {"tick": 1, "secs": 0.01, "kind": "serve", "x": 400.0, "y": 300.0, "speed": 150.0, "dir": [1.000, -1.000]}
+//! {"tick": 410, "secs": 3.42, "kind": "racket_bounce", "x": 770.0, "y": 120.5, "speed": 184.2, "side": "right"}
+//! {"tick": 930, "secs": 7.75, "kind": "point", "x": 801.0, "y": 240.0, "speed": 201.7, "side": "left", "scores": [1, 0]}
+//! ```
+//!
+//! Positions are the center of the ball in court units. The kinds are
+//! `serve`, `wall_bounce`, `racket_bounce`, `target_hit`, `point`, `set`
+//! and `winner`. Serves have the direction of the ball, every other kind
+//! but the bounces off the walls and targets has the side it concerns, and
+//! points have the score after them, which starts over once a set is won.
+use std::fmt::Write;
+
+use macroquad::math::Vec2;
+use pong::{
+    entity::{Ball, Entity},
+    sim::{Event, Side, Simulation, TICK_RATE},
+};
+
+use crate::stats::side_name;
+
+/// What happened.
+#[derive(Clone, Copy)]
+pub enum Kind {
+    /// A ball was served in the given direction.
+    Serve {
+        dir: (f32, f32),
+    },
+    WallBounce,
+    /// The racket on the given side hit the ball.
+    RacketBounce(Side),
+    TargetHit,
+    /// The given side won a point, leaving the set at the given scores.
+    Point {
+        side: Side,
+        scores: (i32, i32),
+    },
+    SetWon(Side),
+    Winner(Side),
+}
+
+#[derive(Clone, Copy)]
+pub struct Entry {
+    /// Step of the match it happened on.
+    pub tick: u64,
+    pub kind: Kind,
+    /// Center of the ball.
+    pub pos: Vec2,
+    /// Speed of the ball, in units per second.
+    pub speed: f32,
+}
+
+#[derive(Clone)]
+pub struct Timeline {
+    pub entries: Vec<Entry>,
+    /// Center and speed of the ball seen in the previous frame. Scoring
+    /// serves a new ball, so that is the ball that went out.
+    last_ball: (Vec2, f32),
+    /// Whether a ball is about to be served.
+    serve_pending: bool,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            last_ball: (Vec2::ZERO, 0.),
+            serve_pending: true,
+        }
+    }
+}
+
+impl Timeline {
+    /// Logs `event`, which has just happened in `sim`, and returns the new
+    /// entry, if the event is logged.
+    pub fn record(&mut self, event: Event, sim: &Simulation) -> Option<Entry> {
+        let ball = first_ball(sim);
+        let (pos, speed) = ball.map_or(self.last_ball, |ball| (ball.center(), ball.speed));
+        let (kind, pos, speed) = match event {
+            Event::WallBounce => (Kind::WallBounce, pos, speed),
+            Event::RacketBounce => {
+                // The ball leaves the racket that hit it.
+                let side = match ball {
+                    Some(ball) if ball.dir.0 < 0. => Side::Right,
+                    _ => Side::Left,
+                };
+                (Kind::RacketBounce(side), pos, speed)
+            }
+            Event::TargetHit => (Kind::TargetHit, pos, speed),
+            Event::Point(side) => {
+                self.serve_pending = true;
+                let kind = Kind::Point {
+                    side,
+                    scores: sim.scores,
+                };
+                (kind, self.last_ball.0, self.last_ball.1)
+            }
+            Event::SetWon(side) => (Kind::SetWon(side), self.last_ball.0, self.last_ball.1),
+            Event::Winner(side) => (Kind::Winner(side), self.last_ball.0, self.last_ball.1),
+            Event::MatchPoint(_) => return None,
+        };
+        let entry = Entry {
+            tick: sim.tick,
+            kind,
+            pos,
+            speed,
+        };
+        self.entries.push(entry);
+        Some(entry)
+    }
+
+    /// Keeps track of the ball of `sim` after the events of a frame have
+    /// been recorded, logging the serves.
+    pub fn observe(&mut self, sim: &Simulation) {
+        let Some(ball) = first_ball(sim) else {
+            return;
+        };
+        if std::mem::take(&mut self.serve_pending) {
+            self.entries.push(Entry {
+                tick: sim.tick,
+                kind: Kind::Serve { dir: ball.dir },
+                pos: ball.center(),
+                speed: ball.speed,
+            });
+        }
+        self.last_ball = (ball.center(), ball.speed);
+    }
+
+    /// Returns the log as JSON Lines.
+    pub fn to_json_lines(&self) -> String {
+        let mut lines = String::new();
+        for entry in &self.entries {
+            let (name, details) = match entry.kind {
+                Kind::Serve { dir } => {
+                    ("serve", format!(r#", "dir": [{:.3}, {:.3}]"#, dir.0, dir.1))
+                }
+                Kind::WallBounce => ("wall_bounce", String::new()),
+                Kind::RacketBounce(side) => ("racket_bounce", side_field(side)),
+                Kind::TargetHit => ("target_hit", String::new()),
+                Kind::Point { side, scores } => (
+                    "point",
+                    format!(
+                        r#"{}, "scores": [{}, {}]"#,
+                        side_field(side),
+                        scores.0,
+                        scores.1
+                    ),
+                ),
+                Kind::SetWon(side) => ("set", side_field(side)),
+                Kind::Winner(side) => ("winner", side_field(side)),
+            };
+            writeln!(
+                lines,
+                r#"{{"tick": {}, "secs": {:.2}, "kind": "{name}", "x": {:.1}, "y": {:.1}, "speed": {:.1}{details}}}"#,
+                entry.tick,
+                entry.tick as f32 / TICK_RATE as f32,
+                entry.pos.x,
+                entry.pos.y,
+                entry.speed
+            )
+            .expect("format entry");
+        }
+        lines
+    }
+}
+
+fn first_ball(sim: &Simulation) -> Option<&Ball> {
+    sim.entities.iter().find_map(|entity| match entity {
+        Entity::Ball(ball) => Some(ball),
+        _ => None,
+    })
+}
+
+fn side_field(side: Side) -> String {
+    format!(r#", "side": "{}""#, side_name(side))
+}
@@ -0,0 +1,95 @@
+//! Headless round-robin tournament between the built-in bots.
+use std::cmp::Reverse;
+
+use pong::{
+    bot::{self, Observation, BOT_NAMES},
+    sim::{Controls, MatchState, Side, Simulation, TICK_RATE},
+};
+
+/// Matches that last longer than this are declared a draw.
+const MAX_MATCH_SECS: u32 = 600;
+
+#[derive(Default)]
+struct Standing {
+    wins: u32,
+    losses: u32,
+    draws: u32,
+    points_for: i32,
+    points_against: i32,
+}
+
+/// Plays every bot against every other bot on both sides of the court, once
+/// per seed, and prints the resulting ranking.
+pub fn run(seeds: u64) {
+    let mut standings: Vec<(&str, Standing)> = BOT_NAMES
+        .iter()
+        .map(|&name| (name, Standing::default()))
+        .collect();
+
+    for (left, left_name) in BOT_NAMES.iter().enumerate() {
+        for (right, right_name) in BOT_NAMES.iter().enumerate() {
+            if left == right {
+                continue;
+            }
+            for seed in 0..seeds {
+                let (winner, scores) = play(left_name, right_name, seed);
+                for (idx, side, points_for, points_against) in [
+                    (left, Side::Left, scores.0, scores.1),
+                    (right, Side::Right, scores.1, scores.0),
+                ] {
+                    let standing = &mut standings[idx].1;
+                    match winner {
+                        Some(winner) if winner == side => standing.wins += 1,
+                        Some(_) => standing.losses += 1,
+                        None => standing.draws += 1,
+                    }
+                    standing.points_for += points_for;
+                    standing.points_against += points_against;
+                }
+            }
+        }
+    }
+
+    standings.sort_by_key(|(_, s)| (Reverse(s.wins), Reverse(s.points_for - s.points_against)));
+
+    println!(
+        "{:>4}  {:<10} {:>5} {:>5} {:>5} {:>6} {:>6}",
+        "RANK", "BOT", "W", "L", "D", "PF", "PA"
+    );
+    for (rank, (name, s)) in standings.iter().enumerate() {
+        println!(
+            "{:>4}  {:<10} {:>5} {:>5} {:>5} {:>6} {:>6}",
+            rank + 1,
+            name,
+            s.wins,
+            s.losses,
+            s.draws,
+            s.points_for,
+            s.points_against
+        );
+    }
+}
+
+/// Plays a single match and returns its winner, if any, and final score.
+fn play(left: &str, right: &str, seed: u64) -> (Option<Side>, (i32, i32)) {
+    let mut left = bot::by_name(left).expect("known bot");
+    let mut right = bot::by_name(right).expect("known bot");
+
+    left.reset(bot::seed(seed, Side::Left));
+    right.reset(bot::seed(seed, Side::Right));
+    let mut sim = Simulation::new(seed);
+    for _ in 0..MAX_MATCH_SECS * TICK_RATE {
+        let controls = Controls {
+            left: left.act(&Observation::new(&sim, Side::Left)),
+            right: right.act(&Observation::new(&sim, Side::Right)),
+            ..Default::default()
+        };
+        sim.step(controls);
+        sim.clear_events();
+
+        if let MatchState::Finished(winner) = sim.state {
+            return (Some(winner), sim.scores);
+        }
+    }
+    (None, sim.scores)
+}
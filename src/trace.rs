@@ -0,0 +1,274 @@
+//! Recorded matches.
+//!
+//! The simulation is deterministic, so a match is fully described by its
+//! settings, its seed and the controls used on every step. Traces are stored
+//! as text:
+//!
+//! ```text
+//! pong-trace 3
+//! court classic
+//! players player predictor
+//! date 1700000000
+//! scores 5 3
+//! seed 1234
+//! 120 S S
+//! 30 U S
+//! ```
+//!
+//! The court is given as accepted by [`Court::parse`]. It may be followed by
+//! a `moving-walls AMPLITUDE,FREQUENCY` line if its walls move, a
+//! `physics NAME` line naming a physics preset other than the default one,
+//! a `ball SHAPE` line if the shape of the ball differs from the preset, a
+//! `slow-ball left|right|both` line if players have the slow-ball handicap,
+//! a `lineup coop` line for co-op matches and a `rules RULES` line, as
+//! accepted by [`Rules::parse`], for matches not played by the classic
+//! rules. Older traces may have a `service table-tennis` line instead for
+//! matches served as in table tennis. Version 3 traces may describe the
+//! match for listing it, with a `players LEFT RIGHT` line naming who played
+//! on every side, a `date SECS` line with when it was played, in seconds
+//! since the Unix epoch, and a `scores LEFT RIGHT` line with its final
+//! score. Version 1 traces have no court line and are played on the default
+//! court. Every line after the seed holds
+//! a number of steps followed by the motion of the left and right rackets
+//! during them: `S` (stop), `U` (up) or `D` (down). Co-op traces add the
+//! motion of the partner racket at the end.
+use std::{fmt, str::FromStr};
+
+use crate::{
+    court::{Court, WallMotion},
+    physics::{BallShape, Physics},
+    rules::Rules,
+    sim::{Controls, Lineup, Motion, Service, TICK_SECS},
+};
+
+const MAGIC_V1: &str = "pong-trace 1";
+const MAGIC_V2: &str = "pong-trace 2";
+const MAGIC: &str = "pong-trace 3";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trace {
+    pub court: Court,
+    pub physics: Physics,
+    pub lineup: Lineup,
+    pub rules: Rules,
+    pub seed: u64,
+    pub meta: Meta,
+    controls: Vec<Controls>,
+}
+
+/// Description of a recorded match, to list it. It plays no part in the
+/// replay.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Meta {
+    /// Names of who played on the left and right sides, without spaces.
+    pub players: Option<(String, String)>,
+    /// When the match was played, in seconds since the Unix epoch.
+    pub date: Option<u64>,
+    /// Final score of the match.
+    pub scores: Option<(i32, i32)>,
+}
+
+impl Trace {
+    pub fn new(court: Court, physics: Physics, lineup: Lineup, rules: Rules, seed: u64) -> Self {
+        Self {
+            court,
+            physics,
+            lineup,
+            rules,
+            seed,
+            meta: Meta::default(),
+            controls: Vec::new(),
+        }
+    }
+
+    /// Appends the controls used during one step.
+    pub fn push(&mut self, controls: Controls) {
+        self.controls.push(controls);
+    }
+
+    /// Returns the controls used on every step.
+    pub fn controls(&self) -> &[Controls] {
+        &self.controls
+    }
+
+    /// Returns how long the match lasted, in seconds.
+    pub fn duration_secs(&self) -> f32 {
+        self.controls.len() as f32 * TICK_SECS
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{MAGIC}")?;
+        writeln!(f, "court {}", self.court.spec())?;
+        if let Some(motion) = self.court.wall_motion {
+            writeln!(f, "moving-walls {motion}")?;
+        }
+        // Custom physics cannot be described and are saved as the default.
+        let preset = self.physics.preset_name().unwrap_or("arcade");
+        if preset != "arcade" {
+            writeln!(f, "physics {preset}")?;
+        }
+        let preset = Physics::parse(preset).unwrap_or_default();
+        if self.physics.ball_shape != preset.ball_shape {
+            writeln!(f, "ball {}", self.physics.ball_shape)?;
+        }
+        if let Some(sides) = self.physics.slow_ball_spec() {
+            writeln!(f, "slow-ball {sides}")?;
+        }
+        if self.lineup != Lineup::Single {
+            writeln!(f, "lineup {}", self.lineup)?;
+        }
+        if self.rules != Rules::CLASSIC {
+            writeln!(f, "rules {}", self.rules.spec())?;
+        }
+        if let Some((left, right)) = &self.meta.players {
+            writeln!(f, "players {left} {right}")?;
+        }
+        if let Some(date) = self.meta.date {
+            writeln!(f, "date {date}")?;
+        }
+        if let Some((left, right)) = self.meta.scores {
+            writeln!(f, "scores {left} {right}")?;
+        }
+        writeln!(f, "seed {}", self.seed)?;
+
+        let mut iter = self.controls.iter().peekable();
+        while let Some(controls) = iter.next() {
+            let mut count = 1;
+            while iter.next_if_eq(&controls).is_some() {
+                count += 1;
+            }
+            write!(
+                f,
+                "{count} {} {}",
+                motion_char(controls.left),
+                motion_char(controls.right)
+            )?;
+            if self.lineup == Lineup::Coop {
+                write!(f, " {}", motion_char(controls.partner))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when a trace cannot be parsed.
+#[derive(Debug)]
+pub struct ParseTraceError {
+    line: usize,
+    msg: &'static str,
+}
+
+impl fmt::Display for ParseTraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl std::error::Error for ParseTraceError {}
+
+impl FromStr for Trace {
+    type Err = ParseTraceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |line, msg| ParseTraceError { line, msg };
+
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        match lines.next() {
+            Some((_, MAGIC_V1 | MAGIC_V2 | MAGIC)) => {}
+            _ => return Err(err(1, "missing header")),
+        }
+
+        // The settings of the match come before the seed.
+        let mut court = Court::DEFAULT;
+        let mut physics = Physics::default();
+        let mut lineup = Lineup::Single;
+        let mut rules = Rules::CLASSIC;
+        let mut meta = Meta::default();
+        let seed = loop {
+            let (n, line) = lines.next().ok_or(err(2, "missing seed"))?;
+            let (key, value) = line.split_once(' ').ok_or(err(n, "invalid setting"))?;
+            match key {
+                "court" => court = Court::parse(value).ok_or(err(n, "invalid court"))?,
+                "moving-walls" => {
+                    let motion = WallMotion::parse(value).ok_or(err(n, "invalid wall motion"))?;
+                    court.wall_motion = Some(motion);
+                }
+                "physics" => physics = Physics::parse(value).ok_or(err(n, "invalid physics"))?,
+                "ball" => {
+                    physics.ball_shape = BallShape::parse(value).ok_or(err(n, "invalid ball"))?;
+                }
+                "slow-ball" => {
+                    physics.slow_ball =
+                        Physics::parse_slow_ball(value).ok_or(err(n, "invalid slow ball"))?;
+                }
+                "lineup" => lineup = Lineup::parse(value).ok_or(err(n, "invalid lineup"))?,
+                "rules" => rules = Rules::parse(value).ok_or(err(n, "invalid rules"))?,
+                "service" => {
+                    rules.service = Service::parse(value).ok_or(err(n, "invalid service"))?;
+                }
+                "players" => {
+                    let (left, right) = value.split_once(' ').ok_or(err(n, "invalid players"))?;
+                    meta.players = Some((left.to_owned(), right.to_owned()));
+                }
+                "date" => meta.date = Some(value.parse().map_err(|_| err(n, "invalid date"))?),
+                "scores" => {
+                    let scores = value
+                        .split_once(' ')
+                        .and_then(|(left, right)| Some((left.parse().ok()?, right.parse().ok()?)));
+                    meta.scores = Some(scores.ok_or(err(n, "invalid scores"))?);
+                }
+                "seed" => break value.parse().map_err(|_| err(n, "invalid seed"))?,
+                _ => return Err(err(n, "unknown setting")),
+            }
+        };
+
+        let mut trace = Trace::new(court, physics, lineup, rules, seed);
+        trace.meta = meta;
+        for (n, line) in lines {
+            let mut fields = line.split_whitespace();
+            let (Some(count), Some(left), Some(right)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(err(n, "expected count and two motions"));
+            };
+            let partner = match (lineup == Lineup::Coop, fields.next()) {
+                (false, None) => "S",
+                (true, Some(partner)) => partner,
+                (false, Some(_)) => return Err(err(n, "expected count and two motions")),
+                (true, None) => return Err(err(n, "expected count and three motions")),
+            };
+            if fields.next().is_some() {
+                return Err(err(n, "too many motions"));
+            }
+            let count: usize = count.parse().map_err(|_| err(n, "invalid count"))?;
+            let controls = Controls {
+                left: parse_motion(left).ok_or(err(n, "invalid motion"))?,
+                right: parse_motion(right).ok_or(err(n, "invalid motion"))?,
+                partner: parse_motion(partner).ok_or(err(n, "invalid motion"))?,
+            };
+            trace.controls.extend(std::iter::repeat_n(controls, count));
+        }
+        Ok(trace)
+    }
+}
+
+pub(crate) fn motion_char(motion: Motion) -> char {
+    match motion {
+        Motion::Stop => 'S',
+        Motion::Up => 'U',
+        Motion::Down => 'D',
+    }
+}
+
+pub(crate) fn parse_motion(s: &str) -> Option<Motion> {
+    match s {
+        "S" => Some(Motion::Stop),
+        "U" => Some(Motion::Up),
+        "D" => Some(Motion::Down),
+        _ => None,
+    }
+}
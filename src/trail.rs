@@ -0,0 +1,97 @@
+//! Afterimages of the rackets.
+//!
+//! Fast rackets leave short-lived translucent copies behind, which make
+//! their motion easier to follow on displays with low refresh rates.
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+use pong::{
+    entity::{Entity, Object},
+    sim::Side,
+};
+
+use crate::{
+    draw::{FOREGROUND_COLOR, LEFT_COLOR, RIGHT_COLOR},
+    settings::TrailStyle,
+};
+
+/// Time the copies last.
+const TRAIL_SECS: f32 = 0.12;
+/// Opacity of the newest copies.
+const TRAIL_ALPHA: f32 = 0.35;
+/// Distance a copy must be from the racket to be drawn, so slow rackets
+/// leave no trail.
+const MIN_GAP: f32 = 4.;
+
+/// Where the rackets were in a frame.
+struct Sample {
+    /// Time since the frame, in seconds.
+    age: f32,
+    rackets: Vec<(Side, Rect)>,
+}
+
+#[derive(Default)]
+pub struct Trail {
+    /// Latest samples, the newest last.
+    samples: VecDeque<Sample>,
+}
+
+impl Trail {
+    /// Takes a sample of the rackets in `entities`, `dt` seconds after the
+    /// previous one.
+    pub fn update(&mut self, entities: &[Entity], dt: f32) {
+        for sample in &mut self.samples {
+            sample.age += dt;
+        }
+        while self
+            .samples
+            .front()
+            .is_some_and(|sample| sample.age > TRAIL_SECS)
+        {
+            self.samples.pop_front();
+        }
+        let rackets = entities
+            .iter()
+            .filter_map(|entity| match entity {
+                Entity::Racket(racket) => Some((racket.side, racket.rect())),
+                _ => None,
+            })
+            .collect();
+        self.samples.push_back(Sample { age: 0., rackets });
+    }
+
+    /// Forgets the rackets, like after they are placed for a new serve.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Draws the copies of the rackets that are far enough from them in
+    /// `style`.
+    pub fn draw(&self, style: TrailStyle) {
+        let Some(latest) = self.samples.back() else {
+            return;
+        };
+        if style == TrailStyle::Off {
+            return;
+        }
+        for sample in &self.samples {
+            // The rackets of every sample are in the same order, unless the
+            // lineup changed.
+            if sample.rackets.len() != latest.rackets.len() {
+                continue;
+            }
+            let alpha = TRAIL_ALPHA * (1. - sample.age / TRAIL_SECS);
+            for (&(side, rect), &(_, now)) in sample.rackets.iter().zip(&latest.rackets) {
+                if (rect.y - now.y).abs() < MIN_GAP {
+                    continue;
+                }
+                let color = match (style, side) {
+                    (TrailStyle::Off | TrailStyle::White, _) => FOREGROUND_COLOR,
+                    (TrailStyle::Players, Side::Left) => LEFT_COLOR,
+                    (TrailStyle::Players, Side::Right) => RIGHT_COLOR,
+                };
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color { a: alpha, ..color });
+            }
+        }
+    }
+}
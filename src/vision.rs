@@ -0,0 +1,143 @@
+//! Simulation of color vision deficiencies.
+//!
+//! The colors of the game are shown as players with each deficiency see
+//! them, so they can check that the colors that matter remain
+//! distinguishable. The simulation uses the matrices of Machado, Oliveira and
+//! Fernandes (2009) for a full deficiency, which work on linear RGB.
+use macroquad::prelude::*;
+
+use crate::{
+    draw::{
+        BACKGROUND_COLOR, FOREGROUND_COLOR, HEATMAP_BALL_COLOR, HEATMAP_POINT_COLOR, LEFT_COLOR,
+        RIGHT_COLOR, WALL_COLOR,
+    },
+    speedrun::{AHEAD_COLOR, BEHIND_COLOR},
+};
+
+/// Colors of the game, by what they are used for. Colors used together are
+/// shown side by side.
+pub const PALETTE: [(&str, &[Color]); 8] = [
+    ("COURT", &[BACKGROUND_COLOR]),
+    ("BALL", &[FOREGROUND_COLOR]),
+    ("WALLS", &[WALL_COLOR]),
+    ("LEFT", &[LEFT_COLOR]),
+    ("RIGHT", &[RIGHT_COLOR]),
+    ("AHEAD", &[AHEAD_COLOR]),
+    ("BEHIND", &[BEHIND_COLOR]),
+    ("HEATMAP", &[HEATMAP_BALL_COLOR, HEATMAP_POINT_COLOR]),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Vision {
+    Normal,
+    /// No red cones.
+    Protanopia,
+    /// No green cones.
+    Deuteranopia,
+    /// No blue cones.
+    Tritanopia,
+}
+
+impl Vision {
+    pub const ALL: [Vision; 4] = [
+        Vision::Normal,
+        Vision::Protanopia,
+        Vision::Deuteranopia,
+        Vision::Tritanopia,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Vision::Normal => "normal",
+            Vision::Protanopia => "protanopia",
+            Vision::Deuteranopia => "deuteranopia",
+            Vision::Tritanopia => "tritanopia",
+        }
+    }
+
+    fn matrix(self) -> Option<[[f32; 3]; 3]> {
+        let matrix = match self {
+            Vision::Normal => return None,
+            Vision::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            Vision::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            Vision::Tritanopia => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+        };
+        Some(matrix)
+    }
+
+    /// Returns `color` as seen with this vision.
+    pub fn simulate(self, color: Color) -> Color {
+        let Some(matrix) = self.matrix() else {
+            return color;
+        };
+        let linear = [color.r, color.g, color.b].map(to_linear);
+        let [r, g, b] = matrix.map(|row| {
+            let value = row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2];
+            to_srgb(value.clamp(0., 1.))
+        });
+        Color::new(r, g, b, color.a)
+    }
+}
+
+/// Draws the swatches of [`PALETTE`] as seen with `vision`, centered at
+/// (`x`, `y`) and `spacing` apart.
+pub fn draw_palette(vision: Vision, x: f32, y: f32, spacing: f32) {
+    const SWATCH_SIZE: (f32, f32) = (40., 24.);
+    const NAME_SIZE: u16 = 16;
+
+    let left = x - spacing * (PALETTE.len() - 1) as f32 * 0.5;
+    for (i, &(name, colors)) in PALETTE.iter().enumerate() {
+        let center = left + i as f32 * spacing;
+        let (x, y) = (center - SWATCH_SIZE.0 * 0.5, y - SWATCH_SIZE.1 * 0.5);
+        // Translucent colors are shown over the court, as in the game.
+        draw_rectangle(
+            x,
+            y,
+            SWATCH_SIZE.0,
+            SWATCH_SIZE.1,
+            vision.simulate(BACKGROUND_COLOR),
+        );
+        let width = SWATCH_SIZE.0 / colors.len() as f32;
+        for (j, &color) in colors.iter().enumerate() {
+            let x = x + j as f32 * width;
+            draw_rectangle(x, y, width, SWATCH_SIZE.1, vision.simulate(color));
+        }
+        draw_rectangle_lines(x, y, SWATCH_SIZE.0, SWATCH_SIZE.1, 1., FOREGROUND_COLOR);
+        let dims = measure_text(name, None, NAME_SIZE, 1.);
+        draw_text(
+            name,
+            center - dims.width * 0.5,
+            y + SWATCH_SIZE.1 + 16.,
+            NAME_SIZE as f32,
+            FOREGROUND_COLOR,
+        );
+    }
+}
+
+fn to_linear(c: f32) -> f32 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
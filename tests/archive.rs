@@ -0,0 +1,37 @@
+//! Data archive tests.
+use pong::archive::{self, Entry};
+
+#[test]
+fn archive_round_trip() {
+    let entries = [
+        Entry {
+            dir: "config",
+            name: "settings.txt",
+            contents: "version 1\ncrt on\n",
+        },
+        Entry {
+            dir: "saves",
+            name: "history.csv",
+            contents: "",
+        },
+        Entry {
+            dir: "replays",
+            name: "ghost-long court.trace",
+            contents: "pong-trace 1\nentry saves 3 fake\n",
+        },
+    ];
+    let packed = archive::pack(&entries).expect("pack archive");
+    assert_eq!(archive::unpack(&packed).expect("unpack archive"), entries);
+
+    // Names must stay in the folder of their kind of data.
+    for name in ["../settings.txt", ".hidden", "two\nlines", ""] {
+        let entry = Entry {
+            dir: "saves",
+            name,
+            contents: "",
+        };
+        assert!(archive::pack(&[entry]).is_err());
+    }
+    assert!(archive::unpack("pong-data 2\nentry saves 4 a/b\nlost").is_err());
+    assert!(archive::unpack("pong-data 2\nentry saves 10 short\nlost").is_err());
+}
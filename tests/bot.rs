@@ -0,0 +1,42 @@
+//! Bot tests.
+use pong::bot::{Adaptive, Observation, PongBot};
+
+/// Returns an observation of the ball approaching the middle of the court
+/// with the score at `score` to `opponent_score`.
+fn observation(score: i32, opponent_score: i32) -> Observation {
+    Observation {
+        court_width: 800.,
+        court_height: 600.,
+        racket_y: 300.,
+        opponent_y: 300.,
+        ball_x: 200.,
+        ball_y: 150.,
+        ball_dir_x: -1.,
+        ball_speed: 300.,
+        score,
+        opponent_score,
+        second_opponent_y: 300.,
+        ..Observation::default()
+    }
+}
+
+#[test]
+fn adaptive_reset_forgets_the_last_match() {
+    let mut played = Adaptive::new();
+    // Winning by a wide margin makes the bot play worse.
+    for _ in 0..600 {
+        played.act(&observation(5, 0));
+    }
+    let mut fresh = Adaptive::new();
+    fresh.act(&observation(0, 0));
+    let level = |bot: &Adaptive| bot.telemetry().unwrap().skill.unwrap();
+    assert!(level(&played) < level(&fresh));
+
+    played.reset(0);
+    let mut fresh = Adaptive::new();
+    for _ in 0..60 {
+        let obs = observation(0, 0);
+        assert_eq!(played.act(&obs), fresh.act(&obs));
+        assert_eq!(played.telemetry(), fresh.telemetry());
+    }
+}
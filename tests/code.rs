@@ -0,0 +1,82 @@
+//! Match code tests.
+use pong::{
+    code::{MatchCode, SaveState},
+    court::{Court, WallMotion},
+    physics::{BallShape, Physics},
+    rules::Rules,
+    sim::Lineup,
+};
+
+#[test]
+fn code_round_trip() {
+    let mut court = Court::parse("garden").unwrap();
+    court.wall_motion = Some(WallMotion {
+        amplitude: 40.,
+        frequency: 0.5,
+    });
+    let code = MatchCode {
+        court,
+        physics: Physics {
+            ball_shape: BallShape::Round,
+            ..Physics::ARCADE
+        },
+        lineup: Lineup::Coop,
+        rules: Rules::TABLE_TENNIS_11,
+        seed: u64::MAX,
+    };
+    let text = code.to_string();
+    assert_eq!(text, "garden-round-coop-tt11-w40,0.5-3w5e11264sgsf");
+    assert_eq!(text.parse::<MatchCode>(), Ok(code));
+}
+
+#[test]
+fn slow_ball_code_round_trip() {
+    let code = MatchCode {
+        court: Court::DEFAULT,
+        physics: Physics {
+            slow_ball: (true, false),
+            ..Physics::REALISTIC
+        },
+        lineup: Lineup::Single,
+        rules: Rules::CLASSIC,
+        seed: 7,
+    };
+    let text = code.to_string();
+    assert_eq!(text, "classic-realistic-slowleft-7");
+    assert_eq!(text.parse::<MatchCode>(), Ok(code));
+}
+
+#[test]
+fn default_code() {
+    let code: MatchCode = "classic-0".parse().unwrap();
+    assert_eq!(code.court, Court::DEFAULT);
+    assert_eq!(code.physics, Physics::default());
+    assert_eq!(code.lineup, Lineup::Single);
+    assert_eq!(code.rules, Rules::CLASSIC);
+    assert_eq!(code.seed, 0);
+}
+
+#[test]
+fn invalid_code() {
+    assert!("classic-zzzzzzzzzzzzzzzzz".parse::<MatchCode>().is_err());
+    assert!("classic-bogus-1".parse::<MatchCode>().is_err());
+    assert!("1".parse::<MatchCode>().is_err());
+}
+
+#[test]
+fn multi_set_save_state_round_trip() {
+    let save = SaveState {
+        code: MatchCode {
+            court: Court::DEFAULT,
+            physics: Physics::default(),
+            lineup: Lineup::Single,
+            rules: Rules::TABLE_TENNIS_11,
+            seed: 42,
+        },
+        scores: (7, 4),
+        sets: (1, 1),
+    };
+    // The save-state is read back from the rest of the crash report.
+    let report = format!("panic boom at src/main.rs:1:1\n{save}tick 1200\n");
+    assert_eq!(SaveState::parse(&report), Some(save));
+}
@@ -0,0 +1,95 @@
+//! Golden-trace regression tests.
+//!
+//! Every trace in `tests/traces` is replayed through the simulation and the
+//! resulting events are compared against `tests/golden/<name>.golden`. Run
+//! with `UPDATE_GOLDEN=1` to regenerate the golden files after an intended
+//! change in the game rules.
+use std::{fmt::Write, fs, path::Path};
+
+use pong::{
+    sim::Simulation,
+    trace::{Meta, Trace},
+};
+
+/// Replays `trace` and returns a log of the events of every step and the
+/// final score.
+fn replay(trace: &Trace) -> String {
+    let mut sim = Simulation::with_rules(trace.court.clone(), trace.physics, trace.seed);
+    sim.lineup = trace.lineup;
+    sim.rules = trace.rules;
+    sim.reset(trace.seed);
+    let mut log = String::new();
+    for &controls in trace.controls() {
+        sim.step(controls);
+        for event in sim.events() {
+            writeln!(log, "{} {event:?}", sim.tick).unwrap();
+        }
+        sim.clear_events();
+    }
+    writeln!(log, "final {} {}", sim.scores.0, sim.scores.1).unwrap();
+    log
+}
+
+#[test]
+fn golden_traces() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+
+    let mut paths: Vec<_> = fs::read_dir(root.join("traces"))
+        .expect("read traces directory")
+        .map(|entry| entry.expect("read traces directory").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "trace"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no traces found");
+
+    let mut failures = Vec::new();
+    for path in paths {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let trace: Trace = fs::read_to_string(&path)
+            .expect("read trace")
+            .parse()
+            .unwrap_or_else(|err| panic!("parse {name}: {err}"));
+        let log = replay(&trace);
+
+        let golden_path = root.join("golden").join(format!("{name}.golden"));
+        if update {
+            fs::write(&golden_path, &log).expect("write golden file");
+            continue;
+        }
+        let golden = fs::read_to_string(&golden_path).unwrap_or_default();
+        if log != golden {
+            failures.push(name);
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "replays differ from their golden files: {}",
+        failures.join(", ")
+    );
+}
+
+#[test]
+fn trace_round_trip() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/traces/tracker_vs_defender.trace");
+    let text = fs::read_to_string(path).expect("read trace");
+    let trace: Trace = text.parse().expect("parse trace");
+    assert_eq!(trace.to_string(), text);
+}
+
+#[test]
+fn trace_meta_round_trip() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/traces/idle.trace");
+    let mut trace: Trace = fs::read_to_string(path)
+        .expect("read trace")
+        .parse()
+        .expect("parse trace");
+    trace.meta = Meta {
+        players: Some(("player".to_owned(), "tracker".to_owned())),
+        date: Some(1_760_000_000),
+        scores: Some((11, 7)),
+    };
+    let parsed: Trace = trace.to_string().parse().expect("parse trace");
+    assert_eq!(parsed.meta, trace.meta);
+    assert_eq!(parsed.to_string(), trace.to_string());
+}
@@ -0,0 +1,52 @@
+//! Input script tests.
+use pong::{
+    runner::Runner,
+    script::InputScript,
+    sim::{Controls, Motion, Simulation, TICK_SECS},
+};
+
+const SCRIPT: &str = "pong-inputs 1
+# Frame-perfect taps on the left.
+0 S S
+120 U S
+121 S S
+300 D U
+600 S S D
+";
+
+#[test]
+fn script_round_trip() {
+    let script: InputScript = SCRIPT.parse().expect("parse script");
+    let text = script.to_string();
+    assert_eq!(
+        text,
+        SCRIPT.replace("# Frame-perfect taps on the left.\n", "")
+    );
+    assert_eq!(text.parse::<InputScript>().expect("parse script"), script);
+    assert!("pong-inputs 1\n10 S S\n10 U S\n"
+        .parse::<InputScript>()
+        .is_err());
+}
+
+#[test]
+fn script_controls() {
+    let script: InputScript = SCRIPT.parse().expect("parse script");
+    assert_eq!(script.controls_at(119), Controls::default());
+    assert_eq!(script.controls_at(120).left, Motion::Up);
+    assert_eq!(script.controls_at(121).left, Motion::Stop);
+    assert_eq!(script.controls_at(10_000).partner, Motion::Down);
+}
+
+#[test]
+fn scripted_inputs_are_recorded() {
+    let script: InputScript = SCRIPT.parse().expect("parse script");
+    let mut runner = Runner::inline(Simulation::new(1));
+    runner.play_script(Some(script.clone()));
+    runner.record_inputs();
+    let mut events = Vec::new();
+    for _ in 0..700 {
+        runner.update(TICK_SECS, &mut events);
+    }
+    let recorded = runner.take_inputs().expect("recorded inputs");
+    assert_eq!(recorded.to_string(), script.to_string());
+}